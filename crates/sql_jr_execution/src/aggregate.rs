@@ -0,0 +1,365 @@
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+};
+
+use sql_jr_parser::{
+    expression::{AggregateArg, AggregateFunction, BinaryOperator, ColumnRef, Expression},
+    value::Value,
+};
+
+use crate::{
+    error::{ExecutionError, ExecutionResult},
+    evaluation::{as_float, compare_values, evaluate, evaluate_binary, RowContext},
+    scope::Scope,
+    select::Executor,
+    table::Row,
+};
+
+/// Whether `expression` calls an aggregate function
+pub(crate) fn contains_aggregate(expression: &Expression) -> bool {
+    let mut aggregates = Vec::new();
+    collect_aggregates(expression, &mut aggregates);
+    !aggregates.is_empty()
+}
+
+/// Add the aggregate calls of `expression` to `aggregates`, a call already there isn't
+/// added again so it is only computed once
+pub(crate) fn collect_aggregates<'a>(
+    expression: &'a Expression,
+    aggregates: &mut Vec<&'a Expression>,
+) {
+    match expression {
+        // the aggregates of a subquery are computed over its own rows
+        Expression::Column(_)
+        | Expression::Literal(_)
+        | Expression::Subquery { .. }
+        | Expression::Exists { .. } => {}
+        Expression::Unary { expr, .. }
+        | Expression::InSubquery { expr, .. }
+        | Expression::IsNull { expr, .. } => collect_aggregates(expr, aggregates),
+        Expression::Binary { left, right, .. } => {
+            collect_aggregates(left, aggregates);
+            collect_aggregates(right, aggregates);
+        }
+        Expression::Case { .. } | Expression::Conditional { .. } => {
+            for operand in expression.conditional_operands() {
+                collect_aggregates(operand, aggregates);
+            }
+        }
+        Expression::Aggregate { .. } => {
+            if !aggregates.contains(&expression) {
+                aggregates.push(expression);
+            }
+        }
+        // a window function is computed from the rows or groups, so over the values
+        // of the aggregates for each group
+        Expression::Window {
+            function, window, ..
+        } => {
+            for expr in function.args().into_iter().chain(window.expressions()) {
+                collect_aggregates(expr, aggregates);
+            }
+        }
+    }
+}
+
+/// The first column `expression` reads that is neither grouped by nor inside an
+/// aggregate call, which has no single value for a group
+pub(crate) fn ungrouped_column<'a>(
+    expression: &'a Expression,
+    group_by: &[Expression],
+    scope: &Scope,
+) -> Option<&'a ColumnRef> {
+    if group_by.contains(expression) {
+        return None;
+    }
+    match expression {
+        // `city` and `Person.city` are the same column
+        Expression::Column(column) => {
+            // a column of an enclosing query has a single value for the whole subquery
+            let index = scope.resolve(column).ok()?;
+            let grouped = group_by.iter().any(|grouped| {
+                matches!(grouped, Expression::Column(grouped) if scope.resolve(grouped) == Ok(index))
+            });
+            (!grouped).then_some(column)
+        }
+        Expression::Literal(_)
+        | Expression::Aggregate { .. }
+        | Expression::Subquery { .. }
+        | Expression::Exists { .. } => None,
+        Expression::Unary { expr, .. }
+        | Expression::InSubquery { expr, .. }
+        | Expression::IsNull { expr, .. } => ungrouped_column(expr, group_by, scope),
+        Expression::Binary { left, right, .. } => ungrouped_column(left, group_by, scope)
+            .or_else(|| ungrouped_column(right, group_by, scope)),
+        Expression::Case { .. } | Expression::Conditional { .. } => expression
+            .conditional_operands()
+            .into_iter()
+            .find_map(|operand| ungrouped_column(operand, group_by, scope)),
+        Expression::Window {
+            function, window, ..
+        } => function
+            .args()
+            .into_iter()
+            .chain(window.expressions())
+            .find_map(|expr| ungrouped_column(expr, group_by, scope)),
+    }
+}
+
+/// The running state of one aggregate call over the rows of a group
+#[derive(Debug)]
+pub(crate) struct Accumulator<'a> {
+    function: AggregateFunction,
+    arg: &'a AggregateArg,
+    // the values aggregated so far, only kept for DISTINCT
+    seen: Option<HashSet<Value>>,
+    count: i64,
+    // the sum for SUM and AVG, the smallest or largest value for MIN and MAX
+    value: Value,
+}
+
+impl<'a> Accumulator<'a> {
+    /// Start computing `aggregate`, which must be an aggregate call
+    pub fn new(aggregate: &'a Expression) -> Self {
+        let Expression::Aggregate {
+            function,
+            distinct,
+            arg,
+            ..
+        } = aggregate
+        else {
+            unreachable!("only aggregate calls are accumulated");
+        };
+        Self::from_parts(*function, *distinct, arg)
+    }
+
+    /// Start computing `function` over the values of `arg`
+    pub fn from_parts(function: AggregateFunction, distinct: bool, arg: &'a AggregateArg) -> Self {
+        Self {
+            function,
+            arg,
+            seen: distinct.then(HashSet::new),
+            count: 0,
+            value: Value::Null,
+        }
+    }
+
+    /// Add the row of `context` to the aggregate
+    pub fn update(&mut self, context: &RowContext) -> ExecutionResult<()> {
+        let value = match self.arg {
+            AggregateArg::Wildcard => {
+                self.count += 1;
+                return Ok(());
+            }
+            AggregateArg::Expression(expr) => evaluate(expr, context)?,
+        };
+        if value == Value::Null {
+            return Ok(());
+        }
+        if let Some(seen) = &mut self.seen {
+            if !seen.insert(value.clone()) {
+                return Ok(());
+            }
+        }
+        self.count += 1;
+
+        match self.function {
+            AggregateFunction::Count => {}
+            AggregateFunction::Sum | AggregateFunction::Avg => {
+                if !matches!(value, Value::Int(_) | Value::Float(_)) {
+                    return Err(ExecutionError::InvalidAggregate {
+                        function: self.function,
+                        value,
+                    });
+                }
+                self.value = match self.value.clone() {
+                    Value::Null => value,
+                    sum => evaluate_binary(BinaryOperator::Plus, sum, value)?,
+                };
+            }
+            AggregateFunction::Min | AggregateFunction::Max => {
+                let keep = if self.function == AggregateFunction::Min {
+                    Ordering::Less
+                } else {
+                    Ordering::Greater
+                };
+                if self.value == Value::Null || compare_values(&value, &self.value) == Some(keep) {
+                    self.value = value;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The value of the aggregate over the rows added, NULL when there were none
+    /// except for COUNT
+    pub fn finish(self) -> Value {
+        match self.function {
+            AggregateFunction::Count => Value::Int(self.count),
+            AggregateFunction::Avg if self.count == 0 => Value::Null,
+            AggregateFunction::Avg => Value::Float(as_float(&self.value) / self.count as f64),
+            _ => self.value,
+        }
+    }
+}
+
+/// One of the rows of a group, with the values of the aggregate calls over the group
+pub(crate) type Group<'a> = (Row, Vec<(&'a Expression, Value)>);
+
+/// Group `rows` by the values of the `group_by` expressions and compute the
+/// `aggregates` over each group. Every group comes with one of its rows, to evaluate
+/// the grouped columns on. Without GROUP BY all the rows are a single group, even
+/// when there are none
+pub(crate) fn group_rows<'a>(
+    scope: &Scope,
+    group_by: &[Expression],
+    aggregates: &[&'a Expression],
+    rows: Vec<Row>,
+    outer: Option<&RowContext>,
+    executor: &Executor,
+) -> ExecutionResult<Vec<Group<'a>>> {
+    let mut groups: Vec<(Row, Vec<Accumulator>)> = Vec::new();
+    let mut positions: HashMap<Vec<Value>, usize> = HashMap::new();
+    let new_group = |row: Row| {
+        (
+            row,
+            aggregates.iter().map(|a| Accumulator::new(a)).collect(),
+        )
+    };
+
+    if group_by.is_empty() {
+        positions.insert(Vec::new(), 0);
+        groups.push(new_group(vec![Value::Null; scope.len()]));
+    }
+    for row in rows {
+        let context = RowContext {
+            scope,
+            row: &row,
+            aggregates: &[],
+            outer,
+            executor,
+        };
+        let key = group_by
+            .iter()
+            .map(|expr| evaluate(expr, &context))
+            .collect::<ExecutionResult<Vec<Value>>>()?;
+        let position = match positions.get(&key) {
+            Some(&position) => position,
+            None => {
+                positions.insert(key, groups.len());
+                groups.push(new_group(row.clone()));
+                groups.len() - 1
+            }
+        };
+        for accumulator in &mut groups[position].1 {
+            accumulator.update(&context)?;
+        }
+    }
+
+    Ok(groups
+        .into_iter()
+        .map(|(row, accumulators)| {
+            let values = aggregates
+                .iter()
+                .copied()
+                .zip(accumulators.into_iter().map(Accumulator::finish))
+                .collect();
+            (row, values)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use sql_jr_parser::{
+        create_statement::{Column, SqlTypeInfo},
+        types::{Parse, Span},
+    };
+
+    use super::*;
+    use crate::select::TableRows;
+
+    fn expression(raw: &str) -> Expression {
+        Expression::parse_from_raw(raw).unwrap().1
+    }
+
+    fn aggregate(raw: &str, values: &[Value]) -> ExecutionResult<Value> {
+        let aggregate = expression(raw);
+        let scope = Scope::table(
+            &"t".into(),
+            &[Column {
+                name: "x".into(),
+                type_info: SqlTypeInfo::Int,
+                span: Span::default(),
+            }],
+        );
+        let tables = TableRows::new();
+        let uncorrelated = HashSet::new();
+        let executor = Executor::new(&tables, &uncorrelated);
+        let mut accumulator = Accumulator::new(&aggregate);
+        for value in values {
+            let row = vec![value.clone()];
+            let context = RowContext {
+                scope: &scope,
+                row: &row,
+                aggregates: &[],
+                outer: None,
+                executor: &executor,
+            };
+            accumulator.update(&context)?;
+        }
+        Ok(accumulator.finish())
+    }
+
+    #[test]
+    fn test_aggregates_skip_nulls() {
+        let values = [Value::Int(3), Value::Null, Value::Int(1), Value::Int(3)];
+
+        assert_eq!(aggregate("COUNT(*)", &values), Ok(Value::Int(4)));
+        assert_eq!(aggregate("COUNT(x)", &values), Ok(Value::Int(3)));
+        assert_eq!(aggregate("COUNT(DISTINCT x)", &values), Ok(Value::Int(2)));
+        assert_eq!(aggregate("SUM(x)", &values), Ok(Value::Int(7)));
+        assert_eq!(aggregate("SUM(DISTINCT x)", &values), Ok(Value::Int(4)));
+        assert_eq!(aggregate("AVG(x)", &values), Ok(Value::Float(7.0 / 3.0)));
+        assert_eq!(aggregate("MIN(x)", &values), Ok(Value::Int(1)));
+        assert_eq!(aggregate("MAX(x)", &values), Ok(Value::Int(3)));
+    }
+
+    #[test]
+    fn test_aggregates_of_no_rows() {
+        assert_eq!(aggregate("COUNT(x)", &[]), Ok(Value::Int(0)));
+        assert_eq!(aggregate("SUM(x)", &[Value::Null]), Ok(Value::Null));
+        assert_eq!(aggregate("AVG(x)", &[]), Ok(Value::Null));
+    }
+
+    #[test]
+    fn test_sum_of_strings_is_an_error() {
+        assert_eq!(
+            aggregate("SUM(x)", &[Value::String("a".to_string())]),
+            Err(ExecutionError::InvalidAggregate {
+                function: AggregateFunction::Sum,
+                value: Value::String("a".to_string())
+            })
+        );
+    }
+
+    #[test]
+    fn test_ungrouped_column() {
+        let scope = Scope::table(
+            &"Person".into(),
+            &["city", "age"].map(|name| Column {
+                name: name.into(),
+                type_info: SqlTypeInfo::Int,
+                span: Span::default(),
+            }),
+        );
+        let group_by = [expression("city"), expression("age / 10")];
+        let ungrouped =
+            |raw| ungrouped_column(&expression(raw), &group_by, &scope).map(|c| c.to_string());
+
+        assert_eq!(ungrouped("Person.city"), None);
+        assert_eq!(ungrouped("age / 10 + COUNT(name)"), None);
+        assert_eq!(ungrouped("age + 1"), Some("age".to_string()));
+    }
+}
@@ -0,0 +1,39 @@
+use std::collections::HashMap;
+
+use sql_jr_parser::create_statement::{Column, CreateStatement};
+
+/// The schemas of a database's tables, as given by the CREATE TABLE statements
+/// that created them
+#[derive(Clone, Debug, Default)]
+pub struct Catalog {
+    tables: HashMap<String, CreateStatement>,
+}
+
+impl Catalog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the schema of a newly created table
+    pub fn add(&mut self, statement: CreateStatement) {
+        self.tables.insert(statement.table.to_string(), statement);
+    }
+
+    /// Forget the table called `name`, returning its schema if it existed
+    pub fn remove(&mut self, name: &str) -> Option<CreateStatement> {
+        self.tables.remove(name)
+    }
+
+    /// The schema of the table called `name`, if it exists
+    pub fn table(&self, name: &str) -> Option<&CreateStatement> {
+        self.tables.get(name)
+    }
+
+    /// The column called `column` of the table called `table`, if both exist
+    pub fn column(&self, table: &str, column: &str) -> Option<&Column> {
+        self.table(table)?
+            .columns
+            .iter()
+            .find(|c| c.name == *column)
+    }
+}
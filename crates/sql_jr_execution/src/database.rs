@@ -0,0 +1,2565 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use miette::{Diagnostic, SourceSpan};
+use sql_jr_parser::{
+    alter_table_statement::{AlterTableAction, AlterTableStatement},
+    create_statement::{Column, CreateStatement, SqlTypeInfo},
+    delete_statement::DeleteStatement,
+    drop_statement::DropStatement,
+    ident::{Ident, ObjectName},
+    insert_statement::InsertStatement,
+    query::SqlQuery,
+    query_expression::QueryExpression,
+    truncate_statement::TruncateStatement,
+    update_statement::UpdateStatement,
+    value::{Literal, Value},
+};
+
+use crate::{
+    catalog::Catalog,
+    error::{ExecutionError, ExecutionResult},
+    evaluation::{evaluate, is_match, RowContext},
+    result::QueryResult,
+    scope::Scope,
+    select::{expression_tables, query_tables, Executor, TableRows},
+    storage::{
+        buffer_pool::{BufferPool, FileId},
+        catalog_file::{self, StoredTable},
+        heap_file::HeapFile,
+        pager::{FilePager, MemoryPager, Pager},
+        StorageError, StorageResult,
+    },
+    table::{is_assignable, Row, Table},
+    validation::{analyse, check_query, Analysis, SemanticError},
+};
+use thiserror::Error;
+
+/// How many times the recursive query of a recursive CTE may run by default
+pub const DEFAULT_RECURSION_LIMIT: usize = 1000;
+
+/// Why [`Database::run`] failed, labelled on the part of the query it is about: what
+/// the checks against the catalog found, or the whole query when running it failed
+#[derive(Debug, Error, Diagnostic)]
+pub enum QueryError<'a> {
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Invalid(SemanticError<'a>),
+
+    #[error("query failed")]
+    Failed {
+        #[source_code]
+        src: &'a str,
+
+        #[label("{kind}")]
+        span: SourceSpan,
+
+        kind: ExecutionError,
+    },
+}
+
+impl QueryError<'_> {
+    pub fn kind(&self) -> &ExecutionError {
+        match self {
+            QueryError::Invalid(error) => error.kind(),
+            QueryError::Failed { kind, .. } => kind,
+        }
+    }
+}
+
+/// A set of tables keyed by their name, either kept in memory or stored in a
+/// data directory
+#[derive(Debug)]
+pub struct Database {
+    catalog: Catalog,
+    tables: HashMap<String, Table>,
+    pool: BufferPool,
+    // where the catalog and heap files are stored, None when kept in memory
+    data_dir: Option<PathBuf>,
+    recursion_limit: usize,
+}
+
+impl Default for Database {
+    fn default() -> Self {
+        Self {
+            catalog: Catalog::default(),
+            tables: HashMap::new(),
+            pool: BufferPool::default(),
+            data_dir: None,
+            recursion_limit: DEFAULT_RECURSION_LIMIT,
+        }
+    }
+}
+
+impl Database {
+    /// An empty database whose tables are lost when it is dropped
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Open the database stored in `data_dir`, creating the directory when it
+    /// doesn't exist yet
+    pub fn open(data_dir: impl AsRef<Path>) -> ExecutionResult<Self> {
+        let data_dir = data_dir.as_ref();
+        fs::create_dir_all(data_dir).map_err(StorageError::from)?;
+
+        let mut database = Self {
+            data_dir: Some(data_dir.to_path_buf()),
+            ..Self::default()
+        };
+        for stored in catalog_file::load(data_dir)? {
+            let pager = FilePager::open(&data_dir.join(&stored.file))?;
+            let file = database.pool.register(Box::new(pager));
+            let heap = HeapFile::open(&mut database.pool, file)?;
+            database.tables.insert(
+                stored.schema.table.to_string(),
+                Table::new(stored.schema.columns.clone(), stored.file, heap),
+            );
+            database.catalog.add(stored.schema);
+        }
+        Ok(database)
+    }
+
+    /// Fail a recursive CTE whose recursive query is still returning rows after
+    /// running `limit` times
+    pub fn set_recursion_limit(&mut self, limit: usize) {
+        self.recursion_limit = limit;
+    }
+
+    /// The schemas of all the tables created so far
+    pub fn catalog(&self) -> &Catalog {
+        &self.catalog
+    }
+
+    /// The table called `name`, if it exists
+    pub fn table(&self, name: &str) -> Option<&Table> {
+        self.tables.get(name)
+    }
+
+    /// Run `query`, once it is checked against the catalog like
+    /// [`validate`](crate::validation::validate) does
+    pub fn execute(&mut self, query: &SqlQuery) -> ExecutionResult<QueryResult> {
+        let analysis = check_query(&self.catalog, query).map_err(|(error, _)| error)?;
+        self.execute_checked(query, &analysis)
+    }
+
+    /// Like [`execute`](Self::execute), with the error labelled in `src`, the text
+    /// `query` was parsed from
+    pub fn run<'s>(
+        &mut self,
+        query: &SqlQuery,
+        src: &'s str,
+    ) -> Result<QueryResult, QueryError<'s>> {
+        let analysis = analyse(&self.catalog, query, src).map_err(QueryError::Invalid)?;
+        self.execute_checked(query, &analysis)
+            .map_err(|kind| QueryError::Failed {
+                src,
+                span: query.span().into(),
+                kind,
+            })
+    }
+
+    fn execute_checked(
+        &mut self,
+        query: &SqlQuery,
+        analysis: &Analysis,
+    ) -> ExecutionResult<QueryResult> {
+        match query {
+            SqlQuery::Select(query) => self.select(query, analysis),
+            SqlQuery::Insert(insert) => self.insert(insert),
+            SqlQuery::Create(create) => self.create(create),
+            SqlQuery::Update(update) => self.update(update, analysis),
+            SqlQuery::Delete(delete) => self.delete(delete, analysis),
+            SqlQuery::Drop(drop) => self.drop_tables(drop),
+            SqlQuery::Truncate(truncate) => self.truncate_tables(truncate),
+            SqlQuery::AlterTable(alter) => self.alter_table(alter),
+        }
+    }
+
+    fn get_table(&self, name: &str) -> ExecutionResult<&Table> {
+        self.tables
+            .get(name)
+            .ok_or_else(|| ExecutionError::TableNotFound(name.to_string()))
+    }
+
+    // a heap file name no table uses yet
+    fn new_file_name(&self) -> String {
+        (0..)
+            .map(|n| format!("{n}.heap"))
+            .find(|name| self.tables.values().all(|table| table.file() != name))
+            .unwrap()
+    }
+
+    fn create_pager(&self, file: &str) -> StorageResult<Box<dyn Pager>> {
+        Ok(match &self.data_dir {
+            // a file left behind by a table that never made it into the catalog is replaced
+            Some(data_dir) => Box::new(FilePager::create(&data_dir.join(file))?),
+            None => Box::<MemoryPager>::default(),
+        })
+    }
+
+    // forgets the heap file `file` that no table uses any more
+    fn discard_file(&mut self, file_id: FileId, file: &str) {
+        self.pool.unregister(file_id);
+        if let Some(data_dir) = &self.data_dir {
+            // a file that can't be removed is harmless, a table created later
+            // with the same file name replaces it
+            let _ = fs::remove_file(data_dir.join(file));
+        }
+    }
+
+    // writes the catalog with the tables as they are, but for the table called `name`
+    // of `changed` that is recorded as its stored table instead, or added when new
+    fn save_catalog(&self, changed: Option<(&str, StoredTable)>) -> StorageResult<()> {
+        let Some(data_dir) = &self.data_dir else {
+            return Ok(());
+        };
+        let (changed_name, changed) = changed.unzip();
+        let tables = self
+            .tables
+            .iter()
+            .filter(|(name, _)| Some(name.as_str()) != changed_name)
+            .map(|(name, table)| StoredTable {
+                file: table.file().to_string(),
+                schema: self.catalog.table(name).unwrap().clone(),
+            })
+            .chain(changed)
+            .collect();
+        catalog_file::save(data_dir, tables)
+    }
+
+    // the names of `tables` that exist, failing on one that doesn't unless `if_exists`
+    fn existing_tables(
+        &self,
+        tables: &[ObjectName],
+        if_exists: bool,
+    ) -> ExecutionResult<Vec<String>> {
+        let mut existing = Vec::new();
+        for table in tables {
+            let name = table.to_string();
+            if self.tables.contains_key(&name) {
+                existing.push(name);
+            } else if !if_exists {
+                return Err(ExecutionError::TableNotFound(name));
+            }
+        }
+        Ok(existing)
+    }
+
+    fn create(&mut self, statement: &CreateStatement) -> ExecutionResult<QueryResult> {
+        let name = statement.table.to_string();
+        if self.tables.contains_key(&name) {
+            if statement.if_not_exists {
+                return Ok(QueryResult::Created);
+            }
+            return Err(ExecutionError::TableAlreadyExists(name));
+        }
+        let schema = CreateStatement {
+            if_not_exists: false,
+            ..statement.clone()
+        };
+
+        let file = self.new_file_name();
+        let pager = self.create_pager(&file)?;
+        let file_id = self.pool.register(pager);
+        let heap = HeapFile::open(&mut self.pool, file_id)?;
+        // the table only exists once the catalog says so
+        let stored = StoredTable {
+            file: file.clone(),
+            schema: schema.clone(),
+        };
+        if let Err(e) = self.save_catalog(Some((&name, stored))) {
+            self.pool.unregister(file_id);
+            return Err(e.into());
+        }
+
+        self.tables
+            .insert(name, Table::new(statement.columns.clone(), file, heap));
+        self.catalog.add(schema);
+        Ok(QueryResult::Created)
+    }
+
+    fn drop_tables(&mut self, statement: &DropStatement) -> ExecutionResult<QueryResult> {
+        let names = self.existing_tables(&statement.tables, statement.if_exists)?;
+
+        let mut dropped = Vec::new();
+        for name in names {
+            // a table named twice is only dropped once
+            if let Some(table) = self.tables.remove(&name) {
+                self.catalog.remove(&name);
+                dropped.push(table);
+            }
+        }
+        // the tables are gone once the catalog says so
+        self.save_catalog(None)?;
+
+        for table in dropped {
+            self.discard_file(table.file_id(), table.file());
+        }
+        Ok(QueryResult::Dropped)
+    }
+
+    fn truncate_tables(&mut self, statement: &TruncateStatement) -> ExecutionResult<QueryResult> {
+        let names = self.existing_tables(&statement.tables, statement.if_exists)?;
+        for name in names {
+            self.tables
+                .get_mut(&name)
+                .unwrap()
+                .truncate(&mut self.pool)?;
+        }
+        self.pool.flush()?;
+        Ok(QueryResult::Truncated)
+    }
+
+    fn alter_table(&mut self, statement: &AlterTableStatement) -> ExecutionResult<QueryResult> {
+        let name = statement.table.to_string();
+        let name = name.as_str();
+        let mut schema = self
+            .catalog
+            .table(name)
+            .cloned()
+            .ok_or_else(|| ExecutionError::TableNotFound(name.to_string()))?;
+        let position = |schema: &CreateStatement, column: &Ident| {
+            schema.columns.iter().position(|c| c.name == *column)
+        };
+        let column_index = |schema: &CreateStatement, column: &Ident| {
+            position(schema, column).ok_or_else(|| ExecutionError::ColumnNotFound {
+                table: name.to_string(),
+                column: column.to_string(),
+            })
+        };
+        let check_new_column =
+            |schema: &CreateStatement, column: &Ident| match position(schema, column) {
+                Some(_) => Err(ExecutionError::ColumnAlreadyExists {
+                    table: name.to_string(),
+                    column: column.to_string(),
+                }),
+                None => Ok(()),
+            };
+
+        // how each stored row changes, when it has to be rewritten
+        let mut rewrite: Option<Box<dyn Fn(Row) -> ExecutionResult<Row>>> = None;
+        match &statement.action {
+            // rows stored before the column existed read as NULL for it, so they
+            // are only back-filled the next time they are written
+            AlterTableAction::AddColumn(column) => {
+                check_new_column(&schema, &column.name)?;
+                schema.columns.push(column.clone());
+            }
+            AlterTableAction::DropColumn(column) => {
+                let index = column_index(&schema, column)?;
+                schema.columns.remove(index);
+                rewrite = Some(Box::new(move |mut row| {
+                    row.remove(index);
+                    Ok(row)
+                }));
+            }
+            AlterTableAction::RenameColumn { from, to } => {
+                let index = column_index(&schema, from)?;
+                check_new_column(&schema, to)?;
+                schema.columns[index].name = to.clone();
+            }
+            AlterTableAction::RenameTable(new_name) => {
+                if self.tables.contains_key(&new_name.to_string()) {
+                    return Err(ExecutionError::TableAlreadyExists(new_name.to_string()));
+                }
+                schema.table = new_name.clone();
+            }
+            AlterTableAction::AlterColumnType { column, type_info } => {
+                let index = column_index(&schema, column)?;
+                schema.columns[index].type_info = type_info.clone();
+                let column = schema.columns[index].clone();
+                rewrite = Some(Box::new(move |mut row| {
+                    row[index] =
+                        convert_value(&column, std::mem::replace(&mut row[index], Value::Null))?;
+                    Ok(row)
+                }));
+            }
+        }
+
+        let table = match rewrite {
+            Some(rewrite) => {
+                // every row is converted before any is written, so a value that can't
+                // be converted leaves the table untouched
+                let rows = self.tables[name]
+                    .scan(&mut self.pool)?
+                    .into_iter()
+                    .map(|(_, row)| rewrite(row))
+                    .collect::<ExecutionResult<Vec<_>>>()?;
+                self.rewritten_table(name, &schema, rows)?
+            }
+            None => {
+                // the table keeps its old name and schema until the catalog has the
+                // new ones
+                let stored = StoredTable {
+                    file: self.tables[name].file().to_string(),
+                    schema: schema.clone(),
+                };
+                self.save_catalog(Some((name, stored)))?;
+                let mut table = self.tables.remove(name).unwrap();
+                table.set_columns(schema.columns.clone());
+                table
+            }
+        };
+
+        self.tables.insert(schema.table.to_string(), table);
+        self.catalog.remove(name);
+        self.catalog.add(schema);
+        Ok(QueryResult::Altered)
+    }
+
+    // the table called `name` with the schema `schema`, its `rows` written to a new heap
+    // file. The old file is still the table's until the catalog points at the new one,
+    // so a failure leaves the stored rows as the old schema describes them
+    fn rewritten_table(
+        &mut self,
+        name: &str,
+        schema: &CreateStatement,
+        rows: Vec<Row>,
+    ) -> ExecutionResult<Table> {
+        let file = self.new_file_name();
+        let file_id = self.pool.register(self.create_pager(&file)?);
+        let stored = StoredTable {
+            file: file.clone(),
+            schema: schema.clone(),
+        };
+        let written = self.write_heap(file_id, &rows).and_then(|heap| {
+            self.save_catalog(Some((name, stored)))?;
+            Ok(heap)
+        });
+        let heap = match written {
+            Ok(heap) => heap,
+            Err(e) => {
+                self.discard_file(file_id, &file);
+                return Err(e.into());
+            }
+        };
+
+        let old = self.tables.remove(name).unwrap();
+        self.discard_file(old.file_id(), old.file());
+        Ok(Table::new(schema.columns.clone(), file, heap))
+    }
+
+    // a heap file holding just `rows`, written out to its pager
+    fn write_heap(&mut self, file_id: FileId, rows: &[Row]) -> StorageResult<HeapFile> {
+        let mut heap = HeapFile::open(&mut self.pool, file_id)?;
+        for row in rows {
+            heap.insert(&mut self.pool, row)?;
+        }
+        self.pool.flush()?;
+        Ok(heap)
+    }
+
+    fn insert(&mut self, statement: &InsertStatement) -> ExecutionResult<QueryResult> {
+        let name = statement.table.to_string();
+        let table = self.get_table(&name)?;
+
+        // position in the table of each of the inserted values
+        let targets = if statement.columns.is_empty() {
+            (0..table.columns().len()).collect()
+        } else {
+            statement
+                .columns
+                .iter()
+                .map(|column| {
+                    table.column_index(column.as_str()).ok_or_else(|| {
+                        ExecutionError::ColumnNotFound {
+                            table: name.clone(),
+                            column: column.to_string(),
+                        }
+                    })
+                })
+                .collect::<ExecutionResult<Vec<usize>>>()?
+        };
+
+        let rows = statement
+            .rows
+            .iter()
+            .map(|values| build_row(&name, table.columns(), &targets, values))
+            .collect::<ExecutionResult<Vec<Row>>>()?;
+
+        let count = rows.len();
+        let table = self.tables.get_mut(&name).unwrap();
+        for row in rows {
+            table.insert(&mut self.pool, &row)?;
+        }
+        self.pool.flush()?;
+        Ok(QueryResult::Affected(count))
+    }
+
+    fn update(
+        &mut self,
+        statement: &UpdateStatement,
+        analysis: &Analysis,
+    ) -> ExecutionResult<QueryResult> {
+        // the tables read by subqueries, as they were before the update
+        let mut names = Vec::new();
+        for assignment in &statement.assignments {
+            expression_tables(&assignment.value, &mut names);
+        }
+        if let Some(predicate) = &statement.where_clause {
+            expression_tables(predicate, &mut names);
+        }
+        let tables = self.scan_tables(&names)?;
+        let executor = Executor::new(&tables, &analysis.uncorrelated);
+
+        let table = self
+            .tables
+            .get_mut(&statement.table.to_string())
+            .ok_or_else(|| ExecutionError::TableNotFound(statement.table.to_string()))?;
+
+        let targets = statement
+            .assignments
+            .iter()
+            .map(|assignment| {
+                table
+                    .column_index(assignment.column.as_str())
+                    .ok_or_else(|| ExecutionError::ColumnNotFound {
+                        table: statement.table.to_string(),
+                        column: assignment.column.to_string(),
+                    })
+            })
+            .collect::<ExecutionResult<Vec<usize>>>()?;
+
+        // every new row is worked out before any is written, so a failing
+        // assignment leaves the table untouched
+        let scope = Scope::table(&statement.table, table.columns());
+        let mut updates = Vec::new();
+        for (id, row) in table.scan(&mut self.pool)? {
+            let context = RowContext {
+                scope: &scope,
+                row: &row,
+                aggregates: &[],
+                outer: None,
+                executor: &executor,
+            };
+            if let Some(predicate) = &statement.where_clause {
+                if !is_match(predicate, &context)? {
+                    continue;
+                }
+            }
+            // assignments all see the row as it was before the update
+            let mut updated = row.clone();
+            for (&target, assignment) in targets.iter().zip(&statement.assignments) {
+                let value = evaluate(&assignment.value, &context)?;
+                check_type(&table.columns()[target], &value)?;
+                updated[target] = value;
+            }
+            updates.push((id, updated));
+        }
+
+        let count = updates.len();
+        for (id, row) in updates {
+            table.update(&mut self.pool, id, &row)?;
+        }
+        self.pool.flush()?;
+        Ok(QueryResult::Affected(count))
+    }
+
+    fn delete(
+        &mut self,
+        statement: &DeleteStatement,
+        analysis: &Analysis,
+    ) -> ExecutionResult<QueryResult> {
+        let mut names = Vec::new();
+        if let Some(predicate) = &statement.where_clause {
+            expression_tables(predicate, &mut names);
+        }
+        let tables = self.scan_tables(&names)?;
+        let executor = Executor::new(&tables, &analysis.uncorrelated);
+
+        let table = self
+            .tables
+            .get_mut(&statement.table.to_string())
+            .ok_or_else(|| ExecutionError::TableNotFound(statement.table.to_string()))?;
+
+        // the rows to delete are all found before any is removed, so a predicate
+        // failing on some row leaves the table untouched
+        let scope = Scope::table(&statement.table, table.columns());
+        let mut deleted = Vec::new();
+        for (id, row) in table.scan(&mut self.pool)? {
+            if let Some(predicate) = &statement.where_clause {
+                let context = RowContext {
+                    scope: &scope,
+                    row: &row,
+                    aggregates: &[],
+                    outer: None,
+                    executor: &executor,
+                };
+                if !is_match(predicate, &context)? {
+                    continue;
+                }
+            }
+            deleted.push(id);
+        }
+
+        for &id in &deleted {
+            table.delete(&mut self.pool, id)?;
+        }
+        self.pool.flush()?;
+        Ok(QueryResult::Affected(deleted.len()))
+    }
+
+    fn select(
+        &mut self,
+        query: &QueryExpression,
+        analysis: &Analysis,
+    ) -> ExecutionResult<QueryResult> {
+        let mut names = Vec::new();
+        query_tables(query, &mut names);
+        let tables = self.scan_tables(&names)?;
+
+        let (columns, rows) = Executor::new(&tables, &analysis.uncorrelated)
+            .with_recursion_limit(self.recursion_limit)
+            .query(query, None)?;
+        Ok(QueryResult::Rows { columns, rows })
+    }
+
+    // the columns and rows of the tables called `names`, for a query to read. A name
+    // no table has may be a CTE, which the query fails on when it isn't
+    fn scan_tables(&mut self, names: &[&ObjectName]) -> ExecutionResult<TableRows> {
+        let mut tables = TableRows::new();
+        for name in names {
+            let name = name.to_string();
+            if tables.contains_key(&name) {
+                continue;
+            }
+            let Some(table) = self.tables.get(&name) else {
+                continue;
+            };
+            let rows = table
+                .scan(&mut self.pool)?
+                .into_iter()
+                .map(|(_, row)| row)
+                .collect();
+            tables.insert(name, (table.columns().to_vec(), rows));
+        }
+        Ok(tables)
+    }
+}
+
+// lays `values` out in table column order, columns without a value are NULL
+fn build_row(
+    table: &str,
+    columns: &[Column],
+    targets: &[usize],
+    values: &[Literal],
+) -> ExecutionResult<Row> {
+    if values.len() != targets.len() {
+        return Err(ExecutionError::ValueCountMismatch {
+            table: table.to_string(),
+            expected: targets.len(),
+            found: values.len(),
+        });
+    }
+
+    let mut row = vec![Value::Null; columns.len()];
+    for (&target, literal) in targets.iter().zip(values) {
+        check_type(&columns[target], &literal.value)?;
+        row[target] = literal.value.clone();
+    }
+    Ok(row)
+}
+
+// converts a value to the new type of `column`
+fn convert_value(column: &Column, value: Value) -> ExecutionResult<Value> {
+    match (&column.type_info, value) {
+        (_, Value::Null) => Ok(Value::Null),
+        (SqlTypeInfo::String, value) => Ok(Value::String(value.to_string())),
+        (SqlTypeInfo::Int, Value::Int(i)) => Ok(Value::Int(i)),
+        (SqlTypeInfo::Int, value) => match &value {
+            Value::String(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+        .map(Value::Int)
+        .ok_or_else(|| ExecutionError::CannotConvert {
+            column: column.name.to_string(),
+            value,
+            to: SqlTypeInfo::Int,
+        }),
+    }
+}
+
+fn check_type(column: &Column, value: &Value) -> ExecutionResult<()> {
+    if is_assignable(&column.type_info, value) {
+        return Ok(());
+    }
+    Err(ExecutionError::TypeMismatch {
+        column: column.name.to_string(),
+        expected: column.type_info.clone(),
+        found: value.clone(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use sql_jr_parser::{
+        create_statement::SqlTypeInfo, expression::ConditionalFunction,
+        query_expression::SetOperator, types::Parse,
+    };
+
+    use super::*;
+    use crate::result::ColumnInfo;
+
+    fn run(database: &mut Database, raw: &str) -> ExecutionResult<QueryResult> {
+        let query = SqlQuery::parse_format_error(raw).unwrap();
+        database.execute(&query)
+    }
+
+    fn selected_rows(database: &mut Database, raw: &str) -> Vec<Row> {
+        match run(database, raw) {
+            Ok(QueryResult::Rows { rows, .. }) => rows,
+            other => panic!("expected rows, got {other:?}"),
+        }
+    }
+
+    fn add_people(database: &mut Database) {
+        run(database, "CREATE TABLE Person (name string, age int);").unwrap();
+        run(
+            database,
+            "INSERT INTO Person VALUES ('Jo', 42), ('Al', 7), ('Bo', NULL);",
+        )
+        .unwrap();
+    }
+
+    fn people() -> Database {
+        let mut database = Database::new();
+        add_people(&mut database);
+        database
+    }
+
+    #[test]
+    fn test_create_table() {
+        let mut database = Database::new();
+
+        let result = run(&mut database, "CREATE TABLE Person (name string, age int);");
+
+        assert_eq!(result, Ok(QueryResult::Created));
+        assert_eq!(database.table("person").unwrap().columns().len(), 2);
+    }
+
+    #[test]
+    fn test_create_existing_table_is_an_error() {
+        let mut database = people();
+
+        let result = run(&mut database, "CREATE TABLE Person (name string);");
+
+        assert_eq!(
+            result,
+            Err(ExecutionError::TableAlreadyExists("person".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_insert_reports_affected_rows() {
+        let mut database = people();
+
+        let result = run(&mut database, "INSERT INTO Person (age) VALUES (1), (2);");
+
+        assert_eq!(result, Ok(QueryResult::Affected(2)));
+        assert_eq!(
+            selected_rows(&mut database, "SELECT name, age FROM Person;")[3],
+            vec![Value::Null, Value::Int(1)]
+        );
+    }
+
+    #[test]
+    fn test_insert_with_wrong_type_is_an_error() {
+        let mut database = people();
+
+        let result = run(&mut database, "INSERT INTO Person VALUES (42, 'Jo');");
+
+        assert_eq!(
+            result,
+            Err(ExecutionError::TypeMismatch {
+                column: "name".to_string(),
+                expected: SqlTypeInfo::String,
+                found: Value::Int(42)
+            })
+        );
+    }
+
+    #[test]
+    fn test_insert_with_wrong_value_count_is_an_error() {
+        let mut database = people();
+
+        let result = run(&mut database, "INSERT INTO Person VALUES ('Jo');");
+
+        assert_eq!(
+            result,
+            Err(ExecutionError::ValueCountMismatch {
+                table: "person".to_string(),
+                expected: 2,
+                found: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_queries_are_validated_before_running() {
+        let mut database = people();
+
+        assert_eq!(
+            run(
+                &mut database,
+                "INSERT INTO Person (age, age) VALUES (1, 2);"
+            ),
+            Err(ExecutionError::DuplicateColumn("age".to_string()))
+        );
+        assert_eq!(
+            run(&mut database, "CREATE TABLE City (name string, name int);"),
+            Err(ExecutionError::DuplicateColumn("name".to_string()))
+        );
+        assert!(database.table("city").is_none());
+        assert_eq!(
+            selected_rows(&mut database, "SELECT * FROM Person;").len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_insert_into_missing_table_is_an_error() {
+        let mut database = Database::new();
+
+        let result = run(&mut database, "INSERT INTO Person VALUES ('Jo');");
+
+        assert_eq!(
+            result,
+            Err(ExecutionError::TableNotFound("person".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_select_columns() {
+        let mut database = people();
+
+        let result = run(&mut database, "SELECT age, name FROM Person;");
+
+        assert_eq!(
+            result,
+            Ok(QueryResult::Rows {
+                columns: vec![
+                    ColumnInfo {
+                        name: "age".to_string(),
+                        type_info: Some(SqlTypeInfo::Int)
+                    },
+                    ColumnInfo {
+                        name: "name".to_string(),
+                        type_info: Some(SqlTypeInfo::String)
+                    },
+                ],
+                rows: vec![
+                    vec![Value::Int(42), Value::String("Jo".to_string())],
+                    vec![Value::Int(7), Value::String("Al".to_string())],
+                    vec![Value::Null, Value::String("Bo".to_string())],
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_select_wildcard_in_schema_order() {
+        let mut database = people();
+
+        let result = run(&mut database, "SELECT * FROM Person WHERE age > 10;");
+
+        assert_eq!(
+            result,
+            Ok(QueryResult::Rows {
+                columns: vec![
+                    ColumnInfo {
+                        name: "name".to_string(),
+                        type_info: Some(SqlTypeInfo::String)
+                    },
+                    ColumnInfo {
+                        name: "age".to_string(),
+                        type_info: Some(SqlTypeInfo::Int)
+                    },
+                ],
+                rows: vec![vec![Value::String("Jo".to_string()), Value::Int(42)]]
+            })
+        );
+    }
+
+    #[test]
+    fn test_select_expressions_with_aliases() {
+        let mut database = people();
+
+        let result = run(
+            &mut database,
+            "SELECT Person.name, age + 1 AS older, age * 1.5 FROM Person WHERE age < 10;",
+        );
+
+        assert_eq!(
+            result,
+            Ok(QueryResult::Rows {
+                columns: vec![
+                    ColumnInfo {
+                        name: "name".to_string(),
+                        type_info: Some(SqlTypeInfo::String)
+                    },
+                    ColumnInfo {
+                        name: "older".to_string(),
+                        type_info: Some(SqlTypeInfo::Int)
+                    },
+                    ColumnInfo {
+                        name: "?column?".to_string(),
+                        type_info: None
+                    },
+                ],
+                rows: vec![vec![
+                    Value::String("Al".to_string()),
+                    Value::Int(8),
+                    Value::Float(10.5)
+                ]]
+            })
+        );
+    }
+
+    #[test]
+    fn test_select_unknown_qualifier_is_an_error() {
+        let mut database = people();
+
+        let result = run(&mut database, "SELECT City.* FROM Person;");
+
+        assert_eq!(
+            result,
+            Err(ExecutionError::TableNotInQuery("city".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_select_order_by() {
+        let mut database = people();
+
+        let names = |database: &mut Database, raw| {
+            selected_rows(database, raw)
+                .into_iter()
+                .map(|row| row[0].to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            names(&mut database, "SELECT name FROM Person ORDER BY age;"),
+            vec!["Al", "Jo", "Bo"]
+        );
+        assert_eq!(
+            names(&mut database, "SELECT name FROM Person ORDER BY age DESC;"),
+            vec!["Bo", "Jo", "Al"]
+        );
+        assert_eq!(
+            names(
+                &mut database,
+                "SELECT name, age * -1 AS negated FROM Person ORDER BY negated NULLS FIRST;"
+            ),
+            vec!["Bo", "Jo", "Al"]
+        );
+        assert_eq!(
+            names(&mut database, "SELECT name FROM Person ORDER BY 1 DESC;"),
+            vec!["Jo", "Bo", "Al"]
+        );
+    }
+
+    #[test]
+    fn test_select_limit_and_offset() {
+        let mut database = people();
+
+        let names = |database: &mut Database, raw| {
+            selected_rows(database, raw)
+                .into_iter()
+                .map(|row| row[0].to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            names(&mut database, "SELECT name FROM Person LIMIT 2;"),
+            vec!["Jo", "Al"]
+        );
+        assert_eq!(
+            names(&mut database, "SELECT name FROM Person LIMIT 2 OFFSET 2;"),
+            vec!["Bo"]
+        );
+        assert_eq!(
+            names(
+                &mut database,
+                "SELECT name FROM Person ORDER BY name OFFSET 1 FETCH FIRST 1 ROW ONLY;"
+            ),
+            vec!["Bo"]
+        );
+        assert_eq!(
+            names(&mut database, "SELECT name FROM Person LIMIT 0;"),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn test_select_order_by_position_out_of_range_is_an_error() {
+        let mut database = people();
+
+        let result = run(&mut database, "SELECT name FROM Person ORDER BY 2;");
+
+        assert_eq!(result, Err(ExecutionError::OrderByPositionOutOfRange(2)));
+    }
+
+    fn add_cities(database: &mut Database) {
+        run(
+            database,
+            "CREATE TABLE Resident (name string, city string, age int);",
+        )
+        .unwrap();
+        run(
+            database,
+            "INSERT INTO Resident VALUES ('Jo', 'Oslo', 42), ('Al', 'Oslo', 7), \
+             ('Bo', 'Rome', NULL), ('Cy', 'Oslo', 30), ('Di', 'Rome', 20), ('Ed', NULL, 5);",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_select_group_by_with_aggregates() {
+        let mut database = Database::new();
+        add_cities(&mut database);
+
+        let result = run(
+            &mut database,
+            "SELECT city, COUNT(*), AVG(age), MIN(age) youngest FROM Resident \
+             GROUP BY city HAVING COUNT(*) > 1 ORDER BY city;",
+        );
+
+        assert_eq!(
+            result,
+            Ok(QueryResult::Rows {
+                columns: vec![
+                    ColumnInfo {
+                        name: "city".to_string(),
+                        type_info: Some(SqlTypeInfo::String)
+                    },
+                    ColumnInfo {
+                        name: "count".to_string(),
+                        type_info: Some(SqlTypeInfo::Int)
+                    },
+                    ColumnInfo {
+                        name: "avg".to_string(),
+                        type_info: None
+                    },
+                    ColumnInfo {
+                        name: "youngest".to_string(),
+                        type_info: Some(SqlTypeInfo::Int)
+                    },
+                ],
+                rows: vec![
+                    vec![
+                        Value::String("Oslo".to_string()),
+                        Value::Int(3),
+                        Value::Float(79.0 / 3.0),
+                        Value::Int(7)
+                    ],
+                    vec![
+                        Value::String("Rome".to_string()),
+                        Value::Int(2),
+                        Value::Float(20.0),
+                        Value::Int(20)
+                    ],
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_select_aggregates_without_group_by() {
+        let mut database = Database::new();
+        add_cities(&mut database);
+
+        let rows = selected_rows(
+            &mut database,
+            "SELECT COUNT(city), COUNT(DISTINCT city), SUM(age) FROM Resident WHERE age > 6;",
+        );
+        assert_eq!(
+            rows,
+            vec![vec![Value::Int(4), Value::Int(2), Value::Int(99)]]
+        );
+
+        let rows = selected_rows(
+            &mut database,
+            "SELECT COUNT(*), MAX(age) FROM Resident WHERE age > 100;",
+        );
+        assert_eq!(rows, vec![vec![Value::Int(0), Value::Null]]);
+    }
+
+    #[test]
+    fn test_select_group_by_sorts_by_aggregates() {
+        let mut database = Database::new();
+        add_cities(&mut database);
+
+        let rows = selected_rows(
+            &mut database,
+            "SELECT city FROM Resident GROUP BY city ORDER BY COUNT(*) DESC, city LIMIT 2;",
+        );
+
+        assert_eq!(
+            rows,
+            vec![
+                vec![Value::String("Oslo".to_string())],
+                vec![Value::String("Rome".to_string())]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_select_ungrouped_column_is_an_error() {
+        let mut database = Database::new();
+        add_cities(&mut database);
+
+        let result = run(
+            &mut database,
+            "SELECT name, COUNT(*) FROM Resident GROUP BY city;",
+        );
+
+        assert_eq!(
+            result,
+            Err(ExecutionError::UngroupedColumn("name".to_string()))
+        );
+    }
+
+    fn add_towns(database: &mut Database) {
+        run(database, "CREATE TABLE Town (city string, country string);").unwrap();
+        run(
+            database,
+            "INSERT INTO Town VALUES ('Oslo', 'Norway'), ('Rome', 'Italy'), ('Lima', 'Peru');",
+        )
+        .unwrap();
+    }
+
+    fn string(value: &str) -> Value {
+        Value::String(value.to_string())
+    }
+
+    #[test]
+    fn test_select_inner_join_with_aliases() {
+        let mut database = Database::new();
+        add_cities(&mut database);
+        add_towns(&mut database);
+
+        let rows = selected_rows(
+            &mut database,
+            "SELECT r.name, t.country FROM Resident r JOIN Town AS t ON r.city = t.city \
+             WHERE r.age > 10 ORDER BY r.name;",
+        );
+
+        assert_eq!(
+            rows,
+            vec![
+                vec![string("Cy"), string("Norway")],
+                vec![string("Di"), string("Italy")],
+                vec![string("Jo"), string("Norway")],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_select_outer_joins_pad_with_nulls() {
+        let mut database = Database::new();
+        add_cities(&mut database);
+        add_towns(&mut database);
+
+        let rows = selected_rows(
+            &mut database,
+            "SELECT t.city, r.name FROM Resident r RIGHT JOIN Town t \
+             ON r.city = t.city AND r.age > 25;",
+        );
+        assert_eq!(
+            rows,
+            vec![
+                vec![string("Oslo"), string("Jo")],
+                vec![string("Oslo"), string("Cy")],
+                vec![string("Rome"), Value::Null],
+                vec![string("Lima"), Value::Null],
+            ]
+        );
+
+        // the merged city comes from whichever side has a row
+        let rows = selected_rows(
+            &mut database,
+            "SELECT city, COUNT(name) FROM Resident FULL JOIN Town USING (city) \
+             GROUP BY city ORDER BY city;",
+        );
+        assert_eq!(
+            rows,
+            vec![
+                vec![string("Lima"), Value::Int(0)],
+                vec![string("Oslo"), Value::Int(3)],
+                vec![string("Rome"), Value::Int(2)],
+                vec![Value::Null, Value::Int(1)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_select_natural_and_cross_joins() {
+        let mut database = Database::new();
+        add_cities(&mut database);
+        add_towns(&mut database);
+
+        let result = run(
+            &mut database,
+            "SELECT * FROM Resident NATURAL JOIN Town WHERE name = 'Di';",
+        );
+        assert_eq!(
+            result,
+            Ok(QueryResult::Rows {
+                columns: ["city", "name", "age", "country"]
+                    .into_iter()
+                    .zip([
+                        SqlTypeInfo::String,
+                        SqlTypeInfo::String,
+                        SqlTypeInfo::Int,
+                        SqlTypeInfo::String
+                    ])
+                    .map(|(name, type_info)| ColumnInfo {
+                        name: name.to_string(),
+                        type_info: Some(type_info)
+                    })
+                    .collect(),
+                rows: vec![vec![
+                    string("Rome"),
+                    string("Di"),
+                    Value::Int(20),
+                    string("Italy")
+                ]],
+            })
+        );
+
+        assert_eq!(
+            selected_rows(&mut database, "SELECT COUNT(*) FROM Resident, Town;"),
+            vec![vec![Value::Int(18)]]
+        );
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "SELECT Town.* FROM Resident CROSS JOIN Town WHERE Resident.name = 'Jo';"
+            )
+            .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_select_join_column_errors() {
+        let mut database = Database::new();
+        add_cities(&mut database);
+
+        assert_eq!(
+            run(
+                &mut database,
+                "SELECT name FROM Resident a JOIN Resident b ON a.age = b.age;"
+            ),
+            Err(ExecutionError::AmbiguousColumn("name".to_string()))
+        );
+        assert_eq!(
+            run(
+                &mut database,
+                "SELECT * FROM Resident JOIN Resident ON age = 1;"
+            ),
+            Err(ExecutionError::DuplicateTableReference(
+                "resident".to_string()
+            ))
+        );
+        assert_eq!(
+            run(
+                &mut database,
+                "SELECT Resident.name FROM Resident r JOIN Resident s USING (age);"
+            ),
+            Err(ExecutionError::TableNotInQuery("resident".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_select_in_subqueries() {
+        let mut database = Database::new();
+        add_cities(&mut database);
+        add_towns(&mut database);
+
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "SELECT name FROM Resident WHERE city IN \
+                 (SELECT city FROM Town WHERE country = 'Italy');"
+            ),
+            vec![vec![string("Bo")], vec![string("Di")]]
+        );
+        // a NULL among the rows makes NOT IN NULL for the values it doesn't find
+        assert!(selected_rows(
+            &mut database,
+            "SELECT city FROM Town WHERE city NOT IN (SELECT city FROM Resident);"
+        )
+        .is_empty());
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "SELECT city FROM Town WHERE city NOT IN \
+                 (SELECT city FROM Resident WHERE name <> 'Ed');"
+            ),
+            vec![vec![string("Lima")]]
+        );
+    }
+
+    #[test]
+    fn test_select_scalar_subqueries() {
+        let mut database = Database::new();
+        add_cities(&mut database);
+
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "SELECT name, age - (SELECT MIN(age) FROM Resident) FROM Resident \
+                 WHERE age = (SELECT MAX(age) FROM Resident);"
+            ),
+            vec![vec![string("Jo"), Value::Int(37)]]
+        );
+        assert_eq!(
+            run(
+                &mut database,
+                "SELECT name FROM Resident WHERE age = (SELECT age FROM Resident);"
+            ),
+            Err(ExecutionError::SubqueryTooManyRows)
+        );
+        assert_eq!(
+            run(
+                &mut database,
+                "SELECT (SELECT name, age FROM Resident) FROM Resident;"
+            ),
+            Err(ExecutionError::SubqueryColumnCount)
+        );
+    }
+
+    #[test]
+    fn test_select_correlated_subqueries() {
+        let mut database = Database::new();
+        add_cities(&mut database);
+        add_towns(&mut database);
+
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "SELECT country FROM Town t WHERE EXISTS \
+                 (SELECT * FROM Resident r WHERE r.city = t.city AND r.age > 25);"
+            ),
+            vec![vec![string("Norway")]]
+        );
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "SELECT city, (SELECT COUNT(*) FROM Resident WHERE Resident.city = Town.city) \
+                 FROM Town ORDER BY 2 DESC;"
+            ),
+            vec![
+                vec![string("Oslo"), Value::Int(3)],
+                vec![string("Rome"), Value::Int(2)],
+                vec![string("Lima"), Value::Int(0)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_select_from_derived_tables() {
+        let mut database = Database::new();
+        add_cities(&mut database);
+        add_towns(&mut database);
+
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "SELECT s.city, s.total FROM \
+                 (SELECT city, SUM(age) AS total FROM Resident GROUP BY city) AS s \
+                 WHERE s.total > 25;"
+            ),
+            vec![vec![string("Oslo"), Value::Int(79)]]
+        );
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "SELECT t.country, n FROM Town t \
+                 JOIN (SELECT city, COUNT(*) n FROM Resident GROUP BY city) s USING (city) \
+                 ORDER BY n;"
+            ),
+            vec![
+                vec![string("Italy"), Value::Int(2)],
+                vec![string("Norway"), Value::Int(3)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_select_distinct() {
+        let mut database = Database::new();
+        add_cities(&mut database);
+
+        assert_eq!(
+            selected_rows(&mut database, "SELECT DISTINCT city FROM Resident;"),
+            vec![
+                vec![string("Oslo")],
+                vec![string("Rome")],
+                vec![Value::Null]
+            ]
+        );
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "SELECT DISTINCT city FROM Resident ORDER BY Resident.city DESC LIMIT 2;"
+            ),
+            vec![vec![Value::Null], vec![string("Rome")]]
+        );
+        assert_eq!(
+            run(
+                &mut database,
+                "SELECT DISTINCT name FROM Resident ORDER BY age;"
+            ),
+            Err(ExecutionError::DistinctOrderBy)
+        );
+    }
+
+    #[test]
+    fn test_select_distinct_on_keeps_the_first_row_of_each_key() {
+        let mut database = Database::new();
+        add_cities(&mut database);
+
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "SELECT DISTINCT ON (1) city, name FROM Resident;"
+            ),
+            vec![
+                vec![string("Oslo"), string("Jo")],
+                vec![string("Rome"), string("Bo")],
+                vec![Value::Null, string("Ed")],
+            ]
+        );
+        // the oldest of each city, the page is taken once the duplicates are gone
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "SELECT DISTINCT ON (city) city, name FROM Resident \
+                 ORDER BY city, age DESC NULLS LAST LIMIT 2 OFFSET 1;"
+            ),
+            vec![
+                vec![string("Rome"), string("Di")],
+                vec![Value::Null, string("Ed")],
+            ]
+        );
+        assert_eq!(
+            run(&mut database, "SELECT DISTINCT ON (3) name FROM Resident;"),
+            Err(ExecutionError::DistinctOnPositionOutOfRange(3))
+        );
+    }
+
+    #[test]
+    fn test_select_distinct_on_a_subquery() {
+        let mut database = Database::new();
+        add_cities(&mut database);
+        add_towns(&mut database);
+
+        // the first resident of each country, where only DISTINCT ON reads Town
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "SELECT DISTINCT ON ((SELECT country FROM Town WHERE Town.city = Resident.city)) \
+                 name FROM Resident;"
+            ),
+            vec![vec![string("Jo")], vec![string("Bo")], vec![string("Ed")]]
+        );
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "SELECT DISTINCT ON ((SELECT country FROM Town WHERE Town.city = Resident.city)) \
+                 name FROM Resident \
+                 ORDER BY (SELECT country FROM Town WHERE Town.city = Resident.city), name;"
+            ),
+            vec![vec![string("Bo")], vec![string("Al")], vec![string("Ed")]]
+        );
+    }
+
+    #[test]
+    fn test_select_set_operations() {
+        let mut database = Database::new();
+        add_cities(&mut database);
+        add_towns(&mut database);
+
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "SELECT city FROM Town UNION SELECT city FROM Resident ORDER BY city;"
+            ),
+            vec![
+                vec![string("Lima")],
+                vec![string("Oslo")],
+                vec![string("Rome")],
+                vec![Value::Null],
+            ]
+        );
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "SELECT city FROM Town UNION ALL SELECT city FROM Resident;"
+            )
+            .len(),
+            9
+        );
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "SELECT city FROM Town INTERSECT SELECT city FROM Resident;"
+            ),
+            vec![vec![string("Oslo")], vec![string("Rome")]]
+        );
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "SELECT city FROM Town EXCEPT SELECT city FROM Resident;"
+            ),
+            vec![vec![string("Lima")]]
+        );
+    }
+
+    #[test]
+    fn test_set_operation_order_by_and_limit_apply_to_all_rows() {
+        let mut database = Database::new();
+        add_cities(&mut database);
+        add_towns(&mut database);
+
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "SELECT name FROM Resident WHERE age > 25 UNION SELECT country FROM Town \
+                 ORDER BY 1 DESC LIMIT 2 OFFSET 1;"
+            ),
+            vec![vec![string("Norway")], vec![string("Jo")]]
+        );
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "SELECT name FROM Resident WHERE city IN \
+                 (SELECT city FROM Town WHERE country = 'Italy' \
+                  UNION SELECT city FROM Town WHERE country = 'Peru');"
+            ),
+            vec![vec![string("Bo")], vec![string("Di")]]
+        );
+    }
+
+    #[test]
+    fn test_incompatible_set_operations_are_errors() {
+        let mut database = Database::new();
+        add_cities(&mut database);
+        add_towns(&mut database);
+
+        assert_eq!(
+            run(
+                &mut database,
+                "SELECT name, age FROM Resident UNION SELECT city FROM Town;"
+            ),
+            Err(ExecutionError::SetOperationColumnCount(SetOperator::Union))
+        );
+        assert_eq!(
+            run(
+                &mut database,
+                "SELECT age FROM Resident EXCEPT SELECT city FROM Town;"
+            ),
+            Err(ExecutionError::SetOperationTypeMismatch {
+                op: SetOperator::Except,
+                left: SqlTypeInfo::Int,
+                right: SqlTypeInfo::String
+            })
+        );
+        assert_eq!(
+            run(
+                &mut database,
+                "SELECT city FROM Town UNION SELECT city FROM Resident ORDER BY country;"
+            ),
+            Err(ExecutionError::SetOperationOrderBy(SetOperator::Union))
+        );
+    }
+
+    #[test]
+    fn test_select_sorted_query_sorted_again() {
+        let mut database = Database::new();
+        add_cities(&mut database);
+        add_towns(&mut database);
+
+        // the three oldest, by name
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "(SELECT name FROM Resident ORDER BY age DESC NULLS LAST LIMIT 3) \
+                 ORDER BY name LIMIT 2;"
+            ),
+            vec![vec![string("Cy")], vec![string("Di")]]
+        );
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "(SELECT name FROM Resident ORDER BY name LIMIT 2) \
+                 UNION SELECT city FROM Town ORDER BY name DESC;"
+            ),
+            vec![
+                vec![string("Rome")],
+                vec![string("Oslo")],
+                vec![string("Lima")],
+                vec![string("Bo")],
+                vec![string("Al")],
+            ]
+        );
+        assert_eq!(
+            run(
+                &mut database,
+                "(SELECT name FROM Resident LIMIT 2) ORDER BY age;"
+            ),
+            Err(ExecutionError::NestedOrderBy)
+        );
+    }
+
+    #[test]
+    fn test_select_with_ctes() {
+        let mut database = Database::new();
+        add_towns(&mut database);
+
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "WITH norse AS (SELECT city FROM Town WHERE country = 'Norway'), \
+                 names(place) AS (SELECT city FROM norse) \
+                 SELECT place FROM names;"
+            ),
+            vec![vec![string("Oslo")]]
+        );
+        // the CTE hides the table it reads in the query, subqueries included
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "WITH Town AS (SELECT city FROM Town WHERE city <> 'Rome') \
+                 SELECT t.city FROM Town AS t \
+                 WHERE EXISTS (SELECT city FROM Town WHERE city = t.city);"
+            ),
+            vec![vec![string("Oslo")], vec![string("Lima")]]
+        );
+        assert_eq!(
+            run(
+                &mut database,
+                "SELECT city FROM (WITH norse AS (SELECT city FROM Town) \
+                 SELECT city FROM norse) AS n, norse;"
+            ),
+            Err(ExecutionError::TableNotFound("norse".to_string()))
+        );
+    }
+
+    fn add_edges(database: &mut Database) {
+        run(database, "CREATE TABLE Edge (parent string, child string);").unwrap();
+        run(
+            database,
+            "INSERT INTO Edge VALUES ('a', 'b'), ('b', 'c'), ('c', 'd'), ('x', 'y');",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_select_recursive_cte() {
+        let mut database = Database::new();
+        add_towns(&mut database);
+        add_edges(&mut database);
+
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "WITH RECURSIVE t(n) AS (SELECT 1 FROM Town WHERE city = 'Oslo' \
+                 UNION ALL SELECT n + 1 FROM t WHERE n < 4) SELECT n FROM t;"
+            ),
+            [1, 2, 3, 4].map(|n| vec![Value::Int(n)])
+        );
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "WITH RECURSIVE below(node) AS (SELECT child FROM Edge WHERE parent = 'a' \
+                 UNION SELECT child FROM Edge JOIN below ON parent = node) \
+                 SELECT node FROM below;"
+            ),
+            ["b", "c", "d"].map(|node| vec![string(node)])
+        );
+    }
+
+    #[test]
+    fn test_recursive_cte_on_a_cycle() {
+        let mut database = Database::new();
+        add_edges(&mut database);
+        run(&mut database, "INSERT INTO Edge VALUES ('d', 'a');").unwrap();
+        database.set_recursion_limit(10);
+        let query = |set_operator| {
+            format!(
+                "WITH RECURSIVE below(node) AS (SELECT child FROM Edge WHERE parent = 'a' \
+                 {set_operator} SELECT child FROM Edge JOIN below ON parent = node) \
+                 SELECT node FROM below;"
+            )
+        };
+
+        // the rows found before are left out, so the query runs out of new ones
+        assert_eq!(
+            selected_rows(&mut database, &query("UNION")),
+            ["b", "c", "d", "a"].map(|node| vec![string(node)])
+        );
+        assert_eq!(
+            run(&mut database, &query("UNION ALL")),
+            Err(ExecutionError::RecursionLimitExceeded {
+                name: "below".to_string(),
+                limit: 10
+            })
+        );
+    }
+
+    #[test]
+    fn test_invalid_ctes_are_errors() {
+        let mut database = Database::new();
+        add_towns(&mut database);
+
+        assert_eq!(
+            run(
+                &mut database,
+                "WITH t(a, b) AS (SELECT city FROM Town) SELECT a FROM t;"
+            ),
+            Err(ExecutionError::CteColumnCount {
+                name: "t".to_string(),
+                available: 1,
+                specified: 2
+            })
+        );
+        assert_eq!(
+            run(
+                &mut database,
+                "WITH RECURSIVE t AS (SELECT city FROM t) SELECT city FROM t;"
+            ),
+            Err(ExecutionError::RecursiveCteForm("t".to_string()))
+        );
+        assert_eq!(
+            run(
+                &mut database,
+                "WITH RECURSIVE t AS (SELECT city FROM Town UNION SELECT country FROM t \
+                 ORDER BY city) SELECT city FROM t;"
+            ),
+            Err(ExecutionError::RecursiveCteForm("t".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_select_window_functions() {
+        let mut database = Database::new();
+        add_cities(&mut database);
+
+        // the window holds the rows WHERE leaves
+        assert_eq!(
+            run(
+                &mut database,
+                "SELECT name, RANK() OVER (PARTITION BY city ORDER BY age DESC) AS r \
+                 FROM Resident WHERE age > 5 ORDER BY city, r;"
+            ),
+            Ok(QueryResult::Rows {
+                columns: vec![
+                    ColumnInfo {
+                        name: "name".to_string(),
+                        type_info: Some(SqlTypeInfo::String)
+                    },
+                    ColumnInfo {
+                        name: "r".to_string(),
+                        type_info: Some(SqlTypeInfo::Int)
+                    },
+                ],
+                rows: vec![
+                    vec![string("Jo"), Value::Int(1)],
+                    vec![string("Cy"), Value::Int(2)],
+                    vec![string("Al"), Value::Int(3)],
+                    vec![string("Di"), Value::Int(1)],
+                ],
+            })
+        );
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "SELECT name, SUM(age) OVER (ORDER BY name ROWS UNBOUNDED PRECEDING) \
+                 FROM Resident ORDER BY name LIMIT 3;"
+            ),
+            vec![
+                vec![string("Al"), Value::Int(7)],
+                vec![string("Bo"), Value::Int(7)],
+                vec![string("Cy"), Value::Int(37)],
+            ]
+        );
+        assert_eq!(
+            run(
+                &mut database,
+                "SELECT name FROM Resident WHERE RANK() OVER () > 1;"
+            ),
+            Err(ExecutionError::WindowNotAllowed)
+        );
+    }
+
+    #[test]
+    fn test_window_functions_over_groups() {
+        let mut database = Database::new();
+        add_cities(&mut database);
+
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "SELECT city, COUNT(*), SUM(COUNT(*)) OVER () FROM Resident \
+                 GROUP BY city ORDER BY city;"
+            ),
+            vec![
+                vec![string("Oslo"), Value::Int(3), Value::Int(6)],
+                vec![string("Rome"), Value::Int(2), Value::Int(6)],
+                vec![Value::Null, Value::Int(1), Value::Int(6)],
+            ]
+        );
+        // the groups HAVING leaves out aren't in the window either
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "SELECT city, LAG(city) OVER (ORDER BY COUNT(*)) FROM Resident \
+                 GROUP BY city HAVING COUNT(*) > 1;"
+            ),
+            vec![
+                vec![string("Oslo"), string("Rome")],
+                vec![string("Rome"), Value::Null],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_select_case_expressions() {
+        let mut database = Database::new();
+        add_cities(&mut database);
+
+        assert_eq!(
+            run(
+                &mut database,
+                "SELECT name, CASE WHEN age >= 18 THEN 'adult' WHEN age < 18 THEN 'minor' END \
+                 FROM Resident WHERE CASE city WHEN 'Rome' THEN true ELSE age < 10 END \
+                 ORDER BY name;"
+            ),
+            Ok(QueryResult::Rows {
+                columns: vec![
+                    ColumnInfo {
+                        name: "name".to_string(),
+                        type_info: Some(SqlTypeInfo::String)
+                    },
+                    ColumnInfo {
+                        name: "case".to_string(),
+                        type_info: Some(SqlTypeInfo::String)
+                    },
+                ],
+                rows: vec![
+                    vec![string("Al"), string("minor")],
+                    vec![string("Bo"), Value::Null],
+                    vec![string("Di"), string("adult")],
+                    vec![string("Ed"), string("minor")],
+                ],
+            })
+        );
+        // the results of the clauses that don't match are never evaluated
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "SELECT CASE WHEN age = 20 THEN 0 ELSE age / (age - 20) END FROM Resident \
+                 WHERE city = 'Rome' ORDER BY name;"
+            ),
+            vec![vec![Value::Null], vec![Value::Int(0)]]
+        );
+        assert_eq!(
+            run(
+                &mut database,
+                "SELECT CASE WHEN age > 1 THEN age ELSE city END FROM Resident;"
+            ),
+            Err(ExecutionError::ResultTypeMismatch {
+                expression: "CASE".to_string(),
+                left: SqlTypeInfo::Int,
+                right: SqlTypeInfo::String
+            })
+        );
+    }
+
+    #[test]
+    fn test_select_conditional_functions_with_nulls() {
+        let mut database = Database::new();
+        add_cities(&mut database);
+
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "SELECT name, COALESCE(age, -1), NULLIF(city, 'Oslo'), GREATEST(age, 10), \
+                 LEAST(age, NULL, 10) FROM Resident WHERE age < 25 OR city = 'Rome' \
+                 ORDER BY name;"
+            ),
+            vec![
+                vec![
+                    string("Al"),
+                    Value::Int(7),
+                    Value::Null,
+                    Value::Int(10),
+                    Value::Int(7)
+                ],
+                vec![
+                    string("Bo"),
+                    Value::Int(-1),
+                    string("Rome"),
+                    Value::Int(10),
+                    Value::Int(10)
+                ],
+                vec![
+                    string("Di"),
+                    Value::Int(20),
+                    string("Rome"),
+                    Value::Int(20),
+                    Value::Int(10)
+                ],
+                vec![
+                    string("Ed"),
+                    Value::Int(5),
+                    Value::Null,
+                    Value::Int(10),
+                    Value::Int(5)
+                ],
+            ]
+        );
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "SELECT city, COALESCE(MAX(age), 0) FROM Resident GROUP BY city ORDER BY city;"
+            ),
+            vec![
+                vec![string("Oslo"), Value::Int(42)],
+                vec![string("Rome"), Value::Int(20)],
+                vec![Value::Null, Value::Int(5)],
+            ]
+        );
+        assert_eq!(
+            run(&mut database, "SELECT GREATEST(age, 1 = 1) FROM Resident;"),
+            Err(ExecutionError::IncomparableArguments {
+                function: ConditionalFunction::Greatest,
+                left: Value::Int(42),
+                right: Value::Boolean(true)
+            })
+        );
+    }
+
+    #[test]
+    fn test_quoted_and_qualified_names() {
+        let mut database = Database::new();
+        run(
+            &mut database,
+            "CREATE TABLE \"Order Details\" (order_id int, \"from\" string, `Unit$Price` int);",
+        )
+        .unwrap();
+        run(
+            &mut database,
+            "CREATE TABLE public.person (first_name string);",
+        )
+        .unwrap();
+        run(
+            &mut database,
+            "INSERT INTO \"Order Details\" VALUES (1, 'Oslo', 10), (2, 'Rome', 20);",
+        )
+        .unwrap();
+        run(&mut database, "INSERT INTO public.person VALUES ('Jo');").unwrap();
+        run(
+            &mut database,
+            "UPDATE \"Order Details\" SET `Unit$Price` = `Unit$Price` + 1 WHERE \"from\" = 'Rome';",
+        )
+        .unwrap();
+
+        assert_eq!(
+            run(
+                &mut database,
+                "SELECT d.order_id, \"from\" AS \"Ship From\", \"Unit$Price\", person.first_name \
+                 FROM \"Order Details\" d, public.person ORDER BY d.order_id;"
+            ),
+            Ok(QueryResult::Rows {
+                columns: vec![
+                    ColumnInfo {
+                        name: "order_id".to_string(),
+                        type_info: Some(SqlTypeInfo::Int)
+                    },
+                    ColumnInfo {
+                        name: "Ship From".to_string(),
+                        type_info: Some(SqlTypeInfo::String)
+                    },
+                    ColumnInfo {
+                        name: "Unit$Price".to_string(),
+                        type_info: Some(SqlTypeInfo::Int)
+                    },
+                    ColumnInfo {
+                        name: "first_name".to_string(),
+                        type_info: Some(SqlTypeInfo::String)
+                    },
+                ],
+                rows: vec![
+                    vec![Value::Int(1), string("Oslo"), Value::Int(10), string("Jo")],
+                    vec![Value::Int(2), string("Rome"), Value::Int(21), string("Jo")],
+                ],
+            })
+        );
+        // a qualified name is the name of another table
+        assert_eq!(
+            run(&mut database, "SELECT * FROM person;"),
+            Err(ExecutionError::TableNotFound("person".to_string()))
+        );
+        // its columns can be qualified by the whole name or its last parts
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "SELECT public.person.first_name, person.*, public.person.* FROM public.person \
+                 WHERE public.person.first_name = person.first_name;"
+            ),
+            vec![vec![string("Jo"), string("Jo"), string("Jo")]]
+        );
+        assert_eq!(
+            run(&mut database, "SELECT other.person.* FROM public.person;"),
+            Err(ExecutionError::TableNotInQuery("other.person".to_string()))
+        );
+        assert_eq!(
+            run(
+                &mut database,
+                "SELECT p.first_name FROM public.person p WHERE public.person.first_name = 'Jo';"
+            ),
+            Err(ExecutionError::TableNotInQuery("public.person".to_string()))
+        );
+        assert_eq!(
+            run(
+                &mut database,
+                "DROP TABLE public.person, \"Order Details\";"
+            ),
+            Ok(QueryResult::Dropped)
+        );
+    }
+
+    #[test]
+    fn test_dotted_quoted_name_is_not_a_qualified_name() {
+        let mut database = Database::new();
+        run(&mut database, "CREATE TABLE \"a.b\" (x int);").unwrap();
+        run(&mut database, "INSERT INTO \"a.b\" VALUES (1);").unwrap();
+
+        assert_eq!(
+            run(&mut database, "SELECT * FROM a.b;"),
+            Err(ExecutionError::TableNotFound("a.b".to_string()))
+        );
+        run(&mut database, "CREATE TABLE a.b (x int);").unwrap();
+        run(&mut database, "INSERT INTO a.b VALUES (2);").unwrap();
+
+        assert_eq!(
+            selected_rows(&mut database, "SELECT x FROM \"a.b\";"),
+            vec![vec![Value::Int(1)]]
+        );
+        assert_eq!(
+            selected_rows(&mut database, "SELECT x FROM a.b;"),
+            vec![vec![Value::Int(2)]]
+        );
+        assert!(database.table("\"a.b\"").is_some());
+        assert!(database.table("a.b").is_some());
+    }
+
+    #[test]
+    fn test_unquoted_names_are_case_insensitive() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut database = Database::open(dir.path()).unwrap();
+        run(&mut database, "CREATE TABLE Person (Name string);").unwrap();
+        run(&mut database, "INSERT INTO PERSON (NAME) VALUES ('Jo');").unwrap();
+
+        assert_eq!(
+            run(&mut database, "CREATE TABLE person (name string);"),
+            Err(ExecutionError::TableAlreadyExists("person".to_string()))
+        );
+        // quoted, the name is kept as written and names another table
+        run(&mut database, "CREATE TABLE \"Person\" (\"Name\" string);").unwrap();
+        run(&mut database, "INSERT INTO \"Person\" VALUES ('Al');").unwrap();
+        drop(database);
+
+        let mut database = Database::open(dir.path()).unwrap();
+        assert_eq!(
+            selected_rows(&mut database, "SELECT person.NAME FROM Person;"),
+            vec![vec![string("Jo")]]
+        );
+        assert_eq!(
+            selected_rows(&mut database, "SELECT \"Name\" FROM \"Person\";"),
+            vec![vec![string("Al")]]
+        );
+        assert_eq!(
+            run(&mut database, "SELECT Name FROM \"Person\";"),
+            Err(ExecutionError::ColumnNotFound {
+                table: "Person".to_string(),
+                column: "name".to_string()
+            })
+        );
+        assert!(database.table("person").is_some());
+        assert!(database.table("Person").is_some());
+    }
+
+    #[test]
+    fn test_delete_with_subquery_reads_rows_before_deleting() {
+        let mut database = Database::new();
+        add_cities(&mut database);
+
+        let result = run(
+            &mut database,
+            "DELETE FROM Resident WHERE age < (SELECT AVG(age) FROM Resident);",
+        );
+
+        assert_eq!(result, Ok(QueryResult::Affected(3)));
+    }
+
+    #[test]
+    fn test_select_with_where_clause() {
+        let mut database = people();
+
+        let rows = selected_rows(&mut database, "SELECT name FROM Person WHERE age > 10;");
+
+        assert_eq!(rows, vec![vec![Value::String("Jo".to_string())]]);
+    }
+
+    #[test]
+    fn test_select_unknown_column_is_an_error() {
+        let mut database = people();
+
+        let result = run(&mut database, "SELECT salary FROM Person;");
+
+        assert_eq!(
+            result,
+            Err(ExecutionError::ColumnNotFound {
+                table: "person".to_string(),
+                column: "salary".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_tables_survive_reopening() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let mut database = Database::open(dir.path()).unwrap();
+            add_people(&mut database);
+            run(&mut database, "CREATE TABLE City (name string);").unwrap();
+        }
+
+        let mut database = Database::open(dir.path()).unwrap();
+
+        assert_eq!(database.table("person").unwrap().columns().len(), 2);
+        assert_eq!(database.table("city").unwrap().file(), "1.heap");
+        assert_eq!(
+            selected_rows(&mut database, "SELECT name FROM Person WHERE age < 10;"),
+            vec![vec![Value::String("Al".to_string())]]
+        );
+        assert_eq!(
+            run(&mut database, "CREATE TABLE City (name string);"),
+            Err(ExecutionError::TableAlreadyExists("city".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_rows_spanning_many_pages_are_stored() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut database = Database::open(dir.path()).unwrap();
+        run(&mut database, "CREATE TABLE Log (line string, n int);").unwrap();
+        let line = "x".repeat(200);
+        for n in 0..100 {
+            run(
+                &mut database,
+                &format!("INSERT INTO Log VALUES ('{line}', {n});"),
+            )
+            .unwrap();
+        }
+        drop(database);
+
+        let mut database = Database::open(dir.path()).unwrap();
+        let rows = selected_rows(&mut database, "SELECT n FROM Log;");
+
+        assert_eq!(rows.len(), 100);
+        assert_eq!(rows[99], vec![Value::Int(99)]);
+        assert!(std::fs::metadata(dir.path().join("0.heap")).unwrap().len() > 4096);
+    }
+
+    #[test]
+    fn test_update_matching_rows() {
+        let mut database = people();
+
+        let result = run(
+            &mut database,
+            "UPDATE Person SET age = age + 1, name = 'Old ' WHERE age > 5;",
+        );
+
+        assert_eq!(result, Ok(QueryResult::Affected(2)));
+        assert_eq!(
+            selected_rows(&mut database, "SELECT name, age FROM Person;"),
+            vec![
+                vec![Value::String("Old ".to_string()), Value::Int(43)],
+                vec![Value::String("Old ".to_string()), Value::Int(8)],
+                vec![Value::String("Bo".to_string()), Value::Null],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_update_without_where_clause_changes_every_row() {
+        let mut database = people();
+
+        let result = run(&mut database, "UPDATE Person SET age = NULL;");
+
+        assert_eq!(result, Ok(QueryResult::Affected(3)));
+        assert!(selected_rows(&mut database, "SELECT age FROM Person;")
+            .iter()
+            .all(|row| row[0] == Value::Null));
+    }
+
+    #[test]
+    fn test_failed_update_changes_nothing() {
+        let mut database = people();
+
+        let result = run(&mut database, "UPDATE Person SET age = 10 / (age - 7);");
+
+        assert_eq!(result, Err(ExecutionError::DivisionByZero));
+        assert_eq!(
+            selected_rows(&mut database, "SELECT age FROM Person;"),
+            vec![vec![Value::Int(42)], vec![Value::Int(7)], vec![Value::Null]]
+        );
+    }
+
+    #[test]
+    fn test_update_with_wrong_type_is_an_error() {
+        let mut database = people();
+
+        let result = run(&mut database, "UPDATE Person SET age = name;");
+
+        assert_eq!(
+            result,
+            Err(ExecutionError::TypeMismatch {
+                column: "age".to_string(),
+                expected: SqlTypeInfo::Int,
+                found: Value::String("Jo".to_string())
+            })
+        );
+    }
+
+    #[test]
+    fn test_delete_matching_rows() {
+        let mut database = people();
+
+        let result = run(&mut database, "DELETE FROM Person WHERE age < 10;");
+
+        assert_eq!(result, Ok(QueryResult::Affected(1)));
+        assert_eq!(
+            selected_rows(&mut database, "SELECT name FROM Person;"),
+            vec![
+                vec![Value::String("Jo".to_string())],
+                vec![Value::String("Bo".to_string())]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_delete_where_is_null() {
+        let mut database = Database::new();
+        add_cities(&mut database);
+
+        let result = run(&mut database, "DELETE FROM Resident WHERE age IS NULL;");
+
+        assert_eq!(result, Ok(QueryResult::Affected(1)));
+        assert_eq!(
+            selected_rows(
+                &mut database,
+                "SELECT name FROM Resident WHERE city IS NOT NULL ORDER BY name;"
+            ),
+            vec![
+                vec![string("Al")],
+                vec![string("Cy")],
+                vec![string("Di")],
+                vec![string("Jo")],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_delete_without_where_clause_empties_the_table() {
+        let mut database = people();
+
+        let result = run(&mut database, "DELETE FROM Person;");
+
+        assert_eq!(result, Ok(QueryResult::Affected(3)));
+        assert!(selected_rows(&mut database, "SELECT name FROM Person;").is_empty());
+        assert_eq!(
+            run(&mut database, "DELETE FROM Person;"),
+            Ok(QueryResult::Affected(0))
+        );
+    }
+
+    #[test]
+    fn test_create_table_if_not_exists() {
+        let mut database = people();
+
+        let result = run(
+            &mut database,
+            "CREATE TABLE IF NOT EXISTS Person (other string);",
+        );
+
+        assert_eq!(result, Ok(QueryResult::Created));
+        assert_eq!(database.table("person").unwrap().columns().len(), 2);
+    }
+
+    #[test]
+    fn test_drop_tables() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut database = Database::open(dir.path()).unwrap();
+        add_people(&mut database);
+        run(&mut database, "CREATE TABLE City (name string);").unwrap();
+
+        let result = run(&mut database, "DROP TABLE Person, City;");
+
+        assert_eq!(result, Ok(QueryResult::Dropped));
+        assert!(database.catalog().table("person").is_none());
+        assert!(!dir.path().join("0.heap").exists());
+        assert!(Database::open(dir.path()).unwrap().table("city").is_none());
+        assert_eq!(
+            run(&mut database, "SELECT name FROM Person;"),
+            Err(ExecutionError::TableNotFound("person".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_drop_missing_table() {
+        let mut database = people();
+
+        assert_eq!(
+            run(&mut database, "DROP TABLE Person, City;"),
+            Err(ExecutionError::TableNotFound("city".to_string()))
+        );
+        assert!(database.table("person").is_some());
+        assert_eq!(
+            run(&mut database, "DROP TABLE IF EXISTS City, Person;"),
+            Ok(QueryResult::Dropped)
+        );
+        assert!(database.table("person").is_none());
+    }
+
+    #[test]
+    fn test_truncate_tables() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut database = Database::open(dir.path()).unwrap();
+        add_people(&mut database);
+
+        let result = run(&mut database, "TRUNCATE TABLE IF EXISTS Person, City;");
+
+        assert_eq!(result, Ok(QueryResult::Truncated));
+        assert_eq!(
+            std::fs::metadata(dir.path().join("0.heap")).unwrap().len(),
+            0
+        );
+        assert!(selected_rows(&mut database, "SELECT name FROM Person;").is_empty());
+        run(&mut database, "INSERT INTO Person VALUES ('Jo', 1);").unwrap();
+        assert_eq!(
+            selected_rows(&mut database, "SELECT name FROM Person;").len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_add_column_reads_null_for_existing_rows() {
+        let mut database = people();
+
+        let result = run(&mut database, "ALTER TABLE Person ADD COLUMN city string;");
+        run(
+            &mut database,
+            "INSERT INTO Person VALUES ('Cy', 3, 'Oslo');",
+        )
+        .unwrap();
+
+        assert_eq!(result, Ok(QueryResult::Altered));
+        assert_eq!(
+            selected_rows(&mut database, "SELECT city FROM Person;"),
+            vec![
+                vec![Value::Null],
+                vec![Value::Null],
+                vec![Value::Null],
+                vec![Value::String("Oslo".to_string())]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_drop_column_rewrites_rows() {
+        let mut database = people();
+
+        run(&mut database, "ALTER TABLE Person DROP COLUMN name;").unwrap();
+
+        assert_eq!(database.table("person").unwrap().columns().len(), 1);
+        assert_eq!(
+            selected_rows(&mut database, "SELECT age FROM Person;"),
+            vec![vec![Value::Int(42)], vec![Value::Int(7)], vec![Value::Null]]
+        );
+    }
+
+    #[test]
+    fn test_renames() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut database = Database::open(dir.path()).unwrap();
+        add_people(&mut database);
+
+        run(
+            &mut database,
+            "ALTER TABLE Person RENAME COLUMN age TO years;",
+        )
+        .unwrap();
+        run(&mut database, "ALTER TABLE Person RENAME TO People;").unwrap();
+        let mut database = Database::open(dir.path()).unwrap();
+
+        assert!(database.table("person").is_none());
+        assert_eq!(
+            selected_rows(&mut database, "SELECT years FROM People WHERE name = 'Jo';"),
+            vec![vec![Value::Int(42)]]
+        );
+        assert_eq!(
+            run(&mut database, "ALTER TABLE People RENAME name TO years;"),
+            Err(ExecutionError::ColumnAlreadyExists {
+                table: "people".to_string(),
+                column: "years".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_alter_column_type_converts_values() {
+        let mut database = people();
+
+        run(
+            &mut database,
+            "ALTER TABLE Person ALTER COLUMN age TYPE string;",
+        )
+        .unwrap();
+        assert_eq!(
+            selected_rows(&mut database, "SELECT age FROM Person;"),
+            vec![
+                vec![Value::String("42".to_string())],
+                vec![Value::String("7".to_string())],
+                vec![Value::Null]
+            ]
+        );
+
+        run(
+            &mut database,
+            "ALTER TABLE Person ALTER COLUMN age TYPE int;",
+        )
+        .unwrap();
+        assert_eq!(
+            selected_rows(&mut database, "SELECT age FROM Person;"),
+            vec![vec![Value::Int(42)], vec![Value::Int(7)], vec![Value::Null]]
+        );
+    }
+
+    #[test]
+    fn test_alter_table_failing_to_save_the_catalog_changes_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut database = Database::open(dir.path()).unwrap();
+        add_people(&mut database);
+        // the new catalog can't be written where a directory is in the way
+        let blocker = dir.path().join("catalog.json.tmp");
+        fs::create_dir(&blocker).unwrap();
+
+        for raw in [
+            "ALTER TABLE Person RENAME TO People;",
+            "ALTER TABLE Person ADD COLUMN city string;",
+        ] {
+            assert!(matches!(
+                run(&mut database, raw),
+                Err(ExecutionError::Storage(_))
+            ));
+        }
+
+        assert!(database.table("people").is_none());
+        assert_eq!(database.table("person").unwrap().columns().len(), 2);
+        assert_eq!(database.catalog().table("person").unwrap().columns.len(), 2);
+        fs::remove_dir(&blocker).unwrap();
+        run(&mut database, "CREATE TABLE City (name string);").unwrap();
+        drop(database);
+
+        let mut database = Database::open(dir.path()).unwrap();
+        assert_eq!(
+            selected_rows(&mut database, "SELECT name FROM Person WHERE age < 10;"),
+            vec![vec![Value::String("Al".to_string())]]
+        );
+    }
+
+    #[test]
+    fn test_rewriting_rows_failing_to_save_the_catalog_keeps_the_old_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut database = Database::open(dir.path()).unwrap();
+        add_people(&mut database);
+        let heap_files = || {
+            let mut files: Vec<_> = fs::read_dir(dir.path())
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+                .filter(|name| name.ends_with(".heap"))
+                .collect();
+            files.sort();
+            files
+        };
+        let before = heap_files();
+        let blocker = dir.path().join("catalog.json.tmp");
+        fs::create_dir(&blocker).unwrap();
+
+        for raw in [
+            "ALTER TABLE Person DROP COLUMN name;",
+            "ALTER TABLE Person ALTER COLUMN age TYPE string;",
+        ] {
+            assert!(matches!(
+                run(&mut database, raw),
+                Err(ExecutionError::Storage(_))
+            ));
+        }
+
+        // the rewritten rows went to files that are gone again
+        assert_eq!(heap_files(), before);
+        let rows = "SELECT name, age FROM Person ORDER BY name;";
+        let expected = vec![
+            vec![string("Al"), Value::Int(7)],
+            vec![string("Bo"), Value::Null],
+            vec![string("Jo"), Value::Int(42)],
+        ];
+        assert_eq!(selected_rows(&mut database, rows), expected);
+        fs::remove_dir(&blocker).unwrap();
+        drop(database);
+
+        let mut database = Database::open(dir.path()).unwrap();
+        assert_eq!(selected_rows(&mut database, rows), expected);
+        run(&mut database, "ALTER TABLE Person DROP COLUMN name;").unwrap();
+        assert_eq!(heap_files().len(), before.len());
+        assert_eq!(
+            selected_rows(&mut database, "SELECT * FROM Person ORDER BY age;"),
+            vec![vec![Value::Int(7)], vec![Value::Int(42)], vec![Value::Null]]
+        );
+    }
+
+    #[test]
+    fn test_failed_type_change_changes_nothing() {
+        let mut database = people();
+
+        let result = run(
+            &mut database,
+            "ALTER TABLE Person ALTER COLUMN name TYPE int;",
+        );
+
+        assert_eq!(
+            result,
+            Err(ExecutionError::CannotConvert {
+                column: "name".to_string(),
+                value: Value::String("Jo".to_string()),
+                to: SqlTypeInfo::Int
+            })
+        );
+        assert_eq!(
+            database.table("person").unwrap().columns()[0].type_info,
+            SqlTypeInfo::String
+        );
+    }
+}
@@ -0,0 +1,181 @@
+use miette::Diagnostic;
+use sql_jr_parser::{
+    create_statement::SqlTypeInfo,
+    expression::{AggregateFunction, BinaryOperator, ConditionalFunction, UnaryOperator},
+    query_expression::SetOperator,
+    value::Value,
+};
+use thiserror::Error;
+
+use crate::storage::StorageError;
+
+/// Errors raised while running a query against a [`Database`](crate::database::Database)
+#[derive(Debug, Error, Diagnostic, PartialEq)]
+pub enum ExecutionError {
+    #[error("table {0} already exists")]
+    TableAlreadyExists(String),
+
+    #[error("table {0} does not exist")]
+    TableNotFound(String),
+
+    #[error("column {column} does not exist in table {table}")]
+    ColumnNotFound { table: String, column: String },
+
+    #[error("table {0} is not in the FROM clause")]
+    TableNotInQuery(String),
+
+    #[error("column {0} does not exist in any table of the FROM clause")]
+    ColumnNotInQuery(String),
+
+    #[error("column reference {0} is ambiguous")]
+    AmbiguousColumn(String),
+
+    #[error("table name {0} is specified more than once")]
+    DuplicateTableReference(String),
+
+    #[error("ORDER BY position {0} is not in the select list")]
+    OrderByPositionOutOfRange(i64),
+
+    #[error("DISTINCT ON position {0} is not in the select list")]
+    DistinctOnPositionOutOfRange(i64),
+
+    #[error("for SELECT DISTINCT, ORDER BY expressions must appear in the select list")]
+    DistinctOrderBy,
+
+    #[error("SELECT DISTINCT ON expressions must match initial ORDER BY expressions")]
+    DistinctOnOrderBy,
+
+    #[error("each {0} query must have the same number of columns")]
+    SetOperationColumnCount(SetOperator),
+
+    #[error("{op} types {left} and {right} cannot be matched")]
+    SetOperationTypeMismatch {
+        op: SetOperator,
+        left: SqlTypeInfo,
+        right: SqlTypeInfo,
+    },
+
+    #[error("ORDER BY of a {0} can only use result column names and positions")]
+    SetOperationOrderBy(SetOperator),
+
+    #[error(
+        "ORDER BY of a sorted query in parentheses can only use result column names and positions"
+    )]
+    NestedOrderBy,
+
+    #[error("column {column} already exists in table {table}")]
+    ColumnAlreadyExists { table: String, column: String },
+
+    #[error("column {0} is given more than once")]
+    DuplicateColumn(String),
+
+    #[error("table {table} has {expected} columns but {found} values were given")]
+    ValueCountMismatch {
+        table: String,
+        expected: usize,
+        found: usize,
+    },
+
+    #[error("column {column} is of type {expected} but the value {found} was given")]
+    TypeMismatch {
+        column: String,
+        expected: SqlTypeInfo,
+        found: Value,
+    },
+
+    #[error("cannot apply {op} to {left} and {right}")]
+    InvalidOperands {
+        op: BinaryOperator,
+        left: Value,
+        right: Value,
+    },
+
+    #[error("cannot apply {op} to {operand}")]
+    InvalidOperand { op: UnaryOperator, operand: Value },
+
+    #[error("cannot convert {value} in column {column} to {to}")]
+    CannotConvert {
+        column: String,
+        value: Value,
+        to: SqlTypeInfo,
+    },
+
+    #[error("cannot apply {function} to {value}")]
+    InvalidAggregate {
+        function: AggregateFunction,
+        value: Value,
+    },
+
+    #[error("aggregate functions are only allowed in the select list, HAVING and ORDER BY")]
+    AggregateNotAllowed,
+
+    #[error("{expression} types {left} and {right} cannot be matched")]
+    ResultTypeMismatch {
+        expression: String,
+        left: SqlTypeInfo,
+        right: SqlTypeInfo,
+    },
+
+    #[error("{function} takes {expected} arguments but {found} were given")]
+    ArgumentCount {
+        function: ConditionalFunction,
+        expected: usize,
+        found: usize,
+    },
+
+    #[error("cannot compare {left} and {right} in {function}")]
+    IncomparableArguments {
+        function: ConditionalFunction,
+        left: Value,
+        right: Value,
+    },
+
+    #[error("window functions are only allowed in the select list and ORDER BY")]
+    WindowNotAllowed,
+
+    #[error("LAG and LEAD offsets must be integers, not {0}")]
+    InvalidWindowOffset(Value),
+
+    #[error("RANGE with an offset PRECEDING or FOLLOWING needs a single numeric ORDER BY key")]
+    RangeFrameOffset,
+
+    #[error("column {0} must appear in the GROUP BY clause or be used in an aggregate function")]
+    UngroupedColumn(String),
+
+    #[error("subquery must return only one column")]
+    SubqueryColumnCount,
+
+    #[error(
+        "WITH query {name} has {available} columns available but {specified} columns specified"
+    )]
+    CteColumnCount {
+        name: String,
+        available: usize,
+        specified: usize,
+    },
+
+    #[error(
+        "recursive query {0} must be of the form non-recursive query UNION [ALL] recursive query"
+    )]
+    RecursiveCteForm(String),
+
+    #[error("recursive query {name} still returned rows after {limit} iterations")]
+    RecursionLimitExceeded { name: String, limit: usize },
+
+    #[error("more than one row returned by a subquery used as an expression")]
+    SubqueryTooManyRows,
+
+    #[error("WHERE clause must be a boolean but evaluated to {0}")]
+    NonBooleanPredicate(Value),
+
+    #[error("division by zero")]
+    DivisionByZero,
+
+    #[error("integer overflow")]
+    Overflow,
+
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+}
+
+pub type ExecutionResult<T> = Result<T, ExecutionError>;
@@ -0,0 +1,650 @@
+use std::cmp::Ordering;
+
+use sql_jr_parser::{
+    create_statement::SqlTypeInfo,
+    expression::{
+        AggregateArg, AggregateFunction, BinaryOperator, ConditionalFunction, Expression,
+        UnaryOperator,
+    },
+    ident::Ident,
+    value::Value,
+    window::WindowFunction,
+};
+
+use crate::{
+    error::{ExecutionError, ExecutionResult},
+    scope::Scope,
+    select::{Executor, QueryRows},
+    table::Row,
+};
+
+/// The row an expression is evaluated against, together with the columns it is made of
+pub(crate) struct RowContext<'a> {
+    pub scope: &'a Scope<'a>,
+    pub row: &'a [Value],
+    /// The values of the aggregate calls over the row's group, when rows are grouped,
+    /// and of the window function calls over the row's window
+    pub aggregates: &'a [(&'a Expression, Value)],
+    /// The row of the query a subquery is nested in, for the columns of `scope`'s
+    /// outer scope
+    pub outer: Option<&'a RowContext<'a>>,
+    /// Runs the subqueries of the expression
+    pub executor: &'a Executor<'a>,
+}
+
+/// Evaluate `expression` for a single row
+pub(crate) fn evaluate(expression: &Expression, context: &RowContext) -> ExecutionResult<Value> {
+    match expression {
+        Expression::Column(column) => {
+            let (depth, index) = context.scope.locate(column)?;
+            let mut context = context;
+            for _ in 0..depth {
+                context = context.outer.expect("every outer scope has a row");
+            }
+            Ok(context.row[index].clone())
+        }
+        Expression::Literal(literal) => Ok(literal.value.clone()),
+        // computed over the whole group beforehand
+        Expression::Aggregate { .. } => context
+            .aggregates
+            .iter()
+            .find(|(aggregate, _)| *aggregate == expression)
+            .map(|(_, value)| value.clone())
+            .ok_or(ExecutionError::AggregateNotAllowed),
+        Expression::Case {
+            operand,
+            when_clauses,
+            else_result,
+            ..
+        } => {
+            let operand = match operand {
+                Some(operand) => Some(evaluate(operand, context)?),
+                None => None,
+            };
+            // only the result of the first clause that matches is evaluated
+            for clause in when_clauses {
+                let matched = match &operand {
+                    Some(operand) => {
+                        let value = evaluate(&clause.condition, context)?;
+                        evaluate_binary(BinaryOperator::Eq, operand.clone(), value)?
+                            == Value::Boolean(true)
+                    }
+                    None => is_match(&clause.condition, context)?,
+                };
+                if matched {
+                    return evaluate(&clause.result, context);
+                }
+            }
+            match else_result {
+                Some(result) => evaluate(result, context),
+                None => Ok(Value::Null),
+            }
+        }
+        Expression::Conditional { function, args, .. } => {
+            evaluate_conditional(*function, args, context)
+        }
+        // computed over the whole window beforehand, once the rows are filtered
+        Expression::Window { .. } => context
+            .aggregates
+            .iter()
+            .find(|(window, _)| *window == expression)
+            .map(|(_, value)| value.clone())
+            .ok_or(ExecutionError::WindowNotAllowed),
+        Expression::Unary { op, expr, .. } => evaluate_unary(*op, evaluate(expr, context)?),
+        Expression::IsNull { expr, negated, .. } => {
+            let is_null = matches!(evaluate(expr, context)?, Value::Null);
+            Ok(Value::Boolean(is_null != *negated))
+        }
+        Expression::Binary {
+            left, op, right, ..
+        } => {
+            let left = evaluate(left, context)?;
+            // AND and OR only look at their right operand when the left one doesn't decide
+            match (op, &left) {
+                (BinaryOperator::And, Value::Boolean(false)) => Ok(Value::Boolean(false)),
+                (BinaryOperator::Or, Value::Boolean(true)) => Ok(Value::Boolean(true)),
+                _ => evaluate_binary(*op, left, evaluate(right, context)?),
+            }
+        }
+        Expression::Subquery { subquery, .. } => {
+            let result = context.executor.subquery(subquery, context)?;
+            match single_column(&result)? {
+                [] => Ok(Value::Null),
+                [row] => Ok(row[0].clone()),
+                _ => Err(ExecutionError::SubqueryTooManyRows),
+            }
+        }
+        Expression::Exists { subquery, .. } => {
+            let (_, rows) = &*context.executor.subquery(subquery, context)?;
+            Ok(Value::Boolean(!rows.is_empty()))
+        }
+        Expression::InSubquery {
+            expr,
+            subquery,
+            negated,
+            ..
+        } => {
+            let value = evaluate(expr, context)?;
+            let result = context.executor.subquery(subquery, context)?;
+            // true when a row equals the value, else NULL when a comparison was NULL
+            let mut found = Value::Boolean(false);
+            for row in single_column(&result)? {
+                let equal = evaluate_binary(BinaryOperator::Eq, value.clone(), row[0].clone())?;
+                found = evaluate_binary(BinaryOperator::Or, found, equal)?;
+                if found == Value::Boolean(true) {
+                    break;
+                }
+            }
+            match negated {
+                true => evaluate_unary(UnaryOperator::Not, found),
+                false => Ok(found),
+            }
+        }
+    }
+}
+
+// COALESCE stops at the first argument that isn't NULL, the other functions need them all
+fn evaluate_conditional(
+    function: ConditionalFunction,
+    args: &[Expression],
+    context: &RowContext,
+) -> ExecutionResult<Value> {
+    let mut values = args.iter().map(|arg| evaluate(arg, context));
+    match function {
+        ConditionalFunction::Coalesce => {
+            for value in values {
+                let value = value?;
+                if value != Value::Null {
+                    return Ok(value);
+                }
+            }
+            Ok(Value::Null)
+        }
+        ConditionalFunction::NullIf => {
+            let (Some(left), Some(right), None) = (values.next(), values.next(), values.next())
+            else {
+                return Err(ExecutionError::ArgumentCount {
+                    function,
+                    expected: 2,
+                    found: args.len(),
+                });
+            };
+            let left = left?;
+            match evaluate_binary(BinaryOperator::Eq, left.clone(), right?)? {
+                Value::Boolean(true) => Ok(Value::Null),
+                _ => Ok(left),
+            }
+        }
+        ConditionalFunction::Greatest | ConditionalFunction::Least => {
+            let keep = match function {
+                ConditionalFunction::Greatest => Ordering::Greater,
+                _ => Ordering::Less,
+            };
+            // NULL arguments are ignored, the result is only NULL when they all are
+            let mut result = Value::Null;
+            for value in values {
+                let value = value?;
+                if value == Value::Null {
+                    continue;
+                }
+                if result == Value::Null {
+                    result = value;
+                    continue;
+                }
+                match compare_values(&value, &result) {
+                    Some(ordering) if ordering == keep => result = value,
+                    Some(_) => {}
+                    None => {
+                        return Err(ExecutionError::IncomparableArguments {
+                            function,
+                            left: result,
+                            right: value,
+                        })
+                    }
+                }
+            }
+            Ok(result)
+        }
+    }
+}
+
+// the rows of a subquery used as a value, which must return a single column
+fn single_column(result: &QueryRows) -> ExecutionResult<&[Row]> {
+    let (columns, rows) = result;
+    if columns.len() != 1 {
+        return Err(ExecutionError::SubqueryColumnCount);
+    }
+    Ok(rows)
+}
+
+/// Check that every column `expression` refers to is exactly one of the columns of
+/// `scope`, and that the results of its CASEs and conditional functions can be matched
+pub(crate) fn check_columns(expression: &Expression, scope: &Scope) -> ExecutionResult<()> {
+    match expression {
+        Expression::Column(column) => {
+            scope.locate(column)?;
+            Ok(())
+        }
+        // checked when they run, against their own tables
+        Expression::Subquery { .. } | Expression::Exists { .. } => Ok(()),
+        Expression::InSubquery { expr, .. } => check_columns(expr, scope),
+        Expression::Literal(_)
+        | Expression::Aggregate {
+            arg: AggregateArg::Wildcard,
+            ..
+        } => Ok(()),
+        Expression::Aggregate {
+            arg: AggregateArg::Expression(expr),
+            ..
+        }
+        | Expression::Unary { expr, .. }
+        | Expression::IsNull { expr, .. } => check_columns(expr, scope),
+        Expression::Binary { left, right, .. } => {
+            check_columns(left, scope)?;
+            check_columns(right, scope)
+        }
+        Expression::Case { .. } | Expression::Conditional { .. } => {
+            for operand in expression.conditional_operands() {
+                check_columns(operand, scope)?;
+            }
+            result_type(expression, scope).map_err(|(left, right)| {
+                ExecutionError::ResultTypeMismatch {
+                    expression: conditional_name(expression),
+                    left,
+                    right,
+                }
+            })?;
+            Ok(())
+        }
+        Expression::Window {
+            function, window, ..
+        } => function
+            .args()
+            .into_iter()
+            .chain(window.expressions())
+            .try_for_each(|expr| check_columns(expr, scope)),
+    }
+}
+
+/// Name of the result column computed by `expression`: its alias, else the name of the
+/// column it is, else `?column?`
+pub(crate) fn output_name(expression: &Expression, alias: Option<&Ident>) -> String {
+    match (alias, expression) {
+        (Some(alias), _) => alias.to_string(),
+        (None, Expression::Column(column)) => column.column.to_string(),
+        (None, Expression::Aggregate { function, .. }) => function.to_string().to_lowercase(),
+        (None, Expression::Window { function, .. }) => function.to_string().to_lowercase(),
+        (None, Expression::Case { .. }) => "case".to_string(),
+        (None, Expression::Conditional { function, .. }) => function.to_string().to_lowercase(),
+        (None, _) => "?column?".to_string(),
+    }
+}
+
+/// The type of the values `expression` evaluates to, when it is known to be one of
+/// the column types
+pub(crate) fn expression_type(expression: &Expression, scope: &Scope) -> Option<SqlTypeInfo> {
+    match expression {
+        Expression::Column(column) => scope
+            .locate(column)
+            .ok()
+            .and_then(|(depth, index)| scope.level(depth).columns()[index].type_info.clone()),
+        Expression::Literal(literal) => match literal.value {
+            Value::Int(_) => Some(SqlTypeInfo::Int),
+            Value::String(_) => Some(SqlTypeInfo::String),
+            _ => None,
+        },
+        Expression::Unary {
+            op: UnaryOperator::Minus,
+            expr,
+            ..
+        } => expression_type(expr, scope),
+        Expression::Unary { .. } | Expression::IsNull { .. } => None,
+        Expression::Binary {
+            left,
+            op:
+                BinaryOperator::Plus
+                | BinaryOperator::Minus
+                | BinaryOperator::Multiply
+                | BinaryOperator::Divide,
+            right,
+            ..
+        } => match (expression_type(left, scope), expression_type(right, scope)) {
+            (Some(SqlTypeInfo::Int), Some(SqlTypeInfo::Int)) => Some(SqlTypeInfo::Int),
+            _ => None,
+        },
+        Expression::Binary { .. } => None,
+        Expression::Aggregate { function, arg, .. } => aggregate_type(*function, arg, scope),
+        Expression::Window { function, .. } => match function {
+            WindowFunction::RowNumber | WindowFunction::Rank | WindowFunction::DenseRank => {
+                Some(SqlTypeInfo::Int)
+            }
+            // the default is only known to be of the same type when it is missing
+            WindowFunction::Lag(args) | WindowFunction::Lead(args) => {
+                let type_info = expression_type(&args.expr, scope);
+                match &args.default {
+                    Some(default) if expression_type(default, scope) != type_info => None,
+                    _ => type_info,
+                }
+            }
+            WindowFunction::FirstValue(expr) => expression_type(expr, scope),
+            WindowFunction::Aggregate { function, arg, .. } => {
+                aggregate_type(*function, arg, scope)
+            }
+        },
+        Expression::Case { .. } | Expression::Conditional { .. } => {
+            result_type(expression, scope).ok().flatten()
+        }
+        Expression::Subquery { .. } | Expression::Exists { .. } | Expression::InSubquery { .. } => {
+            None
+        }
+    }
+}
+
+/// The type of the values a CASE or a conditional function call results in: the type
+/// of those of its results whose type is known, which must all be the same. The error
+/// holds the first two types that differ
+pub(crate) fn result_type(
+    expression: &Expression,
+    scope: &Scope,
+) -> Result<Option<SqlTypeInfo>, (SqlTypeInfo, SqlTypeInfo)> {
+    let mut unified: Option<SqlTypeInfo> = None;
+    for result in expression.conditional_results() {
+        match (&unified, expression_type(result, scope)) {
+            (Some(left), Some(right)) if *left != right => return Err((left.clone(), right)),
+            (None, type_info) => unified = type_info,
+            _ => {}
+        }
+    }
+    Ok(unified)
+}
+
+/// How a CASE or a conditional function call is named in errors
+pub(crate) fn conditional_name(expression: &Expression) -> String {
+    match expression {
+        Expression::Conditional { function, .. } => function.to_string(),
+        _ => "CASE".to_string(),
+    }
+}
+
+fn aggregate_type(
+    function: AggregateFunction,
+    arg: &AggregateArg,
+    scope: &Scope,
+) -> Option<SqlTypeInfo> {
+    match (function, arg) {
+        (AggregateFunction::Count, _) => Some(SqlTypeInfo::Int),
+        (
+            AggregateFunction::Sum | AggregateFunction::Min | AggregateFunction::Max,
+            AggregateArg::Expression(expr),
+        ) => expression_type(expr, scope),
+        _ => None,
+    }
+}
+
+/// Evaluate a WHERE predicate, a row only matches when it is true (not false or NULL)
+pub(crate) fn is_match(predicate: &Expression, context: &RowContext) -> ExecutionResult<bool> {
+    match evaluate(predicate, context)? {
+        Value::Boolean(matches) => Ok(matches),
+        Value::Null => Ok(false),
+        other => Err(ExecutionError::NonBooleanPredicate(other)),
+    }
+}
+
+fn evaluate_unary(op: UnaryOperator, operand: Value) -> ExecutionResult<Value> {
+    match (op, operand) {
+        (_, Value::Null) => Ok(Value::Null),
+        (UnaryOperator::Not, Value::Boolean(b)) => Ok(Value::Boolean(!b)),
+        (UnaryOperator::Minus, Value::Int(i)) => i
+            .checked_neg()
+            .map(Value::Int)
+            .ok_or(ExecutionError::Overflow),
+        (UnaryOperator::Minus, Value::Float(f)) => Ok(Value::Float(-f)),
+        (op, operand) => Err(ExecutionError::InvalidOperand { op, operand }),
+    }
+}
+
+pub(crate) fn evaluate_binary(
+    op: BinaryOperator,
+    left: Value,
+    right: Value,
+) -> ExecutionResult<Value> {
+    use BinaryOperator::*;
+
+    match op {
+        And | Or => evaluate_logical(op, left, right),
+        Eq | NotEq | Lt | LtEq | Gt | GtEq => evaluate_comparison(op, left, right),
+        Plus | Minus | Multiply | Divide => evaluate_arithmetic(op, left, right),
+    }
+}
+
+// three valued logic: NULL is "unknown", so `NULL AND false` is false but `NULL AND true` is NULL
+fn evaluate_logical(op: BinaryOperator, left: Value, right: Value) -> ExecutionResult<Value> {
+    let as_bool = |value: &Value| match value {
+        Value::Boolean(b) => Ok(Some(*b)),
+        Value::Null => Ok(None),
+        _ => Err(ExecutionError::InvalidOperands {
+            op,
+            left: left.clone(),
+            right: right.clone(),
+        }),
+    };
+    let (l, r) = (as_bool(&left)?, as_bool(&right)?);
+
+    let result = match op {
+        BinaryOperator::And => match (l, r) {
+            (Some(false), _) | (_, Some(false)) => Some(false),
+            (Some(true), Some(true)) => Some(true),
+            _ => None,
+        },
+        _ => match (l, r) {
+            (Some(true), _) | (_, Some(true)) => Some(true),
+            (Some(false), Some(false)) => Some(false),
+            _ => None,
+        },
+    };
+    Ok(result.map_or(Value::Null, Value::Boolean))
+}
+
+/// Order two non NULL values of compatible types, ints and floats compare with each other
+pub(crate) fn compare_values(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Int(l), Value::Int(r)) => Some(l.cmp(r)),
+        (Value::Int(l), Value::Float(r)) => (*l as f64).partial_cmp(r),
+        (Value::Float(l), Value::Int(r)) => l.partial_cmp(&(*r as f64)),
+        (Value::Float(l), Value::Float(r)) => l.partial_cmp(r),
+        (Value::String(l), Value::String(r)) => Some(l.cmp(r)),
+        (Value::Boolean(l), Value::Boolean(r)) => Some(l.cmp(r)),
+        _ => None,
+    }
+}
+
+fn evaluate_comparison(op: BinaryOperator, left: Value, right: Value) -> ExecutionResult<Value> {
+    if left == Value::Null || right == Value::Null {
+        return Ok(Value::Null);
+    }
+    let Some(ordering) = compare_values(&left, &right) else {
+        return Err(ExecutionError::InvalidOperands { op, left, right });
+    };
+
+    let result = match op {
+        BinaryOperator::Eq => ordering.is_eq(),
+        BinaryOperator::NotEq => ordering.is_ne(),
+        BinaryOperator::Lt => ordering.is_lt(),
+        BinaryOperator::LtEq => ordering.is_le(),
+        BinaryOperator::Gt => ordering.is_gt(),
+        _ => ordering.is_ge(),
+    };
+    Ok(Value::Boolean(result))
+}
+
+fn evaluate_arithmetic(op: BinaryOperator, left: Value, right: Value) -> ExecutionResult<Value> {
+    match (&left, &right) {
+        (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+        (Value::Int(l), Value::Int(r)) => {
+            let result = match op {
+                BinaryOperator::Plus => l.checked_add(*r),
+                BinaryOperator::Minus => l.checked_sub(*r),
+                BinaryOperator::Multiply => l.checked_mul(*r),
+                _ if *r == 0 => return Err(ExecutionError::DivisionByZero),
+                _ => l.checked_div(*r),
+            };
+            result.map(Value::Int).ok_or(ExecutionError::Overflow)
+        }
+        (Value::Int(_) | Value::Float(_), Value::Int(_) | Value::Float(_)) => {
+            let (l, r) = (as_float(&left), as_float(&right));
+            let result = match op {
+                BinaryOperator::Plus => l + r,
+                BinaryOperator::Minus => l - r,
+                BinaryOperator::Multiply => l * r,
+                _ if r == 0.0 => return Err(ExecutionError::DivisionByZero),
+                _ => l / r,
+            };
+            Ok(Value::Float(result))
+        }
+        _ => Err(ExecutionError::InvalidOperands { op, left, right }),
+    }
+}
+
+pub(crate) fn as_float(value: &Value) -> f64 {
+    match value {
+        Value::Int(i) => *i as f64,
+        Value::Float(f) => *f,
+        _ => unreachable!("only called with numeric values"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use sql_jr_parser::{
+        create_statement::Column,
+        types::{Parse, Span},
+    };
+
+    use super::*;
+    use crate::select::TableRows;
+
+    fn scope() -> Scope<'static> {
+        let columns = [
+            Column {
+                name: "name".into(),
+                type_info: SqlTypeInfo::String,
+                span: Span::default(),
+            },
+            Column {
+                name: "age".into(),
+                type_info: SqlTypeInfo::Int,
+                span: Span::default(),
+            },
+        ];
+        Scope::table(&"person".into(), &columns)
+    }
+
+    fn evaluate_raw(raw: &str) -> ExecutionResult<Value> {
+        evaluate_in_row(&Expression::parse_from_raw(raw).unwrap().1)
+    }
+
+    // `expression` for a row where `name` is 'Jo' and `age` 42
+    fn evaluate_in_row(expression: &Expression) -> ExecutionResult<Value> {
+        let scope = scope();
+        let row = vec![Value::String("Jo".to_string()), Value::Int(42)];
+        let tables = TableRows::new();
+        let uncorrelated = HashSet::new();
+        let executor = Executor::new(&tables, &uncorrelated);
+        let context = RowContext {
+            scope: &scope,
+            row: &row,
+            aggregates: &[],
+            outer: None,
+            executor: &executor,
+        };
+
+        evaluate(expression, &context)
+    }
+
+    #[test]
+    fn test_evaluate_column_and_arithmetic() {
+        assert_eq!(evaluate_raw("age * 2 + 1"), Ok(Value::Int(85)));
+        assert_eq!(evaluate_raw("age / 4.0"), Ok(Value::Float(10.5)));
+        assert_eq!(evaluate_raw("-age"), Ok(Value::Int(-42)));
+    }
+
+    #[test]
+    fn test_evaluate_qualified_column() {
+        assert_eq!(evaluate_raw("Person.age + 1"), Ok(Value::Int(43)));
+        assert_eq!(
+            evaluate_raw("Other.age"),
+            Err(ExecutionError::TableNotInQuery("other".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_expression_type() {
+        let type_of = |raw| expression_type(&Expression::parse_from_raw(raw).unwrap().1, &scope());
+
+        assert_eq!(type_of("age * 2"), Some(SqlTypeInfo::Int));
+        assert_eq!(type_of("name"), Some(SqlTypeInfo::String));
+        assert_eq!(type_of("age / 2.0"), None);
+        assert_eq!(type_of("age > 2"), None);
+    }
+
+    #[test]
+    fn test_evaluate_comparisons() {
+        assert_eq!(evaluate_raw("age >= 42"), Ok(Value::Boolean(true)));
+        assert_eq!(evaluate_raw("name <> 'Jo'"), Ok(Value::Boolean(false)));
+        assert_eq!(evaluate_raw("age = 42.0"), Ok(Value::Boolean(true)));
+    }
+
+    #[test]
+    fn test_evaluate_logic_with_nulls() {
+        assert_eq!(evaluate_raw("NULL AND age > 50"), Ok(Value::Boolean(false)));
+        assert_eq!(evaluate_raw("NULL AND age > 1"), Ok(Value::Null));
+        assert_eq!(evaluate_raw("NULL OR age > 1"), Ok(Value::Boolean(true)));
+        assert_eq!(evaluate_raw("NOT NULL"), Ok(Value::Null));
+        assert_eq!(evaluate_raw("age = NULL"), Ok(Value::Null));
+        assert_eq!(
+            evaluate_raw("(age = NULL) IS NULL"),
+            Ok(Value::Boolean(true))
+        );
+        assert_eq!(evaluate_raw("name IS NULL"), Ok(Value::Boolean(false)));
+        assert_eq!(
+            evaluate_raw("NOT age + 1 IS NOT NULL"),
+            Ok(Value::Boolean(false))
+        );
+    }
+
+    #[test]
+    fn test_evaluate_errors() {
+        assert_eq!(evaluate_raw("age / 0"), Err(ExecutionError::DivisionByZero));
+        assert_eq!(
+            evaluate_raw("salary > 1"),
+            Err(ExecutionError::ColumnNotFound {
+                table: "person".to_string(),
+                column: "salary".to_string()
+            })
+        );
+        assert_eq!(
+            evaluate_raw("name + 1"),
+            Err(ExecutionError::InvalidOperands {
+                op: BinaryOperator::Plus,
+                left: Value::String("Jo".to_string()),
+                right: Value::Int(1)
+            })
+        );
+        // only an AST built by hand can call NULLIF with other than two arguments
+        let call = Expression::Conditional {
+            function: ConditionalFunction::NullIf,
+            args: vec![Expression::parse_from_raw("age").unwrap().1],
+            span: Span::default(),
+        };
+        assert_eq!(
+            evaluate_in_row(&call),
+            Err(ExecutionError::ArgumentCount {
+                function: ConditionalFunction::NullIf,
+                expected: 2,
+                found: 1
+            })
+        );
+    }
+}
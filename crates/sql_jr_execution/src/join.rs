@@ -0,0 +1,109 @@
+use std::cmp::Ordering;
+
+use sql_jr_parser::{
+    from_clause::{JoinConstraint, JoinKind},
+    value::Value,
+};
+
+use crate::{
+    error::ExecutionResult,
+    evaluation::{check_columns, compare_values, is_match, RowContext},
+    scope::{join_scope, Scope},
+    select::Executor,
+    table::Row,
+};
+
+/// Rows together with the columns they are made of
+#[derive(Debug, Default)]
+pub(crate) struct Relation {
+    pub scope: Scope<'static>,
+    pub rows: Vec<Row>,
+}
+
+/// Join the rows of `left` and `right` with a nested loop, pairing every left row
+/// with every right row matching `constraint`. Outer joins add the rows matching
+/// nothing, with NULLs for the columns of the other side. `outer` is the row of the
+/// query the join is in a subquery of
+pub(crate) fn join(
+    left: Relation,
+    right: Relation,
+    kind: JoinKind,
+    constraint: &JoinConstraint,
+    outer: Option<&RowContext>,
+    executor: &Executor,
+) -> ExecutionResult<Relation> {
+    let (scope, using) = join_scope(&left.scope, &right.scope, constraint)?;
+    // the ON condition may read the columns of an enclosing query too
+    let condition_scope = scope.clone().with_outer(outer.map(|outer| outer.scope));
+    let on = match constraint {
+        JoinConstraint::On(expr) => {
+            check_columns(expr, &condition_scope)?;
+            Some(expr)
+        }
+        _ => None,
+    };
+
+    let left_nulls = vec![Value::Null; left.scope.len()];
+    let right_nulls = vec![Value::Null; right.scope.len()];
+    // the columns merged by USING come first, with the value of the side that has one
+    let combine = |l: Option<&Row>, r: Option<&Row>| {
+        let mut row: Row = using
+            .iter()
+            .map(|&(li, ri)| match (l, r) {
+                (Some(l), _) if l[li] != Value::Null => l[li].clone(),
+                (_, Some(r)) => r[ri].clone(),
+                _ => Value::Null,
+            })
+            .collect();
+        row.extend_from_slice(l.unwrap_or(&left_nulls));
+        row.extend_from_slice(r.unwrap_or(&right_nulls));
+        row
+    };
+    let matches = |l: &Row, r: &Row| -> ExecutionResult<bool> {
+        // NULL is equal to nothing, not even NULL
+        if using
+            .iter()
+            .any(|&(li, ri)| compare_values(&l[li], &r[ri]) != Some(Ordering::Equal))
+        {
+            return Ok(false);
+        }
+        match on {
+            Some(predicate) => {
+                let row = combine(Some(l), Some(r));
+                let context = RowContext {
+                    scope: &condition_scope,
+                    row: &row,
+                    aggregates: &[],
+                    outer,
+                    executor,
+                };
+                is_match(predicate, &context)
+            }
+            None => Ok(true),
+        }
+    };
+
+    let mut rows = Vec::new();
+    let mut right_matched = vec![false; right.rows.len()];
+    for l in &left.rows {
+        let mut matched = false;
+        for (r, right_matched) in right.rows.iter().zip(&mut right_matched) {
+            if matches(l, r)? {
+                matched = true;
+                *right_matched = true;
+                rows.push(combine(Some(l), Some(r)));
+            }
+        }
+        if !matched && matches!(kind, JoinKind::Left | JoinKind::Full) {
+            rows.push(combine(Some(l), None));
+        }
+    }
+    if matches!(kind, JoinKind::Right | JoinKind::Full) {
+        for (r, matched) in right.rows.iter().zip(right_matched) {
+            if !matched {
+                rows.push(combine(None, Some(r)));
+            }
+        }
+    }
+    Ok(Relation { scope, rows })
+}
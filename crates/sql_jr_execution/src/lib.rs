@@ -0,0 +1,15 @@
+mod aggregate;
+pub mod catalog;
+pub mod database;
+pub mod error;
+mod evaluation;
+mod join;
+pub mod result;
+mod scope;
+mod select;
+mod set_operation;
+mod sort;
+pub mod storage;
+pub mod table;
+pub mod validation;
+mod window;
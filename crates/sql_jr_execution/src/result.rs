@@ -0,0 +1,31 @@
+use sql_jr_parser::create_statement::SqlTypeInfo;
+
+use crate::table::Row;
+
+/// Name and type of one of the columns returned by a query
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ColumnInfo {
+    pub name: String,
+    /// None when the column is computed and its type isn't known before running it
+    pub type_info: Option<SqlTypeInfo>,
+}
+
+/// The outcome of successfully executing a query
+#[derive(Clone, Debug, PartialEq)]
+pub enum QueryResult {
+    /// The rows selected by a query and the columns they are made of
+    Rows {
+        columns: Vec<ColumnInfo>,
+        rows: Vec<Row>,
+    },
+    /// The number of rows changed by a query
+    Affected(usize),
+    /// A table was created
+    Created,
+    /// Tables were dropped
+    Dropped,
+    /// Tables were emptied
+    Truncated,
+    /// A table's schema was changed
+    Altered,
+}
@@ -0,0 +1,448 @@
+use std::collections::{HashMap, HashSet};
+
+use sql_jr_parser::{
+    create_statement::{Column, SqlTypeInfo},
+    expression::ColumnRef,
+    from_clause::{FromClause, JoinConstraint},
+    ident::{Ident, ObjectName},
+};
+
+use crate::{error::ExecutionError, result::ColumnInfo};
+
+/// One of the columns an expression can refer to
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ScopeColumn {
+    /// Name the table it comes from is referred to by, None for the columns merged
+    /// by a USING or NATURAL join
+    pub table: Option<ObjectName>,
+    pub name: String,
+    /// None for the computed columns of a derived table whose type isn't known
+    pub type_info: Option<SqlTypeInfo>,
+    /// Only reachable qualified by its table, like the columns a USING join merged
+    pub hidden: bool,
+}
+
+/// The columns of the rows a query works on, in the order of the values of a row
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Scope<'a> {
+    columns: Vec<ScopeColumn>,
+    // the positions of the columns of each name
+    positions: HashMap<String, Vec<usize>>,
+    /// The scope of the query a subquery is nested in, whose columns it can read too
+    outer: Option<&'a Scope<'a>>,
+}
+
+/// Why a column reference doesn't name exactly one column of a scope
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum ResolveError {
+    TableNotInQuery(String),
+    ColumnNotFound { table: String, column: String },
+    ColumnNotInQuery(String),
+    AmbiguousColumn(String),
+}
+
+impl From<ResolveError> for ExecutionError {
+    fn from(error: ResolveError) -> Self {
+        match error {
+            ResolveError::TableNotInQuery(table) => Self::TableNotInQuery(table),
+            ResolveError::ColumnNotFound { table, column } => {
+                Self::ColumnNotFound { table, column }
+            }
+            ResolveError::ColumnNotInQuery(column) => Self::ColumnNotInQuery(column),
+            ResolveError::AmbiguousColumn(column) => Self::AmbiguousColumn(column),
+        }
+    }
+}
+
+impl<'a> Scope<'a> {
+    // a scope of `columns` nested in no other
+    fn new(columns: Vec<ScopeColumn>) -> Self {
+        let mut positions: HashMap<String, Vec<usize>> = HashMap::new();
+        for (index, column) in columns.iter().enumerate() {
+            positions
+                .entry(column.name.clone())
+                .or_default()
+                .push(index);
+        }
+        Self {
+            columns,
+            positions,
+            outer: None,
+        }
+    }
+
+    /// The columns of a single table, referred to as `name` or by its last parts
+    pub fn table(name: &ObjectName, columns: &[Column]) -> Self {
+        Self::new(
+            columns
+                .iter()
+                .map(|column| ScopeColumn {
+                    table: Some(name.clone()),
+                    name: column.name.to_string(),
+                    type_info: Some(column.type_info.clone()),
+                    hidden: false,
+                })
+                .collect(),
+        )
+    }
+
+    /// The columns returned by a subquery in FROM, referred to as `name`
+    pub fn derived(name: &str, columns: &[ColumnInfo]) -> Self {
+        Self::new(
+            columns
+                .iter()
+                .map(|column| ScopeColumn {
+                    table: Some(name.into()),
+                    name: column.name.clone(),
+                    type_info: column.type_info.clone(),
+                    hidden: false,
+                })
+                .collect(),
+        )
+    }
+
+    /// This scope nested in `outer`, for a subquery reading the columns of the query
+    /// around it
+    pub fn with_outer(self, outer: Option<&'a Scope<'a>>) -> Self {
+        Self { outer, ..self }
+    }
+
+    pub fn columns(&self) -> &[ScopeColumn] {
+        &self.columns
+    }
+
+    pub fn len(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// A reference to the column at `index`, qualified by its table when it has one
+    pub fn column_ref(&self, index: usize) -> ColumnRef {
+        let column = &self.columns[index];
+        ColumnRef {
+            table: column.table.clone(),
+            column: column.name.as_str().into(),
+        }
+    }
+
+    /// The columns `*` stands for
+    pub fn visible(&self) -> Vec<usize> {
+        (0..self.columns.len())
+            .filter(|&index| !self.columns[index].hidden)
+            .collect()
+    }
+
+    /// The columns `table.*` stands for. `person` qualifies the columns of
+    /// `public.person` too
+    pub fn qualified(&self, table: &ObjectName) -> Result<Vec<usize>, ResolveError> {
+        let indexes: Vec<usize> = (0..self.columns.len())
+            .filter(|&index| {
+                self.columns[index]
+                    .table
+                    .as_ref()
+                    .is_some_and(|name| name.ends_with(table))
+            })
+            .collect();
+        if indexes.is_empty() {
+            return Err(ResolveError::TableNotInQuery(table.to_string()));
+        }
+        Ok(indexes)
+    }
+
+    // the names of the tables the columns come from
+    fn tables(&self) -> HashSet<&ObjectName> {
+        self.columns
+            .iter()
+            .filter_map(|column| column.table.as_ref())
+            .collect()
+    }
+
+    /// Position of the column `column` refers to. An unqualified name must match
+    /// exactly one column that isn't hidden
+    pub fn resolve(&self, column: &ColumnRef) -> Result<usize, ResolveError> {
+        let name = column.column.as_str();
+        let reachable = |&index: &usize| {
+            let column_table = self.columns[index].table.as_ref();
+            match &column.table {
+                Some(table) => column_table.is_some_and(|name| name.ends_with(table)),
+                None => !self.columns[index].hidden,
+            }
+        };
+        let mut matching = self
+            .positions
+            .get(name)
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(reachable);
+
+        match (matching.next(), matching.next()) {
+            (Some(index), None) => Ok(index),
+            (None, _) => {
+                let tables = self.tables();
+                match (&column.table, tables.len()) {
+                    (Some(table), _) => {
+                        self.qualified(table)?;
+                        Err(ResolveError::ColumnNotFound {
+                            table: table.to_string(),
+                            column: name.to_string(),
+                        })
+                    }
+                    (None, 1) => Err(ResolveError::ColumnNotFound {
+                        table: tables.into_iter().next().unwrap().to_string(),
+                        column: name.to_string(),
+                    }),
+                    (None, _) => Err(ResolveError::ColumnNotInQuery(name.to_string())),
+                }
+            }
+            (Some(_), Some(_)) => Err(ResolveError::AmbiguousColumn(column.to_string())),
+        }
+    }
+
+    /// Where the column `column` refers to is: how many scopes out from this one, and
+    /// its position in that scope. A subquery's own columns hide the outer ones
+    pub fn locate(&self, column: &ColumnRef) -> Result<(usize, usize), ResolveError> {
+        match (self.resolve(column), &self.outer) {
+            (Ok(index), _) => Ok((0, index)),
+            (Err(ResolveError::AmbiguousColumn(column)), _) => {
+                Err(ResolveError::AmbiguousColumn(column))
+            }
+            (Err(error), Some(outer)) => match outer.locate(column) {
+                Ok((depth, index)) => Ok((depth + 1, index)),
+                // the error is about the scope that has the qualifier's table
+                Err(outer_error) if matches!(error, ResolveError::TableNotInQuery(_)) => {
+                    Err(outer_error)
+                }
+                Err(_) => Err(error),
+            },
+            (Err(error), None) => Err(error),
+        }
+    }
+
+    /// How many scopes enclose this one, 0 for the scope of a statement
+    pub fn nesting(&self) -> usize {
+        self.outer.map_or(0, |outer| outer.nesting() + 1)
+    }
+
+    /// The scope `depth` scopes out from this one
+    pub fn level(&self, depth: usize) -> &Scope<'a> {
+        match depth {
+            0 => self,
+            _ => self
+                .outer
+                .expect("located columns are in an enclosing scope")
+                .level(depth - 1),
+        }
+    }
+
+    /// The columns of the rows joining a row of `self` to a row of `right`
+    pub fn join(&self, right: &Scope) -> Scope<'static> {
+        let mut columns = self.columns.clone();
+        columns.extend(right.columns.iter().cloned());
+        Scope::new(columns)
+    }
+
+    // the columns of a USING join on `names`: the merged columns first, then the
+    // columns of both sides, with the merged ones hidden. Also returns the position of
+    // each merged column in both sides
+    fn join_using(
+        &self,
+        right: &Scope,
+        names: &[&str],
+    ) -> Result<(Scope<'static>, Vec<(usize, usize)>), ResolveError> {
+        let mut columns = Vec::new();
+        let mut pairs = Vec::new();
+        for &name in names {
+            let column = ColumnRef::from(name);
+            let (left_index, right_index) = (self.resolve(&column)?, right.resolve(&column)?);
+            columns.push(ScopeColumn {
+                table: None,
+                ..self.columns[left_index].clone()
+            });
+            pairs.push((left_index, right_index));
+        }
+
+        let hide = |columns: &[ScopeColumn], merged: Vec<usize>| {
+            columns
+                .iter()
+                .enumerate()
+                .map(|(index, column)| ScopeColumn {
+                    hidden: column.hidden || merged.contains(&index),
+                    ..column.clone()
+                })
+                .collect::<Vec<_>>()
+        };
+        columns.extend(hide(&self.columns, pairs.iter().map(|p| p.0).collect()));
+        columns.extend(hide(&right.columns, pairs.iter().map(|p| p.1).collect()));
+        Ok((Scope::new(columns), pairs))
+    }
+}
+
+/// The scope of the rows of a join of `left` and `right`, and for USING and NATURAL
+/// joins the positions in each side of the columns that must be equal
+pub(crate) fn join_scope(
+    left: &Scope,
+    right: &Scope,
+    constraint: &JoinConstraint,
+) -> Result<(Scope<'static>, Vec<(usize, usize)>), ResolveError> {
+    match constraint {
+        JoinConstraint::On(_) | JoinConstraint::None => Ok((left.join(right), Vec::new())),
+        JoinConstraint::Using(names) => {
+            let names: Vec<&str> = names.iter().map(Ident::as_str).collect();
+            left.join_using(right, &names)
+        }
+        JoinConstraint::Natural => {
+            // the columns both sides have, in the order of the left side
+            let right_names: Vec<&str> = right
+                .visible()
+                .into_iter()
+                .map(|index| right.columns[index].name.as_str())
+                .collect();
+            let mut names: Vec<&str> = Vec::new();
+            for index in left.visible() {
+                let name = left.columns[index].name.as_str();
+                if right_names.contains(&name) && !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+            left.join_using(right, &names)
+        }
+    }
+}
+
+/// A table name or alias given to more than one table of `from`, which makes the
+/// columns qualified by it ambiguous
+pub(crate) fn duplicate_table_reference(from: &FromClause) -> Option<&Ident> {
+    let mut seen = HashSet::new();
+    from.reference_names()
+        .into_iter()
+        .find(|name| !seen.insert(name.as_str()))
+}
+
+#[cfg(test)]
+mod test {
+    use sql_jr_parser::{create_statement::CreateStatement, types::Parse};
+
+    use super::*;
+
+    fn scope(table: &str, raw: &str) -> Scope<'static> {
+        let (_, create) = CreateStatement::parse_from_raw(raw).unwrap();
+        Scope::table(&table.into(), &create.columns)
+    }
+
+    fn column(raw: &str) -> ColumnRef {
+        match raw.rsplit_once('.') {
+            Some((table, column)) => ColumnRef {
+                table: Some(ObjectName(table.split('.').map(Ident::from).collect())),
+                column: column.into(),
+            },
+            None => raw.into(),
+        }
+    }
+
+    fn people_and_cities() -> Scope<'static> {
+        scope("p", "CREATE TABLE Person (name string, city string)").join(&scope(
+            "c",
+            "CREATE TABLE City (name string, population int)",
+        ))
+    }
+
+    #[test]
+    fn test_resolve_in_a_join() {
+        let scope = people_and_cities();
+
+        assert_eq!(scope.resolve(&column("city")), Ok(1));
+        assert_eq!(scope.resolve(&column("c.name")), Ok(2));
+        assert_eq!(
+            scope.resolve(&column("name")),
+            Err(ResolveError::AmbiguousColumn("name".to_string()))
+        );
+        assert_eq!(
+            scope.resolve(&column("x.name")),
+            Err(ResolveError::TableNotInQuery("x".to_string()))
+        );
+        assert_eq!(
+            scope.resolve(&column("salary")),
+            Err(ResolveError::ColumnNotInQuery("salary".to_string()))
+        );
+        assert_eq!(
+            scope.resolve(&column("c.city")),
+            Err(ResolveError::ColumnNotFound {
+                table: "c".to_string(),
+                column: "city".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_resolve_with_a_qualified_table_name() {
+        let (_, create) =
+            CreateStatement::parse_from_raw("CREATE TABLE public.person (name string)").unwrap();
+        let scope = Scope::table(&create.table, &create.columns);
+
+        assert_eq!(scope.resolve(&column("name")), Ok(0));
+        assert_eq!(scope.resolve(&column("person.name")), Ok(0));
+        assert_eq!(scope.resolve(&column("public.person.name")), Ok(0));
+        assert_eq!(
+            scope.resolve(&column("other.person.name")),
+            Err(ResolveError::TableNotInQuery("other.person".to_string()))
+        );
+        assert_eq!(
+            scope.resolve(&column("public.name")),
+            Err(ResolveError::TableNotInQuery("public".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_natural_join_merges_common_columns() {
+        let left = scope("a", "CREATE TABLE A (id int, name string)");
+        let right = scope("b", "CREATE TABLE B (size int, id int)");
+
+        let (scope, pairs) = join_scope(&left, &right, &JoinConstraint::Natural).unwrap();
+
+        assert_eq!(pairs, vec![(0, 1)]);
+        let names: Vec<&str> = scope
+            .visible()
+            .into_iter()
+            .map(|index| scope.columns()[index].name.as_str())
+            .collect();
+        assert_eq!(names, vec!["id", "name", "size"]);
+        assert_eq!(scope.resolve(&column("id")), Ok(0));
+        assert_eq!(scope.resolve(&column("b.id")), Ok(4));
+        assert_eq!(scope.qualified(&"a".into()), Ok(vec![1, 2]));
+    }
+
+    #[test]
+    fn test_locate_in_outer_scopes() {
+        let outer = scope("p", "CREATE TABLE Person (name string, city string)");
+        let inner =
+            scope("c", "CREATE TABLE City (name string, population int)").with_outer(Some(&outer));
+
+        assert_eq!(inner.locate(&column("name")), Ok((0, 0)));
+        assert_eq!(inner.locate(&column("city")), Ok((1, 1)));
+        assert_eq!(inner.locate(&column("p.name")), Ok((1, 0)));
+        assert_eq!(
+            inner.locate(&column("p.age")),
+            Err(ResolveError::ColumnNotFound {
+                table: "p".to_string(),
+                column: "age".to_string()
+            })
+        );
+        assert_eq!(
+            inner.level(1).columns()[1].type_info,
+            Some(SqlTypeInfo::String)
+        );
+        assert_eq!((inner.nesting(), outer.nesting()), (1, 0));
+    }
+
+    #[test]
+    fn test_duplicate_table_reference() {
+        let (_, from) = FromClause::parse_from_raw("Person JOIN Person ON 1 = 1").unwrap();
+        assert_eq!(
+            duplicate_table_reference(&from).map(Ident::as_str),
+            Some("person")
+        );
+
+        let (_, from) = FromClause::parse_from_raw("Person a JOIN Person b ON 1 = 1").unwrap();
+        assert_eq!(duplicate_table_reference(&from), None);
+    }
+}
@@ -0,0 +1,761 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use sql_jr_parser::{
+    create_statement::Column,
+    expression::{AggregateArg, Expression},
+    from_clause::{FromClause, JoinConstraint},
+    ident::{Ident, ObjectName},
+    query_expression::{
+        CommonTableExpression, NestedQuery, QueryExpression, SetOperation, SetOperator, WithQuery,
+    },
+    select_statement::{Distinct, OrderByExpr, SelectItem, SelectStatement},
+    value::Value,
+};
+
+use crate::{
+    aggregate::{collect_aggregates, group_rows, ungrouped_column},
+    database::DEFAULT_RECURSION_LIMIT,
+    error::{ExecutionError, ExecutionResult},
+    evaluation::{check_columns, evaluate, expression_type, is_match, output_name, RowContext},
+    join::{join, Relation},
+    result::ColumnInfo,
+    scope::{duplicate_table_reference, Scope},
+    set_operation::{combine_rows, result_columns},
+    sort::{in_select_list, resolve_sort_key, sort_columns, SortKey, SortOrder, Sorter},
+    table::Row,
+    window::{collect_windows, compute_windows},
+};
+
+/// The columns and rows of every table a query reads, scanned before it runs
+pub(crate) type TableRows = HashMap<String, (Vec<Column>, Vec<Row>)>;
+
+/// The columns and rows returned by a query
+pub(crate) type QueryRows = (Vec<ColumnInfo>, Vec<Row>);
+
+/// Runs queries, and the subqueries nested in them, over the rows of the tables they
+/// read
+#[derive(Debug)]
+pub(crate) struct Executor<'a> {
+    tables: &'a TableRows,
+    // the results of the CTEs of the WITH clauses being run, the innermost last, which
+    // hide the tables of the same name
+    ctes: RefCell<Vec<(String, Rc<QueryRows>)>>,
+    recursion_limit: usize,
+    // the subqueries validation found don't read the row of an enclosing query, whose
+    // results are the same for every row so are only computed once
+    uncorrelated: &'a HashSet<QueryExpression>,
+    results: RefCell<HashMap<QueryExpression, Rc<QueryRows>>>,
+}
+
+impl<'a> Executor<'a> {
+    /// Every subquery not in `uncorrelated` is run again for each row it is used for
+    pub fn new(tables: &'a TableRows, uncorrelated: &'a HashSet<QueryExpression>) -> Self {
+        Self {
+            tables,
+            ctes: RefCell::new(Vec::new()),
+            recursion_limit: DEFAULT_RECURSION_LIMIT,
+            uncorrelated,
+            results: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Fail a recursive CTE whose recursive query is still returning rows after
+    /// running `limit` times
+    pub fn with_recursion_limit(mut self, limit: usize) -> Self {
+        self.recursion_limit = limit;
+        self
+    }
+
+    /// The result of `subquery` for the row of `context`, the query it is nested in
+    pub fn subquery(
+        &self,
+        subquery: &QueryExpression,
+        context: &RowContext,
+    ) -> ExecutionResult<Rc<QueryRows>> {
+        if !self.uncorrelated.contains(subquery) {
+            return Ok(Rc::new(self.query(subquery, Some(context))?));
+        }
+        if let Some(result) = self.results.borrow().get(subquery) {
+            return Ok(result.clone());
+        }
+        let result = Rc::new(self.query(subquery, None)?);
+        self.results
+            .borrow_mut()
+            .insert(subquery.clone(), result.clone());
+        Ok(result)
+    }
+
+    /// Run `query`, a subquery of the query whose row is `outer` when it has one
+    pub fn query(
+        &self,
+        query: &QueryExpression,
+        outer: Option<&RowContext>,
+    ) -> ExecutionResult<QueryRows> {
+        match query {
+            QueryExpression::Select(select) => self.select(select, outer),
+            QueryExpression::SetOperation(operation) => self.set_operation(operation, outer),
+            QueryExpression::With(with) => {
+                let depth = self.ctes.borrow().len();
+                let result = self.with_query(with, outer);
+                self.pop_ctes(depth);
+                result
+            }
+            QueryExpression::Nested(nested) => self.nested(nested, outer),
+        }
+    }
+
+    // computes the CTEs of `with` in order, each reading those before it, then the
+    // query reading them all. The CTEs are left for the caller to drop
+    fn with_query(
+        &self,
+        with: &WithQuery,
+        outer: Option<&RowContext>,
+    ) -> ExecutionResult<QueryRows> {
+        for cte in &with.tables {
+            let rows = match with.recursive && reads_table(&cte.query, &cte.name) {
+                true => self.recursive_cte(cte, outer)?,
+                false => {
+                    let (columns, rows) = self.query(&cte.query, outer)?;
+                    (named_columns(cte, columns)?, rows)
+                }
+            };
+            self.push_cte(cte, rows);
+        }
+        self.query(&with.query, outer)
+    }
+
+    // runs `anchor UNION [ALL] recursive` until the recursive query returns no new row,
+    // reading the rows the run before it added as the rows of the CTE. The anchor
+    // query runs first and can't read them
+    fn recursive_cte(
+        &self,
+        cte: &CommonTableExpression,
+        outer: Option<&RowContext>,
+    ) -> ExecutionResult<QueryRows> {
+        let operation = match &cte.query {
+            QueryExpression::SetOperation(operation)
+                if operation.op == SetOperator::Union
+                    && operation.order_by.is_empty()
+                    && operation.limit.is_none()
+                    && operation.offset.is_none() =>
+            {
+                operation
+            }
+            _ => return Err(ExecutionError::RecursiveCteForm(cte.name.to_string())),
+        };
+        let (columns, anchor) = self.query(&operation.left, outer)?;
+        let columns = named_columns(cte, columns)?;
+
+        // without ALL the rows returned before are left out as duplicates
+        let mut seen = HashSet::new();
+        let mut new_rows = |rows: Vec<Row>| -> Vec<Row> {
+            match operation.all {
+                true => rows,
+                false => rows
+                    .into_iter()
+                    .filter(|row| seen.insert(row.clone()))
+                    .collect(),
+            }
+        };
+        let mut working = new_rows(anchor);
+        let mut rows = working.clone();
+        let mut runs = 0;
+        while !working.is_empty() {
+            if runs == self.recursion_limit {
+                return Err(ExecutionError::RecursionLimitExceeded {
+                    name: cte.name.to_string(),
+                    limit: self.recursion_limit,
+                });
+            }
+            runs += 1;
+
+            let depth = self.ctes.borrow().len();
+            self.push_cte(cte, (columns.clone(), working));
+            let result = self.query(&operation.right, outer);
+            self.pop_ctes(depth);
+            let (right_columns, right) = result?;
+            result_columns(SetOperator::Union, columns.clone(), &right_columns)?;
+
+            working = new_rows(right);
+            rows.extend(working.iter().cloned());
+        }
+        Ok((columns, rows))
+    }
+
+    fn push_cte(&self, cte: &CommonTableExpression, rows: QueryRows) {
+        self.ctes
+            .borrow_mut()
+            .push((cte.name.to_string(), Rc::new(rows)));
+        // the subqueries that ran before may have read different rows under that name
+        self.results.borrow_mut().clear();
+    }
+
+    // drops the CTEs pushed after the first `depth`
+    fn pop_ctes(&self, depth: usize) {
+        self.ctes.borrow_mut().truncate(depth);
+        self.results.borrow_mut().clear();
+    }
+
+    // the rows of both queries of `operation` combined, then sorted and paginated
+    fn set_operation(
+        &self,
+        operation: &SetOperation,
+        outer: Option<&RowContext>,
+    ) -> ExecutionResult<QueryRows> {
+        let (columns, left) = self.query(&operation.left, outer)?;
+        let (right_columns, right) = self.query(&operation.right, outer)?;
+        let columns = result_columns(operation.op, columns, &right_columns)?;
+        let output_names: Vec<&str> = columns.iter().map(|column| column.name.as_str()).collect();
+        let sort_columns = sort_columns(&operation.order_by, &output_names, || {
+            ExecutionError::SetOperationOrderBy(operation.op)
+        })
+        .map_err(|(error, _)| error)?;
+
+        let rows = combine_rows(operation.op, operation.all, left, right);
+        let rows = sort_result(
+            rows,
+            &operation.order_by,
+            &sort_columns,
+            operation.limit,
+            operation.offset,
+        );
+        Ok((columns, rows))
+    }
+
+    // the rows of the query of `nested`, sorted and paginated again
+    fn nested(
+        &self,
+        nested: &NestedQuery,
+        outer: Option<&RowContext>,
+    ) -> ExecutionResult<QueryRows> {
+        let (columns, rows) = self.query(&nested.query, outer)?;
+        let output_names: Vec<&str> = columns.iter().map(|column| column.name.as_str()).collect();
+        let sort_columns = sort_columns(&nested.order_by, &output_names, || {
+            ExecutionError::NestedOrderBy
+        })
+        .map_err(|(error, _)| error)?;
+        let rows = sort_result(
+            rows,
+            &nested.order_by,
+            &sort_columns,
+            nested.limit,
+            nested.offset,
+        );
+        Ok((columns, rows))
+    }
+
+    // the rows `statement` selects
+    fn select(
+        &self,
+        statement: &SelectStatement,
+        outer: Option<&RowContext>,
+    ) -> ExecutionResult<QueryRows> {
+        if let Some(name) = duplicate_table_reference(&statement.from) {
+            return Err(ExecutionError::DuplicateTableReference(name.to_string()));
+        }
+        let Relation { scope, rows: input } = self.scan_from(&statement.from, outer)?;
+        let scope = scope.with_outer(outer.map(|outer| outer.scope));
+
+        let projection = projection(statement, &scope)?;
+        let output_names: Vec<&str> = projection
+            .iter()
+            .map(|(column, _)| column.name.as_str())
+            .collect();
+        let resolve_key = |expr, out_of_range: fn(i64) -> ExecutionError| {
+            let key = resolve_sort_key(expr, &output_names).map_err(out_of_range)?;
+            if let SortKey::Input(expr) = key {
+                check_columns(expr, &scope)?;
+            }
+            Ok(key)
+        };
+        let sort_keys = statement
+            .order_by
+            .iter()
+            .map(|order_by| resolve_key(&order_by.expr, ExecutionError::OrderByPositionOutOfRange))
+            .collect::<ExecutionResult<Vec<SortKey>>>()?;
+        // DISTINCT ON keys name result columns the way ORDER BY keys do
+        let distinct_on = match &statement.distinct {
+            Some(Distinct::On(exprs)) => exprs
+                .iter()
+                .map(|expr| resolve_key(expr, ExecutionError::DistinctOnPositionOutOfRange))
+                .collect::<ExecutionResult<Vec<SortKey>>>()?,
+            _ => Vec::new(),
+        };
+        if statement.distinct == Some(Distinct::Rows) {
+            // a row left out as a duplicate has no values of its own to sort by
+            let selected: Vec<&Expression> = projection.iter().map(|(_, expr)| expr).collect();
+            for key in &sort_keys {
+                if let SortKey::Input(expr) = key {
+                    if !in_select_list(expr, &selected, &scope) {
+                        return Err(ExecutionError::DistinctOrderBy);
+                    }
+                }
+            }
+        }
+        for expr in statement.group_by.iter().chain(&statement.having) {
+            check_columns(expr, &scope)?;
+        }
+
+        let mut aggregates = Vec::new();
+        let computed = projection
+            .iter()
+            .map(|(_, expr)| expr)
+            .chain(&statement.having);
+        for expr in computed.clone() {
+            collect_aggregates(expr, &mut aggregates);
+        }
+        for order_by in &statement.order_by {
+            collect_aggregates(&order_by.expr, &mut aggregates);
+        }
+        if let Some(Distinct::On(exprs)) = &statement.distinct {
+            for expr in exprs {
+                collect_aggregates(expr, &mut aggregates);
+            }
+        }
+        let grouped =
+            !statement.group_by.is_empty() || statement.having.is_some() || !aggregates.is_empty();
+        // window functions can be called where aggregates can but in HAVING
+        let mut windows = Vec::new();
+        for expr in projection.iter().map(|(_, expr)| expr) {
+            collect_windows(expr, &mut windows);
+        }
+        for order_by in &statement.order_by {
+            collect_windows(&order_by.expr, &mut windows);
+        }
+        if let Some(Distinct::On(exprs)) = &statement.distinct {
+            for expr in exprs {
+                collect_windows(expr, &mut windows);
+            }
+        }
+        if grouped {
+            let input_keys = sort_keys
+                .iter()
+                .chain(&distinct_on)
+                .filter_map(|key| match key {
+                    SortKey::Input(expr) => Some(*expr),
+                    SortKey::Output(_) => None,
+                });
+            for expr in computed.chain(input_keys) {
+                if let Some(column) = ungrouped_column(expr, &statement.group_by, &scope) {
+                    return Err(ExecutionError::UngroupedColumn(column.to_string()));
+                }
+            }
+        }
+
+        let offset = statement.offset.map_or(0, row_count);
+        // only the rows up to the end of the page are needed
+        let keep = statement
+            .limit
+            .map(|limit| offset.saturating_add(row_count(limit)));
+        // DISTINCT ON keeps the first row of each key in sorted order, so once sorted
+        // the duplicates are dropped before the rows past the page can be
+        let distinct_after_sort = !distinct_on.is_empty() && !sort_keys.is_empty();
+        let mut sorter = Sorter::new(
+            statement.order_by.iter().map(SortOrder::from).collect(),
+            if distinct_after_sort { None } else { keep },
+        );
+
+        // the rows or the groups the result is made of, with the values of the
+        // aggregate calls for groups
+        let mut sources = if grouped {
+            let mut matching = Vec::new();
+            for row in input {
+                let context = RowContext {
+                    scope: &scope,
+                    row: &row,
+                    aggregates: &[],
+                    outer,
+                    executor: self,
+                };
+                if let Some(predicate) = &statement.where_clause {
+                    if !is_match(predicate, &context)? {
+                        continue;
+                    }
+                }
+                matching.push(row);
+            }
+            group_rows(
+                &scope,
+                &statement.group_by,
+                &aggregates,
+                matching,
+                outer,
+                self,
+            )?
+        } else {
+            // WHERE is evaluated below, so the rows after the last one needed aren't
+            input.into_iter().map(|row| (row, Vec::new())).collect()
+        };
+        let mut filter = match grouped {
+            true => statement.having.as_ref(),
+            false => statement.where_clause.as_ref(),
+        };
+        if !windows.is_empty() {
+            // a window holds the rows left once filtered, so they are filtered first
+            if let Some(predicate) = filter.take() {
+                let mut matching = Vec::new();
+                for (row, aggregates) in sources {
+                    let context = RowContext {
+                        scope: &scope,
+                        row: &row,
+                        aggregates: &aggregates,
+                        outer,
+                        executor: self,
+                    };
+                    if is_match(predicate, &context)? {
+                        matching.push((row, aggregates));
+                    }
+                }
+                sources = matching;
+            }
+            compute_windows(&scope, &windows, &mut sources, outer, self)?;
+        }
+
+        let mut rows = Vec::new();
+        // the rows or the DISTINCT ON keys returned so far
+        let mut seen = HashSet::new();
+        for (row, aggregates) in &sources {
+            // without ORDER BY the rows come in storage order, so they can stop early
+            if sort_keys.is_empty() && Some(rows.len()) == keep {
+                break;
+            }
+            let context = RowContext {
+                scope: &scope,
+                row,
+                aggregates,
+                outer,
+                executor: self,
+            };
+            if let Some(predicate) = filter {
+                if !is_match(predicate, &context)? {
+                    continue;
+                }
+            }
+            let output = projection
+                .iter()
+                .map(|(_, expression)| evaluate(expression, &context))
+                .collect::<ExecutionResult<Row>>()?;
+
+            // NULLs are equal to each other here, unlike in a comparison
+            let mut distinct_key = match &statement.distinct {
+                Some(Distinct::Rows) => Some(output.clone()),
+                Some(Distinct::On(_)) => Some(key_values(&distinct_on, &output, &context)?),
+                None => None,
+            };
+            if !distinct_after_sort {
+                if let Some(key) = distinct_key.take() {
+                    if !seen.insert(key) {
+                        continue;
+                    }
+                }
+            }
+
+            if sort_keys.is_empty() {
+                rows.push(output);
+                continue;
+            }
+            let mut keys = key_values(&sort_keys, &output, &context)?;
+            keys.extend(distinct_key.into_iter().flatten());
+            sorter.push(keys, output);
+        }
+        if distinct_after_sort {
+            rows = sorter
+                .finish_with_keys()
+                .into_iter()
+                .filter(|(keys, _)| seen.insert(keys[sort_keys.len()..].to_vec()))
+                .map(|(_, row)| row)
+                .collect();
+        } else if !sort_keys.is_empty() {
+            rows = sorter.finish();
+        }
+        let rows = rows
+            .into_iter()
+            .skip(offset)
+            .take(statement.limit.map_or(usize::MAX, row_count))
+            .collect();
+
+        let columns = projection.into_iter().map(|(column, _)| column).collect();
+        Ok((columns, rows))
+    }
+
+    // the rows of the tables of `from` joined together
+    fn scan_from(
+        &self,
+        from: &FromClause,
+        outer: Option<&RowContext>,
+    ) -> ExecutionResult<Relation> {
+        match from {
+            FromClause::Table(reference) => {
+                // a qualified name never refers to a CTE
+                let cte = reference.name.as_unqualified().and_then(|name| {
+                    self.ctes
+                        .borrow()
+                        .iter()
+                        .rev()
+                        .find(|(cte, _)| cte == name.as_str())
+                        .map(|(_, rows)| rows.clone())
+                });
+                if let Some(cte) = cte {
+                    let (columns, rows) = &*cte;
+                    return Ok(Relation {
+                        scope: Scope::derived(reference.reference_name().as_str(), columns),
+                        rows: rows.clone(),
+                    });
+                }
+                let (columns, rows) = self
+                    .tables
+                    .get(&reference.name.to_string())
+                    .ok_or_else(|| ExecutionError::TableNotFound(reference.name.to_string()))?;
+                Ok(Relation {
+                    scope: Scope::table(&reference.qualifier(), columns),
+                    rows: rows.clone(),
+                })
+            }
+            FromClause::Subquery { query, alias } => {
+                let (columns, rows) = self.query(query, outer)?;
+                Ok(Relation {
+                    scope: Scope::derived(alias.as_str(), &columns),
+                    rows,
+                })
+            }
+            FromClause::Join {
+                left,
+                kind,
+                right,
+                constraint,
+            } => {
+                let left = self.scan_from(left, outer)?;
+                let right = self.scan_from(right, outer)?;
+                join(left, right, *kind, constraint, outer, self)
+            }
+        }
+    }
+}
+
+// the values of `keys` for the row of `context`, whose result row is `output`
+fn key_values(keys: &[SortKey], output: &Row, context: &RowContext) -> ExecutionResult<Vec<Value>> {
+    keys.iter()
+        .map(|key| match key {
+            SortKey::Output(index) => Ok(output[*index].clone()),
+            SortKey::Input(expr) => evaluate(expr, context),
+        })
+        .collect()
+}
+
+/// The result columns of `cte`, renamed by the column names it was given. None when
+/// it was given more names than its query has columns
+pub(crate) fn cte_columns(
+    cte: &CommonTableExpression,
+    mut columns: Vec<ColumnInfo>,
+) -> Option<Vec<ColumnInfo>> {
+    if cte.columns.len() > columns.len() {
+        return None;
+    }
+    for (column, name) in columns.iter_mut().zip(&cte.columns) {
+        column.name = name.to_string();
+    }
+    Some(columns)
+}
+
+fn named_columns(
+    cte: &CommonTableExpression,
+    columns: Vec<ColumnInfo>,
+) -> ExecutionResult<Vec<ColumnInfo>> {
+    let available = columns.len();
+    cte_columns(cte, columns).ok_or_else(|| ExecutionError::CteColumnCount {
+        name: cte.name.to_string(),
+        available,
+        specified: cte.columns.len(),
+    })
+}
+
+// a LIMIT or OFFSET as a number of rows, one too large to count is as good as no limit
+fn row_count(count: u64) -> usize {
+    usize::try_from(count).unwrap_or(usize::MAX)
+}
+
+// the result columns of `statement` with the expression computing each of them, the
+// wildcards expanded to the columns of the tables in the order they were created in
+fn projection(
+    statement: &SelectStatement,
+    scope: &Scope,
+) -> ExecutionResult<Vec<(ColumnInfo, Expression)>> {
+    let scope_column = |index: usize| {
+        let column = &scope.columns()[index];
+        (
+            ColumnInfo {
+                name: column.name.clone(),
+                type_info: column.type_info.clone(),
+            },
+            Expression::Column(scope.column_ref(index)),
+        )
+    };
+
+    let mut projection = Vec::new();
+    for item in &statement.columns {
+        match item {
+            SelectItem::Wildcard => {
+                projection.extend(scope.visible().into_iter().map(scope_column))
+            }
+            SelectItem::QualifiedWildcard(table) => {
+                projection.extend(scope.qualified(table)?.into_iter().map(scope_column))
+            }
+            SelectItem::Expression { expr, alias } => {
+                // caught here too so selecting from an empty table fails the same way
+                check_columns(expr, scope)?;
+                projection.push((
+                    ColumnInfo {
+                        name: output_name(expr, alias.as_ref()),
+                        type_info: expression_type(expr, scope),
+                    },
+                    expr.clone(),
+                ));
+            }
+        }
+    }
+    Ok(projection)
+}
+
+// sorts `rows` by the result columns at `sort_columns`, in the directions of
+// `order_by`, then keeps the page `limit` and `offset` select
+fn sort_result(
+    mut rows: Vec<Row>,
+    order_by: &[OrderByExpr],
+    sort_columns: &[usize],
+    limit: Option<u64>,
+    offset: Option<u64>,
+) -> Vec<Row> {
+    let offset = offset.map_or(0, row_count);
+    if !sort_columns.is_empty() {
+        let keep = limit.map(|limit| offset.saturating_add(row_count(limit)));
+        let mut sorter = Sorter::new(order_by.iter().map(SortOrder::from).collect(), keep);
+        for row in rows {
+            let keys = sort_columns
+                .iter()
+                .map(|&index| row[index].clone())
+                .collect();
+            sorter.push(keys, row);
+        }
+        rows = sorter.finish();
+    }
+    rows.into_iter()
+        .skip(offset)
+        .take(limit.map_or(usize::MAX, row_count))
+        .collect()
+}
+
+/// Add the tables `query` and the subqueries nested in it read to `tables`, with the
+/// names of the CTEs they read
+pub(crate) fn query_tables<'s>(query: &'s QueryExpression, tables: &mut Vec<&'s ObjectName>) {
+    match query {
+        QueryExpression::Select(select) => statement_tables(select, tables),
+        QueryExpression::SetOperation(operation) => {
+            query_tables(&operation.left, tables);
+            query_tables(&operation.right, tables);
+        }
+        QueryExpression::With(with) => {
+            for cte in &with.tables {
+                query_tables(&cte.query, tables);
+            }
+            query_tables(&with.query, tables);
+        }
+        QueryExpression::Nested(nested) => query_tables(&nested.query, tables),
+    }
+}
+
+/// Whether `query` or one of its subqueries reads the table or CTE called `name`
+pub(crate) fn reads_table(query: &QueryExpression, name: &Ident) -> bool {
+    let mut tables = Vec::new();
+    query_tables(query, &mut tables);
+    tables
+        .iter()
+        .any(|table| table.as_unqualified() == Some(name))
+}
+
+fn statement_tables<'s>(statement: &'s SelectStatement, tables: &mut Vec<&'s ObjectName>) {
+    from_tables(&statement.from, tables);
+    for item in &statement.columns {
+        if let SelectItem::Expression { expr, .. } = item {
+            expression_tables(expr, tables);
+        }
+    }
+    if let Some(Distinct::On(exprs)) = &statement.distinct {
+        for expr in exprs {
+            expression_tables(expr, tables);
+        }
+    }
+    let order_by = statement.order_by.iter().map(|order_by| &order_by.expr);
+    for expr in statement
+        .where_clause
+        .iter()
+        .chain(&statement.group_by)
+        .chain(&statement.having)
+        .chain(order_by)
+    {
+        expression_tables(expr, tables);
+    }
+}
+
+fn from_tables<'s>(from: &'s FromClause, tables: &mut Vec<&'s ObjectName>) {
+    match from {
+        FromClause::Table(reference) => tables.push(&reference.name),
+        FromClause::Subquery { query, .. } => query_tables(query, tables),
+        FromClause::Join {
+            left,
+            right,
+            constraint,
+            ..
+        } => {
+            from_tables(left, tables);
+            from_tables(right, tables);
+            if let JoinConstraint::On(predicate) = constraint {
+                expression_tables(predicate, tables);
+            }
+        }
+    }
+}
+
+/// Add the tables the subqueries of `expression` read to `tables`
+pub(crate) fn expression_tables<'s>(expression: &'s Expression, tables: &mut Vec<&'s ObjectName>) {
+    match expression {
+        Expression::Column(_)
+        | Expression::Literal(_)
+        | Expression::Aggregate {
+            arg: AggregateArg::Wildcard,
+            ..
+        } => {}
+        Expression::Aggregate {
+            arg: AggregateArg::Expression(expr),
+            ..
+        }
+        | Expression::Unary { expr, .. }
+        | Expression::IsNull { expr, .. } => expression_tables(expr, tables),
+        Expression::Binary { left, right, .. } => {
+            expression_tables(left, tables);
+            expression_tables(right, tables);
+        }
+        Expression::Subquery { subquery, .. } | Expression::Exists { subquery, .. } => {
+            query_tables(subquery, tables)
+        }
+        Expression::InSubquery { expr, subquery, .. } => {
+            expression_tables(expr, tables);
+            query_tables(subquery, tables);
+        }
+        Expression::Case { .. } | Expression::Conditional { .. } => {
+            for operand in expression.conditional_operands() {
+                expression_tables(operand, tables);
+            }
+        }
+        Expression::Window {
+            function, window, ..
+        } => {
+            for expr in function.args().into_iter().chain(window.expressions()) {
+                expression_tables(expr, tables);
+            }
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::{HashMap, HashSet};
+
+use sql_jr_parser::{create_statement::SqlTypeInfo, query_expression::SetOperator};
+
+use crate::{error::ExecutionError, result::ColumnInfo, table::Row};
+
+/// Why the results of the two queries of a set operation can't be combined
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum SetOperationError {
+    ColumnCount(SetOperator),
+    TypeMismatch {
+        op: SetOperator,
+        left: SqlTypeInfo,
+        right: SqlTypeInfo,
+    },
+}
+
+impl From<SetOperationError> for ExecutionError {
+    fn from(error: SetOperationError) -> Self {
+        match error {
+            SetOperationError::ColumnCount(op) => Self::SetOperationColumnCount(op),
+            SetOperationError::TypeMismatch { op, left, right } => {
+                Self::SetOperationTypeMismatch { op, left, right }
+            }
+        }
+    }
+}
+
+/// The columns of the result of a set operation, named after the columns of the left
+/// query. Both queries must return as many columns, of the same types where known
+pub(crate) fn result_columns(
+    op: SetOperator,
+    left: Vec<ColumnInfo>,
+    right: &[ColumnInfo],
+) -> Result<Vec<ColumnInfo>, SetOperationError> {
+    if left.len() != right.len() {
+        return Err(SetOperationError::ColumnCount(op));
+    }
+    left.into_iter()
+        .zip(right)
+        .map(
+            |(column, other)| match (&column.type_info, &other.type_info) {
+                (Some(left), Some(right)) if left != right => {
+                    Err(SetOperationError::TypeMismatch {
+                        op,
+                        left: left.clone(),
+                        right: right.clone(),
+                    })
+                }
+                (None, type_info) => Ok(ColumnInfo {
+                    type_info: type_info.clone(),
+                    ..column
+                }),
+                _ => Ok(column),
+            },
+        )
+        .collect()
+}
+
+// the rows without their duplicates, in the order they first come in
+fn distinct(rows: impl IntoIterator<Item = Row>) -> Vec<Row> {
+    let mut seen = HashSet::new();
+    rows.into_iter()
+        .filter(|row| seen.insert(row.clone()))
+        .collect()
+}
+
+/// Combine the rows of the two queries of a set operation. Unlike in a comparison
+/// NULLs are equal to each other here. With `all` a row the queries return several
+/// times is kept as often as the operation allows, else only once
+pub(crate) fn combine_rows(
+    op: SetOperator,
+    all: bool,
+    left: Vec<Row>,
+    right: Vec<Row>,
+) -> Vec<Row> {
+    if op == SetOperator::Union {
+        let rows = left.into_iter().chain(right);
+        return match all {
+            true => rows.collect(),
+            false => distinct(rows),
+        };
+    }
+
+    // how many times each row is left in the right query's rows
+    let mut counts: HashMap<Row, usize> = HashMap::new();
+    for row in right {
+        *counts.entry(row).or_default() += 1;
+    }
+    let left = match all {
+        true => left,
+        false => distinct(left),
+    };
+    left.into_iter()
+        .filter(|row| {
+            // each row of the right query matches a single row of the left one
+            let matched = match counts.get_mut(row) {
+                Some(count) if *count > 0 => {
+                    *count -= usize::from(all);
+                    true
+                }
+                _ => false,
+            };
+            matched == (op == SetOperator::Intersect)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use sql_jr_parser::value::Value;
+
+    use super::*;
+
+    fn rows(values: &[i64]) -> Vec<Row> {
+        values
+            .iter()
+            .map(|value| vec![Value::Int(*value)])
+            .collect()
+    }
+
+    #[test]
+    fn test_combine_rows() {
+        let combine = |op, all| combine_rows(op, all, rows(&[1, 1, 1, 2, 3]), rows(&[1, 1, 4]));
+
+        assert_eq!(combine(SetOperator::Union, false), rows(&[1, 2, 3, 4]));
+        assert_eq!(
+            combine(SetOperator::Union, true),
+            rows(&[1, 1, 1, 2, 3, 1, 1, 4])
+        );
+        assert_eq!(combine(SetOperator::Intersect, false), rows(&[1]));
+        assert_eq!(combine(SetOperator::Intersect, true), rows(&[1, 1]));
+        assert_eq!(combine(SetOperator::Except, false), rows(&[2, 3]));
+        assert_eq!(combine(SetOperator::Except, true), rows(&[1, 2, 3]));
+    }
+
+    #[test]
+    fn test_nulls_are_not_distinct() {
+        let null = vec![vec![Value::Null]];
+
+        assert_eq!(
+            combine_rows(SetOperator::Union, false, null.clone(), null.clone()),
+            null
+        );
+        assert!(combine_rows(SetOperator::Except, false, null.clone(), null).is_empty());
+    }
+
+    #[test]
+    fn test_result_columns() {
+        let column = |name: &str, type_info| ColumnInfo {
+            name: name.to_string(),
+            type_info,
+        };
+
+        assert_eq!(
+            result_columns(
+                SetOperator::Union,
+                vec![column("a", None)],
+                &[column("b", Some(SqlTypeInfo::Int))]
+            ),
+            Ok(vec![column("a", Some(SqlTypeInfo::Int))])
+        );
+        assert_eq!(
+            result_columns(
+                SetOperator::Except,
+                vec![column("a", Some(SqlTypeInfo::String))],
+                &[column("b", Some(SqlTypeInfo::Int))]
+            ),
+            Err(SetOperationError::TypeMismatch {
+                op: SetOperator::Except,
+                left: SqlTypeInfo::String,
+                right: SqlTypeInfo::Int
+            })
+        );
+        assert_eq!(
+            result_columns(SetOperator::Intersect, vec![column("a", None)], &[]),
+            Err(SetOperationError::ColumnCount(SetOperator::Intersect))
+        );
+    }
+}
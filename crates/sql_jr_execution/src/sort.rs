@@ -0,0 +1,285 @@
+use std::cmp::Ordering;
+
+use sql_jr_parser::{
+    expression::Expression,
+    select_statement::{NullsOrder, OrderByExpr, OrderDirection},
+    types::Span,
+    value::Value,
+};
+
+use crate::{error::ExecutionError, evaluation::compare_values, scope::Scope, table::Row};
+
+/// What an ORDER BY key is computed from
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum SortKey<'a> {
+    /// A column of the result, named by its position or its output name
+    Output(usize),
+    /// An expression over the columns of the table
+    Input(&'a Expression),
+}
+
+/// Find what `expr` sorts by: `ORDER BY 2` is the second result column and a bare
+/// name matching a result column, such as an alias, is that column. Anything else
+/// is evaluated against the table's rows. A position outside the result is returned
+/// as the error
+pub(crate) fn resolve_sort_key<'a>(
+    expr: &'a Expression,
+    output_names: &[&str],
+) -> Result<SortKey<'a>, i64> {
+    match expr {
+        Expression::Literal(literal) => match literal.value {
+            Value::Int(position) if position >= 1 && position as usize <= output_names.len() => {
+                Ok(SortKey::Output(position as usize - 1))
+            }
+            Value::Int(position) => Err(position),
+            _ => Ok(SortKey::Input(expr)),
+        },
+        Expression::Column(column) if column.table.is_none() => Ok(output_names
+            .iter()
+            .position(|name| column.column == **name)
+            .map_or(SortKey::Input(expr), SortKey::Output)),
+        _ => Ok(SortKey::Input(expr)),
+    }
+}
+
+/// The result columns `order_by` sorts by, for a query that only has the columns of
+/// its result left to sort by, such as a set operation. A key must name one of them or
+/// be its position, `not_a_column` makes the error for one that doesn't. The error
+/// comes with where the key was written
+pub(crate) fn sort_columns(
+    order_by: &[OrderByExpr],
+    output_names: &[&str],
+    not_a_column: impl Fn() -> ExecutionError,
+) -> Result<Vec<usize>, (ExecutionError, Span)> {
+    order_by
+        .iter()
+        .map(
+            |order_by| match resolve_sort_key(&order_by.expr, output_names) {
+                Ok(SortKey::Output(index)) => Ok(index),
+                Ok(SortKey::Input(expr)) => Err((not_a_column(), expr.span())),
+                Err(position) => Err((
+                    ExecutionError::OrderByPositionOutOfRange(position),
+                    order_by.expr.span(),
+                )),
+            },
+        )
+        .collect()
+}
+
+/// Whether `expr` is one of the `selected` expressions, a column however it is
+/// qualified. A SELECT DISTINCT can only be sorted by those
+pub(crate) fn in_select_list(expr: &Expression, selected: &[&Expression], scope: &Scope) -> bool {
+    selected.iter().any(|selected| match (selected, expr) {
+        (Expression::Column(selected), Expression::Column(column)) => {
+            let location = scope.locate(selected);
+            location.is_ok() && location == scope.locate(column)
+        }
+        _ => *selected == expr,
+    })
+}
+
+/// Direction and NULL placement of one ORDER BY key
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct SortOrder {
+    descending: bool,
+    nulls_first: bool,
+}
+
+impl SortOrder {
+    pub fn is_descending(&self) -> bool {
+        self.descending
+    }
+}
+
+impl From<&OrderByExpr> for SortOrder {
+    fn from(order_by: &OrderByExpr) -> Self {
+        let descending = order_by.direction == OrderDirection::Desc;
+        Self {
+            descending,
+            // NULLs sort as larger than every value unless told otherwise
+            nulls_first: match order_by.nulls {
+                Some(nulls) => nulls == NullsOrder::First,
+                None => descending,
+            },
+        }
+    }
+}
+
+/// Compare two rows by their ORDER BY keys, `orders` saying how to compare each
+pub(crate) fn compare_keys(orders: &[SortOrder], left: &[Value], right: &[Value]) -> Ordering {
+    for ((order, left), right) in orders.iter().zip(left).zip(right) {
+        let ordering = match (left, right) {
+            (Value::Null, Value::Null) => Ordering::Equal,
+            (Value::Null, _) if order.nulls_first => Ordering::Less,
+            (Value::Null, _) => Ordering::Greater,
+            (_, Value::Null) if order.nulls_first => Ordering::Greater,
+            (_, Value::Null) => Ordering::Less,
+            _ => {
+                // values of different types can't be ordered, they are kept as equal
+                let ordering = compare_values(left, right).unwrap_or(Ordering::Equal);
+                if order.descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            }
+        };
+        if ordering.is_ne() {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/// Sorts rows by their ORDER BY keys. With a limit only the first rows can be
+/// returned, so only about twice that many are kept while rows are added instead
+/// of all of them (a top-N sort)
+#[derive(Debug)]
+pub(crate) struct Sorter {
+    orders: Vec<SortOrder>,
+    keep: Option<usize>,
+    // the keys, the position the row was added at so equal rows keep their order,
+    // and the row
+    rows: Vec<(Vec<Value>, usize, Row)>,
+    added: usize,
+}
+
+impl Sorter {
+    /// A sorter returning at most the first `keep` rows, or all of them
+    pub fn new(orders: Vec<SortOrder>, keep: Option<usize>) -> Self {
+        Self {
+            orders,
+            keep,
+            rows: Vec::new(),
+            added: 0,
+        }
+    }
+
+    pub fn push(&mut self, keys: Vec<Value>, row: Row) {
+        self.rows.push((keys, self.added, row));
+        self.added += 1;
+
+        if let Some(keep) = self.keep {
+            if self.rows.len() > keep.saturating_mul(2).max(1) {
+                self.prune(keep);
+            }
+        }
+    }
+
+    // drops all but the first `keep` rows, which don't need to be sorted yet
+    fn prune(&mut self, keep: usize) {
+        let orders = &self.orders;
+        if keep < self.rows.len() {
+            self.rows.select_nth_unstable_by(keep, |left, right| {
+                compare_keys(orders, &left.0, &right.0).then(left.1.cmp(&right.1))
+            });
+        }
+        self.rows.truncate(keep);
+    }
+
+    /// The rows in order
+    pub fn finish(self) -> Vec<Row> {
+        self.finish_with_keys()
+            .into_iter()
+            .map(|(_, row)| row)
+            .collect()
+    }
+
+    /// The rows in order with their keys. Keys past the sort orders the sorter was
+    /// made with are carried along without being compared
+    pub fn finish_with_keys(mut self) -> Vec<(Vec<Value>, Row)> {
+        if let Some(keep) = self.keep {
+            self.prune(keep);
+        }
+        let orders = &self.orders;
+        self.rows.sort_by(|left, right| {
+            compare_keys(orders, &left.0, &right.0).then(left.1.cmp(&right.1))
+        });
+        self.rows
+            .into_iter()
+            .map(|(keys, _, row)| (keys, row))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use sql_jr_parser::{select_statement::SelectStatement, types::Parse};
+
+    use super::*;
+
+    fn orders(raw: &str) -> Vec<SortOrder> {
+        let (_, select) = SelectStatement::parse_from_raw(raw).unwrap();
+        select.order_by.iter().map(SortOrder::from).collect()
+    }
+
+    fn sorted(orders: Vec<SortOrder>, keep: Option<usize>, keys: &[Value]) -> Vec<Row> {
+        let mut sorter = Sorter::new(orders, keep);
+        for key in keys {
+            sorter.push(vec![key.clone()], vec![key.clone()]);
+        }
+        sorter.finish()
+    }
+
+    #[test]
+    fn test_nulls_are_larger_unless_told_otherwise() {
+        let keys = [Value::Int(2), Value::Null, Value::Int(1)];
+        let sorted = |raw| sorted(orders(raw), None, &keys).concat();
+
+        assert_eq!(
+            sorted("SELECT a FROM t ORDER BY a"),
+            vec![Value::Int(1), Value::Int(2), Value::Null]
+        );
+        assert_eq!(
+            sorted("SELECT a FROM t ORDER BY a DESC"),
+            vec![Value::Null, Value::Int(2), Value::Int(1)]
+        );
+        assert_eq!(
+            sorted("SELECT a FROM t ORDER BY a DESC NULLS LAST"),
+            vec![Value::Int(2), Value::Int(1), Value::Null]
+        );
+    }
+
+    #[test]
+    fn test_top_n_keeps_the_first_rows() {
+        let keys: Vec<Value> = (0..100).rev().map(Value::Int).collect();
+
+        let rows = sorted(orders("SELECT a FROM t ORDER BY a"), Some(3), &keys);
+
+        assert_eq!(
+            rows.concat(),
+            vec![Value::Int(0), Value::Int(1), Value::Int(2)]
+        );
+    }
+
+    #[test]
+    fn test_equal_rows_keep_their_order() {
+        let mut sorter = Sorter::new(orders("SELECT a FROM t ORDER BY a"), Some(2));
+        for i in 0..10 {
+            sorter.push(vec![Value::Int(0)], vec![Value::Int(i)]);
+        }
+
+        assert_eq!(
+            sorter.finish(),
+            vec![vec![Value::Int(0)], vec![Value::Int(1)]]
+        );
+    }
+
+    #[test]
+    fn test_resolve_sort_key() {
+        let names = ["name", "older"];
+        let expression = |raw| Expression::parse_from_raw(raw).unwrap().1;
+
+        assert_eq!(
+            resolve_sort_key(&expression("2"), &names),
+            Ok(SortKey::Output(1))
+        );
+        assert_eq!(
+            resolve_sort_key(&expression("older"), &names),
+            Ok(SortKey::Output(1))
+        );
+        let age = expression("age");
+        assert_eq!(resolve_sort_key(&age, &names), Ok(SortKey::Input(&age)));
+        assert_eq!(resolve_sort_key(&expression("3"), &names), Err(3));
+    }
+}
@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+
+use super::{
+    page::Page,
+    pager::{PageNo, Pager},
+    StorageResult,
+};
+
+/// Number of pages the pool of a database keeps in memory
+pub const DEFAULT_CAPACITY: usize = 256;
+
+/// Identifies one of the files registered with a [`BufferPool`]
+pub type FileId = u32;
+
+#[derive(Debug)]
+struct Frame {
+    page: Page,
+    // the page was changed since it was read and must be written back
+    dirty: bool,
+    // value of the pool's clock the last time the page was used
+    last_used: u64,
+}
+
+/// Caches the pages of all the files of a database, evicting the least recently
+/// used page when full. Changed pages are written back when evicted or flushed.
+#[derive(Debug)]
+pub struct BufferPool {
+    capacity: usize,
+    pagers: HashMap<FileId, Box<dyn Pager>>,
+    next_file: FileId,
+    frames: HashMap<(FileId, PageNo), Frame>,
+    clock: u64,
+}
+
+impl Default for BufferPool {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl BufferPool {
+    /// A pool holding at most `capacity` pages in memory
+    pub fn new(capacity: usize) -> Self {
+        assert!(
+            capacity > 0,
+            "a buffer pool needs room for at least one page"
+        );
+        Self {
+            capacity,
+            pagers: HashMap::new(),
+            next_file: 0,
+            frames: HashMap::new(),
+            clock: 0,
+        }
+    }
+
+    /// Start caching the pages of `pager`
+    pub fn register(&mut self, pager: Box<dyn Pager>) -> FileId {
+        let file = self.next_file;
+        self.next_file += 1;
+        self.pagers.insert(file, pager);
+        file
+    }
+
+    /// Stop caching the pages of `file`, dropping its changed pages
+    pub fn unregister(&mut self, file: FileId) {
+        self.pagers.remove(&file);
+        self.frames.retain(|&(f, _), _| f != file);
+    }
+
+    /// Remove every page of `file`, including the ones in memory
+    pub fn truncate(&mut self, file: FileId) -> StorageResult<()> {
+        self.frames.retain(|&(f, _), _| f != file);
+        self.pagers.get_mut(&file).unwrap().truncate()
+    }
+
+    pub fn page_count(&self, file: FileId) -> PageNo {
+        self.pager(file).page_count()
+    }
+
+    fn pager(&self, file: FileId) -> &dyn Pager {
+        self.pagers
+            .get(&file)
+            .expect("file is registered with the buffer pool")
+            .as_ref()
+    }
+
+    /// Add an empty page at the end of `file`, returning its number
+    pub fn allocate(&mut self, file: FileId) -> StorageResult<PageNo> {
+        let page_no = self.page_count(file);
+        // written straight away so the file's page count includes it
+        self.pagers
+            .get_mut(&file)
+            .unwrap()
+            .write_page(page_no, &Page::new())?;
+        Ok(page_no)
+    }
+
+    /// The page `page_no` of `file`, to read
+    pub fn read(&mut self, file: FileId, page_no: PageNo) -> StorageResult<&Page> {
+        Ok(&self.frame(file, page_no)?.page)
+    }
+
+    /// The page `page_no` of `file`, to change
+    pub fn write(&mut self, file: FileId, page_no: PageNo) -> StorageResult<&mut Page> {
+        let frame = self.frame(file, page_no)?;
+        frame.dirty = true;
+        Ok(&mut frame.page)
+    }
+
+    fn frame(&mut self, file: FileId, page_no: PageNo) -> StorageResult<&mut Frame> {
+        self.clock += 1;
+        let key = (file, page_no);
+        if !self.frames.contains_key(&key) {
+            if self.frames.len() >= self.capacity {
+                self.evict()?;
+            }
+            let page = self.pagers.get_mut(&file).unwrap().read_page(page_no)?;
+            self.frames.insert(
+                key,
+                Frame {
+                    page,
+                    dirty: false,
+                    last_used: 0,
+                },
+            );
+        }
+        let frame = self.frames.get_mut(&key).unwrap();
+        frame.last_used = self.clock;
+        Ok(frame)
+    }
+
+    fn evict(&mut self) -> StorageResult<()> {
+        let Some(&key) = self
+            .frames
+            .iter()
+            .min_by_key(|(_, frame)| frame.last_used)
+            .map(|(key, _)| key)
+        else {
+            return Ok(());
+        };
+        let frame = self.frames.remove(&key).unwrap();
+        if frame.dirty {
+            let (file, page_no) = key;
+            self.pagers
+                .get_mut(&file)
+                .unwrap()
+                .write_page(page_no, &frame.page)?;
+        }
+        Ok(())
+    }
+
+    /// Write every changed page back to its file and make them durable
+    pub fn flush(&mut self) -> StorageResult<()> {
+        for (&(file, page_no), frame) in self.frames.iter_mut().filter(|(_, f)| f.dirty) {
+            self.pagers
+                .get_mut(&file)
+                .unwrap()
+                .write_page(page_no, &frame.page)?;
+            frame.dirty = false;
+        }
+        for pager in self.pagers.values_mut() {
+            pager.sync()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::storage::pager::MemoryPager;
+
+    #[test]
+    fn test_evicted_pages_are_written_back() {
+        let mut pool = BufferPool::new(2);
+        let file = pool.register(Box::<MemoryPager>::default());
+        for _ in 0..3 {
+            pool.allocate(file).unwrap();
+        }
+
+        for page_no in 0..3 {
+            pool.write(file, page_no)
+                .unwrap()
+                .insert(&[page_no as u8])
+                .unwrap();
+        }
+        assert_eq!(pool.frames.len(), 2);
+
+        for page_no in 0..3 {
+            let page = pool.read(file, page_no).unwrap();
+            assert_eq!(page.get(0), Some(&[page_no as u8][..]));
+        }
+    }
+
+    #[test]
+    fn test_least_recently_used_page_is_evicted() {
+        let mut pool = BufferPool::new(2);
+        let file = pool.register(Box::<MemoryPager>::default());
+        for _ in 0..3 {
+            pool.allocate(file).unwrap();
+        }
+
+        pool.read(file, 0).unwrap();
+        pool.read(file, 1).unwrap();
+        pool.read(file, 0).unwrap();
+        pool.read(file, 2).unwrap();
+
+        assert!(pool.frames.contains_key(&(file, 0)));
+        assert!(!pool.frames.contains_key(&(file, 1)));
+    }
+}
@@ -0,0 +1,73 @@
+use std::{fs, io::ErrorKind, path::Path};
+
+use serde::{Deserialize, Serialize};
+use sql_jr_parser::create_statement::CreateStatement;
+
+use super::{StorageError, StorageResult};
+
+/// Name of the file the catalog is kept in, inside the data directory
+pub const CATALOG_FILE: &str = "catalog.json";
+
+/// A table as recorded in the catalog file: its schema and the heap file its rows
+/// are in, relative to the data directory
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StoredTable {
+    pub file: String,
+    pub schema: CreateStatement,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CatalogFile {
+    tables: Vec<StoredTable>,
+}
+
+/// The tables recorded in the catalog of `data_dir`, none if it has no catalog yet
+pub fn load(data_dir: &Path) -> StorageResult<Vec<StoredTable>> {
+    let json = match fs::read_to_string(data_dir.join(CATALOG_FILE)) {
+        Ok(json) => json,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let catalog: CatalogFile =
+        serde_json::from_str(&json).map_err(|e| StorageError::Corrupt(e.to_string()))?;
+    Ok(catalog.tables)
+}
+
+/// Replace the catalog of `data_dir` with `tables`
+pub fn save(data_dir: &Path, mut tables: Vec<StoredTable>) -> StorageResult<()> {
+    tables.sort_by_key(|table| table.schema.table.to_string());
+    let json = serde_json::to_string_pretty(&CatalogFile { tables })
+        .map_err(|e| StorageError::Corrupt(e.to_string()))?;
+
+    // written next to the old catalog and renamed over it, so a crash leaves one
+    // or the other but never half of each
+    let path = data_dir.join(CATALOG_FILE);
+    let temporary = path.with_extension("json.tmp");
+    fs::write(&temporary, json)?;
+    fs::File::open(&temporary)?.sync_all()?;
+    fs::rename(&temporary, &path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use sql_jr_parser::types::Parse;
+
+    use super::*;
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let table = StoredTable {
+            file: "0.heap".to_string(),
+            schema: CreateStatement::parse_from_raw("CREATE TABLE Person (name string, age int)")
+                .unwrap()
+                .1,
+        };
+
+        assert_eq!(load(dir.path()), Ok(vec![]));
+        save(dir.path(), vec![table.clone()]).unwrap();
+
+        assert_eq!(load(dir.path()), Ok(vec![table]));
+    }
+}
@@ -0,0 +1,53 @@
+use super::pager::PageNo;
+
+/// How many bytes are free in each page of a heap file, so inserts don't have to
+/// read every page to find room. It isn't stored: it is rebuilt from the pages
+/// when the file is opened.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FreeSpaceMap {
+    free: Vec<usize>,
+}
+
+impl FreeSpaceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that page `page_no` has `free` bytes free, the map grows to cover it
+    pub fn update(&mut self, page_no: PageNo, free: usize) {
+        let index = page_no as usize;
+        if index >= self.free.len() {
+            self.free.resize(index + 1, 0);
+        }
+        self.free[index] = free;
+    }
+
+    /// The first page with at least `needed` bytes free
+    pub fn find(&self, needed: usize) -> Option<PageNo> {
+        self.free
+            .iter()
+            .position(|&free| free >= needed)
+            .map(|index| index as PageNo)
+    }
+
+    pub fn clear(&mut self) {
+        self.free.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_find_first_page_with_room() {
+        let mut map = FreeSpaceMap::new();
+        map.update(0, 10);
+        map.update(2, 500);
+        map.update(1, 200);
+
+        assert_eq!(map.find(100), Some(1));
+        assert_eq!(map.find(300), Some(2));
+        assert_eq!(map.find(1000), None);
+    }
+}
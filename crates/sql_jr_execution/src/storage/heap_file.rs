@@ -0,0 +1,241 @@
+use sql_jr_parser::value::Value;
+
+use super::{
+    buffer_pool::{BufferPool, FileId},
+    free_space_map::FreeSpaceMap,
+    page::{SlotId, MAX_RECORD_SIZE, SLOT_SIZE},
+    pager::PageNo,
+    record::{decode_row, encode_row},
+    StorageError, StorageResult,
+};
+use crate::table::Row;
+
+/// Where a row is stored: its page and its slot in that page
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct RowId {
+    pub page: PageNo,
+    pub slot: SlotId,
+}
+
+/// The rows of a table, stored unordered in the pages of a file
+#[derive(Debug)]
+pub struct HeapFile {
+    file: FileId,
+    free_space: FreeSpaceMap,
+}
+
+impl HeapFile {
+    /// The heap stored in `file`, which is registered with `pool`
+    pub fn open(pool: &mut BufferPool, file: FileId) -> StorageResult<Self> {
+        let mut free_space = FreeSpaceMap::new();
+        for page_no in 0..pool.page_count(file) {
+            free_space.update(page_no, pool.read(file, page_no)?.free_space());
+        }
+        Ok(Self { file, free_space })
+    }
+
+    pub fn file(&self) -> FileId {
+        self.file
+    }
+
+    /// Store `row` in the first page with room for it, adding a page when none has
+    pub fn insert(&mut self, pool: &mut BufferPool, row: &[Value]) -> StorageResult<RowId> {
+        let record = encode(row)?;
+        let page_no = match self.free_space.find(record.len() + SLOT_SIZE) {
+            Some(page_no) => page_no,
+            None => pool.allocate(self.file)?,
+        };
+        let page = pool.write(self.file, page_no)?;
+        let slot = page
+            .insert(&record)
+            .expect("the free space map only returns pages with room");
+        self.free_space.update(page_no, page.free_space());
+        Ok(RowId {
+            page: page_no,
+            slot,
+        })
+    }
+
+    /// The row stored at `id`, if it is still there
+    pub fn get(&self, pool: &mut BufferPool, id: RowId) -> StorageResult<Option<Row>> {
+        if id.page >= pool.page_count(self.file) {
+            return Ok(None);
+        }
+        match pool.read(self.file, id.page)?.get(id.slot) {
+            Some(record) => decode_row(record).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Replace the row stored at `id` with `row`. A row that no longer fits its page
+    /// is moved, so the id it is now stored at is returned
+    pub fn update(
+        &mut self,
+        pool: &mut BufferPool,
+        id: RowId,
+        row: &[Value],
+    ) -> StorageResult<RowId> {
+        let record = encode(row)?;
+        let page = pool.write(self.file, id.page)?;
+        if page.update(id.slot, &record) {
+            self.free_space.update(id.page, page.free_space());
+            return Ok(id);
+        }
+        self.delete(pool, id)?;
+        self.insert(pool, row)
+    }
+
+    /// Remove the row at `id`, returns whether there was one
+    pub fn delete(&mut self, pool: &mut BufferPool, id: RowId) -> StorageResult<bool> {
+        if id.page >= pool.page_count(self.file) {
+            return Ok(false);
+        }
+        let page = pool.write(self.file, id.page)?;
+        let deleted = page.delete(id.slot);
+        self.free_space.update(id.page, page.free_space());
+        Ok(deleted)
+    }
+
+    /// Remove every row, giving back the pages they were in
+    pub fn truncate(&mut self, pool: &mut BufferPool) -> StorageResult<()> {
+        pool.truncate(self.file)?;
+        self.free_space.clear();
+        Ok(())
+    }
+
+    /// Every row of the heap with its id, in storage order
+    pub fn scan(&self, pool: &mut BufferPool) -> StorageResult<Vec<(RowId, Row)>> {
+        let mut rows = Vec::new();
+        for page_no in 0..pool.page_count(self.file) {
+            for (slot, record) in pool.read(self.file, page_no)?.records() {
+                rows.push((
+                    RowId {
+                        page: page_no,
+                        slot,
+                    },
+                    decode_row(record)?,
+                ));
+            }
+        }
+        Ok(rows)
+    }
+}
+
+fn encode(row: &[Value]) -> StorageResult<Vec<u8>> {
+    let record = encode_row(row);
+    if record.len() > MAX_RECORD_SIZE {
+        return Err(StorageError::RowTooLarge {
+            size: record.len(),
+            max: MAX_RECORD_SIZE,
+        });
+    }
+    Ok(record)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::storage::pager::MemoryPager;
+
+    fn heap() -> (BufferPool, HeapFile) {
+        let mut pool = BufferPool::new(4);
+        let file = pool.register(Box::<MemoryPager>::default());
+        let heap = HeapFile::open(&mut pool, file).unwrap();
+        (pool, heap)
+    }
+
+    fn row(i: i64) -> Row {
+        vec![Value::Int(i), Value::String("x".repeat(100))]
+    }
+
+    #[test]
+    fn test_rows_spill_into_new_pages() {
+        let (mut pool, mut heap) = heap();
+
+        let ids: Vec<RowId> = (0..100)
+            .map(|i| heap.insert(&mut pool, &row(i)).unwrap())
+            .collect();
+
+        assert!(pool.page_count(heap.file()) > 1);
+        assert_eq!(heap.get(&mut pool, ids[99]).unwrap(), Some(row(99)));
+        let scanned: Vec<Row> = heap
+            .scan(&mut pool)
+            .unwrap()
+            .into_iter()
+            .map(|(_, row)| row)
+            .collect();
+        assert_eq!(scanned, (0..100).map(row).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_deleted_space_is_reused() {
+        let (mut pool, mut heap) = heap();
+        let ids: Vec<RowId> = (0..100)
+            .map(|i| heap.insert(&mut pool, &row(i)).unwrap())
+            .collect();
+        let pages = pool.page_count(heap.file());
+
+        assert!(heap.delete(&mut pool, ids[0]).unwrap());
+        assert!(!heap.delete(&mut pool, ids[0]).unwrap());
+        let id = heap.insert(&mut pool, &row(100)).unwrap();
+
+        assert_eq!(id, ids[0]);
+        assert_eq!(pool.page_count(heap.file()), pages);
+        assert!(!heap.delete(&mut pool, RowId { page: 99, slot: 0 }).unwrap());
+    }
+
+    #[test]
+    fn test_update_moves_rows_that_outgrow_their_page() {
+        let (mut pool, mut heap) = heap();
+        let ids: Vec<RowId> = (0..100)
+            .map(|i| heap.insert(&mut pool, &row(i)).unwrap())
+            .collect();
+        let grown = vec![Value::Int(0), Value::String("y".repeat(1000))];
+
+        let id = heap.update(&mut pool, ids[0], &grown).unwrap();
+
+        assert_ne!(id, ids[0]);
+        assert_eq!(heap.get(&mut pool, ids[0]).unwrap(), None);
+        assert_eq!(heap.get(&mut pool, id).unwrap(), Some(grown));
+    }
+
+    #[test]
+    fn test_free_space_is_rebuilt_on_open() {
+        let (mut pool, mut heap) = heap();
+        let ids: Vec<RowId> = (0..100)
+            .map(|i| heap.insert(&mut pool, &row(i)).unwrap())
+            .collect();
+        heap.delete(&mut pool, ids[0]).unwrap();
+
+        let mut reopened = HeapFile::open(&mut pool, heap.file()).unwrap();
+
+        assert_eq!(reopened.free_space, heap.free_space);
+        assert_eq!(reopened.insert(&mut pool, &row(0)).unwrap(), ids[0]);
+    }
+
+    #[test]
+    fn test_truncate_removes_every_page() {
+        let (mut pool, mut heap) = heap();
+        for i in 0..100 {
+            heap.insert(&mut pool, &row(i)).unwrap();
+        }
+
+        heap.truncate(&mut pool).unwrap();
+
+        assert_eq!(pool.page_count(heap.file()), 0);
+        assert_eq!(heap.scan(&mut pool).unwrap(), vec![]);
+        assert_eq!(
+            heap.insert(&mut pool, &row(0)).unwrap(),
+            RowId { page: 0, slot: 0 }
+        );
+    }
+
+    #[test]
+    fn test_too_large_row_is_an_error() {
+        let (mut pool, mut heap) = heap();
+
+        let result = heap.insert(&mut pool, &[Value::String("x".repeat(5000))]);
+
+        assert!(matches!(result, Err(StorageError::RowTooLarge { .. })));
+    }
+}
@@ -0,0 +1,38 @@
+//! Rows are stored in fixed size slotted [pages](page::Page) that make up a
+//! [heap file](heap_file::HeapFile) per table. Pages are read and written through
+//! a [buffer pool](buffer_pool::BufferPool) that keeps the most recently used
+//! ones in memory, and each heap file keeps a [free space map](free_space_map::FreeSpaceMap)
+//! to find a page with room for a new row.
+
+use std::io;
+
+use thiserror::Error;
+
+pub mod buffer_pool;
+pub mod catalog_file;
+pub mod free_space_map;
+pub mod heap_file;
+pub mod page;
+pub mod pager;
+mod record;
+
+/// Errors raised while reading or writing the stored tables
+#[derive(Debug, Error, PartialEq)]
+pub enum StorageError {
+    #[error("i/o error: {0}")]
+    Io(String),
+
+    #[error("row of {size} bytes does not fit in a page, at most {max} bytes are allowed")]
+    RowTooLarge { size: usize, max: usize },
+
+    #[error("corrupt data: {0}")]
+    Corrupt(String),
+}
+
+impl From<io::Error> for StorageError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error.to_string())
+    }
+}
+
+pub type StorageResult<T> = Result<T, StorageError>;
@@ -0,0 +1,305 @@
+/// Size in bytes of every page, on disk and in memory
+pub const PAGE_SIZE: usize = 4096;
+
+// the header holds the number of slots and where the record area starts
+const HEADER_SIZE: usize = 4;
+/// Bytes taken by the slot pointing at each record, it holds the record's offset and length
+pub const SLOT_SIZE: usize = 4;
+
+/// Largest record a single page can hold
+pub const MAX_RECORD_SIZE: usize = PAGE_SIZE - HEADER_SIZE - SLOT_SIZE;
+
+/// Position of a record in its page, it doesn't change while the record lives
+pub type SlotId = u16;
+
+/// A slotted page: a slot array growing from the start of the page pointing at
+/// records packed from its end. A deleted record leaves an empty slot behind so
+/// the ids of the other records stay the same.
+///
+/// ```text
+/// | slot count | records start | slot 0 | slot 1 | ... free ... | record 1 | record 0 |
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct Page {
+    data: Box<[u8; PAGE_SIZE]>,
+}
+
+impl std::fmt::Debug for Page {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Page")
+            .field("slots", &self.slot_count())
+            .field("free_space", &self.free_space())
+            .finish()
+    }
+}
+
+impl Default for Page {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Page {
+    /// An empty page
+    pub fn new() -> Self {
+        let mut page = Self {
+            data: Box::new([0; PAGE_SIZE]),
+        };
+        page.set_records_start(PAGE_SIZE);
+        page
+    }
+
+    pub fn from_bytes(bytes: [u8; PAGE_SIZE]) -> Self {
+        Self {
+            data: Box::new(bytes),
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8; PAGE_SIZE] {
+        &self.data
+    }
+
+    fn read_u16(&self, at: usize) -> usize {
+        u16::from_le_bytes([self.data[at], self.data[at + 1]]) as usize
+    }
+
+    fn write_u16(&mut self, at: usize, value: usize) {
+        // PAGE_SIZE fits in a u16 so every offset and length does too
+        self.data[at..at + 2].copy_from_slice(&(value as u16).to_le_bytes());
+    }
+
+    fn slot_count(&self) -> usize {
+        self.read_u16(0)
+    }
+
+    fn set_slot_count(&mut self, count: usize) {
+        self.write_u16(0, count)
+    }
+
+    fn records_start(&self) -> usize {
+        self.read_u16(2)
+    }
+
+    fn set_records_start(&mut self, start: usize) {
+        self.write_u16(2, start)
+    }
+
+    // offset and length of the record in `slot`, an offset of 0 marks an empty slot
+    fn slot(&self, slot: usize) -> (usize, usize) {
+        let at = HEADER_SIZE + slot * SLOT_SIZE;
+        (self.read_u16(at), self.read_u16(at + 2))
+    }
+
+    fn set_slot(&mut self, slot: usize, offset: usize, length: usize) {
+        let at = HEADER_SIZE + slot * SLOT_SIZE;
+        self.write_u16(at, offset);
+        self.write_u16(at + 2, length);
+    }
+
+    fn slots_end(&self) -> usize {
+        HEADER_SIZE + self.slot_count() * SLOT_SIZE
+    }
+
+    fn empty_slot(&self) -> Option<usize> {
+        (0..self.slot_count()).find(|&slot| self.slot(slot).0 == 0)
+    }
+
+    /// Bytes available for new records and their slots, including the space left
+    /// by deleted records
+    pub fn free_space(&self) -> usize {
+        let used: usize = self.records().map(|(_, record)| record.len()).sum();
+        PAGE_SIZE - self.slots_end() - used
+    }
+
+    /// Bytes a new record of `length` bytes takes up in this page
+    pub fn space_needed(&self, length: usize) -> usize {
+        match self.empty_slot() {
+            Some(_) => length,
+            None => length + SLOT_SIZE,
+        }
+    }
+
+    /// The record in `slot`, if there is one
+    pub fn get(&self, slot: SlotId) -> Option<&[u8]> {
+        let slot = slot as usize;
+        if slot >= self.slot_count() {
+            return None;
+        }
+        match self.slot(slot) {
+            (0, _) => None,
+            (offset, length) => Some(&self.data[offset..offset + length]),
+        }
+    }
+
+    /// All the records of the page with their slots
+    pub fn records(&self) -> impl Iterator<Item = (SlotId, &[u8])> {
+        (0..self.slot_count()).filter_map(|slot| {
+            let slot = slot as SlotId;
+            self.get(slot).map(|record| (slot, record))
+        })
+    }
+
+    /// Store `record`, returning its slot or None when the page is too full
+    pub fn insert(&mut self, record: &[u8]) -> Option<SlotId> {
+        let needed = self.space_needed(record.len());
+        if needed > self.free_space() {
+            return None;
+        }
+        // make room before a new slot can be written over a record
+        if self.records_start() - self.slots_end() < needed {
+            self.compact();
+        }
+        let slot = match self.empty_slot() {
+            Some(slot) => slot,
+            None => {
+                let slot = self.slot_count();
+                self.set_slot_count(slot + 1);
+                self.set_slot(slot, 0, 0);
+                slot
+            }
+        };
+        self.place(slot, record);
+        Some(slot as SlotId)
+    }
+
+    /// Replace the record in `slot`, returns false when the new record doesn't fit
+    /// in this page, in which case the page is unchanged
+    pub fn update(&mut self, slot: SlotId, record: &[u8]) -> bool {
+        let Some(old) = self.get(slot) else {
+            return false;
+        };
+        let old_length = old.len();
+        let slot = slot as usize;
+
+        if record.len() <= old_length {
+            let (offset, _) = self.slot(slot);
+            self.data[offset..offset + record.len()].copy_from_slice(record);
+            self.set_slot(slot, offset, record.len());
+            return true;
+        }
+        if record.len() > self.free_space() + old_length {
+            return false;
+        }
+        self.set_slot(slot, 0, 0);
+        self.place(slot, record);
+        true
+    }
+
+    /// Remove the record in `slot`, returns whether there was one
+    pub fn delete(&mut self, slot: SlotId) -> bool {
+        if self.get(slot).is_none() {
+            return false;
+        }
+        self.set_slot(slot as usize, 0, 0);
+        true
+    }
+
+    // writes `record` to the record area and points the empty `slot` at it, the
+    // caller has checked it fits
+    fn place(&mut self, slot: usize, record: &[u8]) {
+        if self.records_start() - self.slots_end() < record.len() {
+            self.compact();
+        }
+        let offset = self.records_start() - record.len();
+        self.data[offset..offset + record.len()].copy_from_slice(record);
+        self.set_records_start(offset);
+        self.set_slot(slot, offset, record.len());
+    }
+
+    // moves the records to the end of the page so the space left by deleted or
+    // shrunk records is contiguous again
+    fn compact(&mut self) {
+        let records: Vec<(usize, Vec<u8>)> = self
+            .records()
+            .map(|(slot, record)| (slot as usize, record.to_vec()))
+            .collect();
+
+        let mut start = PAGE_SIZE;
+        for (slot, record) in records {
+            start -= record.len();
+            self.data[start..start + record.len()].copy_from_slice(&record);
+            self.set_slot(slot, start, record.len());
+        }
+        self.set_records_start(start);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_insert_and_get() {
+        let mut page = Page::new();
+
+        let first = page.insert(b"hello").unwrap();
+        let second = page.insert(b"world!").unwrap();
+
+        assert_eq!(page.get(first), Some(&b"hello"[..]));
+        assert_eq!(page.get(second), Some(&b"world!"[..]));
+        assert_eq!(
+            page.free_space(),
+            PAGE_SIZE - HEADER_SIZE - 2 * SLOT_SIZE - 11
+        );
+    }
+
+    #[test]
+    fn test_full_page_rejects_records() {
+        let mut page = Page::new();
+
+        assert!(page.insert(&[1; MAX_RECORD_SIZE]).is_some());
+        assert_eq!(page.insert(b"x"), None);
+    }
+
+    #[test]
+    fn test_delete_keeps_other_slots_and_reuses_its_own() {
+        let mut page = Page::new();
+        let first = page.insert(b"one").unwrap();
+        let second = page.insert(b"two").unwrap();
+
+        assert!(page.delete(first));
+        assert_eq!(page.get(first), None);
+        assert_eq!(page.get(second), Some(&b"two"[..]));
+        assert_eq!(page.insert(b"three"), Some(first));
+    }
+
+    #[test]
+    fn test_deleted_space_is_reclaimed() {
+        let mut page = Page::new();
+        let half = MAX_RECORD_SIZE / 2;
+        let first = page.insert(&vec![1; half]).unwrap();
+        let second = page.insert(&vec![2; half - SLOT_SIZE]).unwrap();
+        assert_eq!(page.insert(&vec![3; half]), None);
+
+        page.delete(first);
+
+        assert_eq!(page.insert(&vec![3; half]), Some(first));
+        assert_eq!(page.get(first), Some(&vec![3; half][..]));
+        assert_eq!(page.get(second), Some(&vec![2; half - SLOT_SIZE][..]));
+    }
+
+    #[test]
+    fn test_update_grows_and_shrinks_records() {
+        let mut page = Page::new();
+        let first = page.insert(b"short").unwrap();
+        let second = page.insert(b"other").unwrap();
+
+        assert!(page.update(first, b"a much longer record"));
+        assert!(page.update(second, b"o"));
+
+        assert_eq!(page.get(first), Some(&b"a much longer record"[..]));
+        assert_eq!(page.get(second), Some(&b"o"[..]));
+        assert!(!page.update(first, &[0; MAX_RECORD_SIZE]));
+        assert_eq!(page.get(first), Some(&b"a much longer record"[..]));
+    }
+
+    #[test]
+    fn test_round_trips_through_bytes() {
+        let mut page = Page::new();
+        page.insert(b"kept").unwrap();
+
+        let copy = Page::from_bytes(*page.as_bytes());
+
+        assert_eq!(copy.records().collect::<Vec<_>>(), vec![(0, &b"kept"[..])]);
+    }
+}
@@ -0,0 +1,157 @@
+use std::{
+    fmt::Debug,
+    fs::{File, OpenOptions},
+    io::{Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use super::{
+    page::{Page, PAGE_SIZE},
+    StorageResult,
+};
+
+/// Number of a page within its file
+pub type PageNo = u32;
+
+/// Where the pages of a heap file are kept between the times they are cached
+/// in the [buffer pool](super::buffer_pool::BufferPool)
+pub trait Pager: Debug {
+    fn page_count(&self) -> PageNo;
+
+    fn read_page(&mut self, page_no: PageNo) -> StorageResult<Page>;
+
+    /// Write `page` at `page_no`, which is at most [`Pager::page_count`] so a
+    /// file grows by appending one page at a time
+    fn write_page(&mut self, page_no: PageNo, page: &Page) -> StorageResult<()>;
+
+    /// Remove every page
+    fn truncate(&mut self) -> StorageResult<()>;
+
+    /// Make the pages written so far durable
+    fn sync(&mut self) -> StorageResult<()>;
+}
+
+/// Pages stored one after the other in a file on disk
+#[derive(Debug)]
+pub struct FilePager {
+    file: File,
+    page_count: PageNo,
+}
+
+impl FilePager {
+    /// Open the file at `path`, creating it when it doesn't exist yet
+    pub fn open(path: &Path) -> StorageResult<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        // a page only partially written when the process stopped is ignored
+        let page_count = (file.metadata()?.len() / PAGE_SIZE as u64) as PageNo;
+        Ok(Self { file, page_count })
+    }
+
+    /// Create an empty file at `path`, replacing any file already there
+    pub fn create(path: &Path) -> StorageResult<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        Ok(Self {
+            file,
+            page_count: 0,
+        })
+    }
+}
+
+impl Pager for FilePager {
+    fn page_count(&self) -> PageNo {
+        self.page_count
+    }
+
+    fn read_page(&mut self, page_no: PageNo) -> StorageResult<Page> {
+        let mut bytes = [0; PAGE_SIZE];
+        self.file
+            .seek(SeekFrom::Start(page_no as u64 * PAGE_SIZE as u64))?;
+        self.file.read_exact(&mut bytes)?;
+        Ok(Page::from_bytes(bytes))
+    }
+
+    fn write_page(&mut self, page_no: PageNo, page: &Page) -> StorageResult<()> {
+        self.file
+            .seek(SeekFrom::Start(page_no as u64 * PAGE_SIZE as u64))?;
+        self.file.write_all(page.as_bytes())?;
+        self.page_count = self.page_count.max(page_no + 1);
+        Ok(())
+    }
+
+    fn truncate(&mut self) -> StorageResult<()> {
+        self.file.set_len(0)?;
+        self.page_count = 0;
+        Ok(())
+    }
+
+    fn sync(&mut self) -> StorageResult<()> {
+        self.file.sync_data()?;
+        Ok(())
+    }
+}
+
+/// Pages kept in memory, for databases that aren't stored anywhere
+#[derive(Debug, Default)]
+pub struct MemoryPager {
+    pages: Vec<Page>,
+}
+
+impl Pager for MemoryPager {
+    fn page_count(&self) -> PageNo {
+        self.pages.len() as PageNo
+    }
+
+    fn read_page(&mut self, page_no: PageNo) -> StorageResult<Page> {
+        Ok(self.pages[page_no as usize].clone())
+    }
+
+    fn write_page(&mut self, page_no: PageNo, page: &Page) -> StorageResult<()> {
+        match self.pages.get_mut(page_no as usize) {
+            Some(stored) => *stored = page.clone(),
+            None => self.pages.push(page.clone()),
+        }
+        Ok(())
+    }
+
+    fn truncate(&mut self) -> StorageResult<()> {
+        self.pages.clear();
+        Ok(())
+    }
+
+    fn sync(&mut self) -> StorageResult<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_file_pager_keeps_pages_across_opens() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("table.heap");
+        let mut page = Page::new();
+        page.insert(b"stored").unwrap();
+
+        let mut pager = FilePager::open(&path).unwrap();
+        pager.write_page(0, &Page::new()).unwrap();
+        pager.write_page(1, &page).unwrap();
+        pager.sync().unwrap();
+        drop(pager);
+
+        let mut pager = FilePager::open(&path).unwrap();
+        assert_eq!(pager.page_count(), 2);
+        assert_eq!(pager.read_page(1).unwrap(), page);
+    }
+}
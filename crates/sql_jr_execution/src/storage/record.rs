@@ -0,0 +1,107 @@
+use sql_jr_parser::value::Value;
+
+use super::{StorageError, StorageResult};
+use crate::table::Row;
+
+// every value starts with a tag saying which kind of value follows
+const NULL: u8 = 0;
+const INT: u8 = 1;
+const FLOAT: u8 = 2;
+const STRING: u8 = 3;
+const BOOLEAN: u8 = 4;
+
+/// Serialize `row` into the bytes stored in a page
+pub(crate) fn encode_row(row: &[Value]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for value in row {
+        match value {
+            Value::Null => bytes.push(NULL),
+            Value::Int(i) => {
+                bytes.push(INT);
+                bytes.extend_from_slice(&i.to_le_bytes());
+            }
+            Value::Float(f) => {
+                bytes.push(FLOAT);
+                bytes.extend_from_slice(&f.to_le_bytes());
+            }
+            Value::String(s) => {
+                bytes.push(STRING);
+                bytes.extend_from_slice(&(s.len() as u32).to_le_bytes());
+                bytes.extend_from_slice(s.as_bytes());
+            }
+            Value::Boolean(b) => {
+                bytes.push(BOOLEAN);
+                bytes.push(*b as u8);
+            }
+        }
+    }
+    bytes
+}
+
+/// Read back a row written by [`encode_row`]
+pub(crate) fn decode_row(mut bytes: &[u8]) -> StorageResult<Row> {
+    let mut row = Vec::new();
+    while let Some((&tag, rest)) = bytes.split_first() {
+        bytes = rest;
+        let value = match tag {
+            NULL => Value::Null,
+            INT => Value::Int(i64::from_le_bytes(take(&mut bytes)?)),
+            FLOAT => Value::Float(f64::from_le_bytes(take(&mut bytes)?)),
+            STRING => {
+                let length = u32::from_le_bytes(take(&mut bytes)?) as usize;
+                if bytes.len() < length {
+                    return Err(truncated());
+                }
+                let (text, rest) = bytes.split_at(length);
+                bytes = rest;
+                let text = String::from_utf8(text.to_vec())
+                    .map_err(|e| StorageError::Corrupt(e.to_string()))?;
+                Value::String(text)
+            }
+            BOOLEAN => Value::Boolean(take::<1>(&mut bytes)?[0] != 0),
+            tag => return Err(StorageError::Corrupt(format!("unknown value tag {tag}"))),
+        };
+        row.push(value);
+    }
+    Ok(row)
+}
+
+fn truncated() -> StorageError {
+    StorageError::Corrupt("row ends in the middle of a value".to_string())
+}
+
+// splits the next N bytes off `bytes`
+fn take<const N: usize>(bytes: &mut &[u8]) -> StorageResult<[u8; N]> {
+    if bytes.len() < N {
+        return Err(truncated());
+    }
+    let (value, rest) = bytes.split_at(N);
+    *bytes = rest;
+    Ok(value.try_into().unwrap())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let row = vec![
+            Value::Int(-42),
+            Value::String("Jö".to_string()),
+            Value::Null,
+            Value::Float(1.5),
+            Value::Boolean(true),
+            Value::String(String::new()),
+        ];
+
+        assert_eq!(decode_row(&encode_row(&row)), Ok(row));
+    }
+
+    #[test]
+    fn test_truncated_row_is_corrupt() {
+        let bytes = encode_row(&[Value::String("hello".to_string())]);
+
+        assert_eq!(decode_row(&bytes[..bytes.len() - 1]), Err(truncated()));
+    }
+}
@@ -0,0 +1,95 @@
+use sql_jr_parser::{
+    create_statement::{Column, SqlTypeInfo},
+    value::Value,
+};
+
+use crate::storage::{
+    buffer_pool::{BufferPool, FileId},
+    heap_file::{HeapFile, RowId},
+    StorageResult,
+};
+
+/// The values of a single row, in the order of its table's columns
+pub type Row = Vec<Value>;
+
+/// Whether `value` can be stored in a column of type `type_info`, NULL fits any column
+pub fn is_assignable(type_info: &SqlTypeInfo, value: &Value) -> bool {
+    matches!(
+        (type_info, value),
+        (_, Value::Null)
+            | (SqlTypeInfo::Int, Value::Int(_))
+            | (SqlTypeInfo::String, Value::String(_))
+    )
+}
+
+/// A table's columns and the heap file its rows are stored in
+#[derive(Debug)]
+pub struct Table {
+    columns: Vec<Column>,
+    file: String,
+    heap: HeapFile,
+}
+
+impl Table {
+    pub(crate) fn new(columns: Vec<Column>, file: String, heap: HeapFile) -> Self {
+        Self {
+            columns,
+            file,
+            heap,
+        }
+    }
+
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    /// Name of the table's heap file in the data directory
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// Position of the column called `name`, if the table has one
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|column| column.name == *name)
+    }
+
+    pub(crate) fn set_columns(&mut self, columns: Vec<Column>) {
+        self.columns = columns;
+    }
+
+    /// Every row of the table with where it is stored
+    pub(crate) fn scan(&self, pool: &mut BufferPool) -> StorageResult<Vec<(RowId, Row)>> {
+        let mut rows = self.heap.scan(pool)?;
+        // rows stored before a column was added don't have a value for it
+        for (_, row) in &mut rows {
+            row.resize(self.columns.len(), Value::Null);
+        }
+        Ok(rows)
+    }
+
+    pub(crate) fn insert(&mut self, pool: &mut BufferPool, row: &[Value]) -> StorageResult<RowId> {
+        self.heap.insert(pool, row)
+    }
+
+    pub(crate) fn update(
+        &mut self,
+        pool: &mut BufferPool,
+        id: RowId,
+        row: &[Value],
+    ) -> StorageResult<RowId> {
+        self.heap.update(pool, id, row)
+    }
+
+    pub(crate) fn truncate(&mut self, pool: &mut BufferPool) -> StorageResult<()> {
+        self.heap.truncate(pool)
+    }
+
+    /// Id of the heap file in the buffer pool
+    pub(crate) fn file_id(&self) -> FileId {
+        self.heap.file()
+    }
+
+    pub(crate) fn delete(&mut self, pool: &mut BufferPool, id: RowId) -> StorageResult<bool> {
+        self.heap.delete(pool, id)
+    }
+}
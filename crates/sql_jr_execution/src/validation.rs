@@ -0,0 +1,1539 @@
+use std::{cell::RefCell, collections::HashSet};
+
+use miette::{Diagnostic, SourceSpan};
+use sql_jr_parser::{
+    alter_table_statement::{AlterTableAction, AlterTableStatement},
+    create_statement::{Column, CreateStatement, SqlTypeInfo},
+    delete_statement::DeleteStatement,
+    expression::{AggregateArg, ColumnRef, Expression},
+    from_clause::{FromClause, JoinConstraint},
+    ident::{Ident, ObjectName},
+    insert_statement::InsertStatement,
+    query::SqlQuery,
+    query_expression::{
+        CommonTableExpression, NestedQuery, QueryExpression, SetOperation, SetOperator, WithQuery,
+    },
+    select_statement::{Distinct, SelectItem, SelectStatement},
+    types::Span,
+    update_statement::UpdateStatement,
+    window::{FrameBound, FrameUnits, WindowSpec},
+};
+use thiserror::Error;
+
+use crate::{
+    aggregate::{contains_aggregate, ungrouped_column},
+    catalog::Catalog,
+    error::ExecutionError,
+    evaluation::{conditional_name, expression_type, output_name, result_type},
+    result::ColumnInfo,
+    scope::{duplicate_table_reference, join_scope, ResolveError, Scope},
+    select::{cte_columns, reads_table},
+    set_operation::result_columns,
+    sort::{in_select_list, resolve_sort_key, sort_columns, SortKey},
+    table::is_assignable,
+    window::collect_windows,
+};
+
+/// A semantic error labelled on the part of the query it is about
+#[derive(Debug, Error, Diagnostic)]
+#[error("invalid query")]
+pub struct SemanticError<'a> {
+    #[source_code]
+    src: &'a str,
+
+    #[label("{kind}")]
+    span: SourceSpan,
+
+    kind: ExecutionError,
+}
+
+impl SemanticError<'_> {
+    pub fn kind(&self) -> &ExecutionError {
+        &self.kind
+    }
+}
+
+type ValidationResult = Result<(), (ExecutionError, SourceSpan)>;
+
+/// What checking a statement finds out about it that running it relies on
+#[derive(Debug, Default)]
+pub(crate) struct Analysis {
+    /// The subqueries that don't read the row of a query they are nested in, so have
+    /// the same result for every row. A subquery written twice is only in here when
+    /// neither reads an enclosing row
+    pub uncorrelated: HashSet<QueryExpression>,
+}
+
+// where the column references of a statement were found, to tell which subqueries
+// read the rows of the queries they are nested in
+#[derive(Default)]
+struct References {
+    // the nesting of the scope each column reference was found in, in the order they
+    // were checked
+    nestings: RefCell<Vec<usize>>,
+    correlated: RefCell<HashSet<QueryExpression>>,
+    uncorrelated: RefCell<HashSet<QueryExpression>>,
+}
+
+impl References {
+    fn into_analysis(self) -> Analysis {
+        let correlated = self.correlated.into_inner();
+        let mut uncorrelated = self.uncorrelated.into_inner();
+        uncorrelated.retain(|subquery| !correlated.contains(subquery));
+        Analysis { uncorrelated }
+    }
+}
+
+// the tables a query can read: those of the catalog and the CTEs of the WITH clauses
+// it is in, the innermost last, which hide the tables of the same name
+#[derive(Clone)]
+struct Tables<'a> {
+    catalog: &'a Catalog,
+    ctes: Vec<(String, Vec<ColumnInfo>)>,
+    references: &'a References,
+}
+
+impl<'a> Tables<'a> {
+    fn new(catalog: &'a Catalog, references: &'a References) -> Self {
+        Self {
+            catalog,
+            ctes: Vec::new(),
+            references,
+        }
+    }
+
+    // a qualified name never refers to a CTE
+    fn cte(&self, name: &ObjectName) -> Option<&[ColumnInfo]> {
+        let name = name.as_unqualified()?;
+        self.ctes
+            .iter()
+            .rev()
+            .find(|(cte, _)| name == cte)
+            .map(|(_, columns)| columns.as_slice())
+    }
+}
+
+/// Check that the tables and columns `query` refers to exist in `catalog` and that
+/// the values it inserts fit their columns. `src` is the text `query` was parsed from
+pub fn validate<'a>(
+    catalog: &Catalog,
+    query: &SqlQuery,
+    src: &'a str,
+) -> Result<(), SemanticError<'a>> {
+    analyse(catalog, query, src).map(|_| ())
+}
+
+/// Like [`validate`], with what running the query needs to know about it
+pub(crate) fn analyse<'a>(
+    catalog: &Catalog,
+    query: &SqlQuery,
+    src: &'a str,
+) -> Result<Analysis, SemanticError<'a>> {
+    check_query(catalog, query).map_err(|(kind, span)| SemanticError { src, span, kind })
+}
+
+/// Like [`validate`], with the error only labelled with where it is in the query, and
+/// what running the query needs to know about it
+pub(crate) fn check_query(
+    catalog: &Catalog,
+    query: &SqlQuery,
+) -> Result<Analysis, (ExecutionError, SourceSpan)> {
+    let references = References::default();
+    let tables = Tables::new(catalog, &references);
+    match query {
+        SqlQuery::Select(select) => validate_select(&tables, select),
+        SqlQuery::Insert(insert) => validate_insert(catalog, insert),
+        SqlQuery::Create(create) => validate_create(catalog, create),
+        SqlQuery::Update(update) => validate_update(&tables, update),
+        SqlQuery::Delete(delete) => validate_delete(&tables, delete),
+        SqlQuery::Drop(drop) => check_tables_exist(catalog, &drop.tables, drop.if_exists),
+        SqlQuery::Truncate(truncate) => {
+            check_tables_exist(catalog, &truncate.tables, truncate.if_exists)
+        }
+        SqlQuery::AlterTable(alter) => validate_alter_table(catalog, alter),
+    }?;
+    Ok(references.into_analysis())
+}
+
+fn table_schema<'c>(
+    catalog: &'c Catalog,
+    table: &ObjectName,
+) -> Result<&'c CreateStatement, (ExecutionError, SourceSpan)> {
+    catalog.table(&table.to_string()).ok_or_else(|| {
+        (
+            ExecutionError::TableNotFound(table.to_string()),
+            table.span().into(),
+        )
+    })
+}
+
+fn check_tables_exist(
+    catalog: &Catalog,
+    tables: &[ObjectName],
+    if_exists: bool,
+) -> ValidationResult {
+    if if_exists {
+        return Ok(());
+    }
+    for table in tables {
+        table_schema(catalog, table)?;
+    }
+    Ok(())
+}
+
+fn check_column(catalog: &Catalog, table: &ObjectName, column: &Ident) -> ValidationResult {
+    match catalog.column(&table.to_string(), column.as_str()) {
+        Some(_) => Ok(()),
+        None => Err((
+            ExecutionError::ColumnNotFound {
+                table: table.to_string(),
+                column: column.to_string(),
+            },
+            column.span.into(),
+        )),
+    }
+}
+
+// a column must name exactly one of the columns of the tables queried, or of the
+// queries a subquery is nested in
+fn locate_column(tables: &Tables, scope: &Scope, column: &ColumnRef) -> ValidationResult {
+    let (depth, _) = scope.locate(column).map_err(|error| {
+        let span = match (&error, &column.table) {
+            (ResolveError::TableNotInQuery(_), Some(qualifier)) => qualifier.span(),
+            _ => column.span(),
+        };
+        (error.into(), span.into())
+    })?;
+    tables
+        .references
+        .nestings
+        .borrow_mut()
+        .push(scope.nesting() - depth);
+    Ok(())
+}
+
+// a subquery is checked against the tables it reads, within the scope of the query it
+// is nested in. Used as a value it must return a single column. It is correlated when
+// one of its columns, or of the subqueries nested in it, is found outside of it
+fn check_subquery(
+    tables: &Tables,
+    scope: &Scope,
+    subquery: &QueryExpression,
+    single_column: Option<Span>,
+) -> ValidationResult {
+    let references = tables.references;
+    let first = references.nestings.borrow().len();
+    let columns = validate_query(tables, subquery, Some(scope))?;
+    let correlated = references.nestings.borrow()[first..]
+        .iter()
+        .any(|&nesting| nesting <= scope.nesting());
+    match correlated {
+        true => references.correlated.borrow_mut().insert(subquery.clone()),
+        false => references
+            .uncorrelated
+            .borrow_mut()
+            .insert(subquery.clone()),
+    };
+    match single_column {
+        Some(span) if columns.len() != 1 => Err((ExecutionError::SubqueryColumnCount, span.into())),
+        _ => Ok(()),
+    }
+}
+
+fn check_expression(tables: &Tables, scope: &Scope, expression: &Expression) -> ValidationResult {
+    match expression {
+        Expression::Column(column) => locate_column(tables, scope, column),
+        Expression::Literal(_) => Ok(()),
+        Expression::Unary { expr, .. } | Expression::IsNull { expr, .. } => {
+            check_expression(tables, scope, expr)
+        }
+        Expression::Binary { left, right, .. } => {
+            check_expression(tables, scope, left)?;
+            check_expression(tables, scope, right)
+        }
+        Expression::Aggregate { span, .. } => {
+            Err((ExecutionError::AggregateNotAllowed, (*span).into()))
+        }
+        Expression::Subquery { subquery, span } => {
+            check_subquery(tables, scope, subquery, Some(*span))
+        }
+        Expression::Exists { subquery, .. } => check_subquery(tables, scope, subquery, None),
+        Expression::InSubquery {
+            expr,
+            subquery,
+            span,
+            ..
+        } => {
+            check_expression(tables, scope, expr)?;
+            check_subquery(tables, scope, subquery, Some(*span))
+        }
+        Expression::Case { .. } | Expression::Conditional { .. } => {
+            for operand in expression.conditional_operands() {
+                check_expression(tables, scope, operand)?;
+            }
+            check_result_type(scope, expression)
+        }
+        Expression::Window { span, .. } => Err((ExecutionError::WindowNotAllowed, (*span).into())),
+    }
+}
+
+// the results of a CASE or a conditional function call must be of a single type
+fn check_result_type(scope: &Scope, expression: &Expression) -> ValidationResult {
+    result_type(expression, scope)
+        .map(|_| ())
+        .map_err(|(left, right)| {
+            (
+                ExecutionError::ResultTypeMismatch {
+                    expression: conditional_name(expression),
+                    left,
+                    right,
+                },
+                expression.span().into(),
+            )
+        })
+}
+
+// like `check_expression` for the expressions computed once per group, which may call
+// aggregate functions as long as the calls aren't nested
+fn check_computed(tables: &Tables, scope: &Scope, expression: &Expression) -> ValidationResult {
+    match expression {
+        Expression::Aggregate {
+            arg: AggregateArg::Expression(expr),
+            ..
+        } => check_expression(tables, scope, expr),
+        Expression::Aggregate { .. } => Ok(()),
+        Expression::Unary { expr, .. }
+        | Expression::InSubquery { expr, .. }
+        | Expression::IsNull { expr, .. } => {
+            check_computed(tables, scope, expr)?;
+            match expression {
+                Expression::InSubquery { subquery, span, .. } => {
+                    check_subquery(tables, scope, subquery, Some(*span))
+                }
+                _ => Ok(()),
+            }
+        }
+        Expression::Binary { left, right, .. } => {
+            check_computed(tables, scope, left)?;
+            check_computed(tables, scope, right)
+        }
+        Expression::Case { .. } | Expression::Conditional { .. } => {
+            for operand in expression.conditional_operands() {
+                check_computed(tables, scope, operand)?;
+            }
+            check_result_type(scope, expression)
+        }
+        Expression::Window {
+            function,
+            window,
+            span,
+        } => {
+            // the arguments are computed like the select list but can't call windows
+            for expr in function.args().into_iter().chain(window.expressions()) {
+                check_no_window(expr)?;
+                check_computed(tables, scope, expr)?;
+            }
+            check_frame(window, scope, *span)
+        }
+        _ => check_expression(tables, scope, expression),
+    }
+}
+
+fn check_no_window(expression: &Expression) -> ValidationResult {
+    let mut windows = Vec::new();
+    collect_windows(expression, &mut windows);
+    match windows.first() {
+        Some(window) => Err((ExecutionError::WindowNotAllowed, window.span().into())),
+        None => Ok(()),
+    }
+}
+
+// a RANGE frame an offset away from the row is measured on its only ORDER BY value
+fn check_frame(window: &WindowSpec, scope: &Scope, span: Span) -> ValidationResult {
+    let Some(frame) = window.frame else {
+        return Ok(());
+    };
+    let offset = |bound| matches!(bound, FrameBound::Preceding(_) | FrameBound::Following(_));
+    if frame.units != FrameUnits::Range || !(offset(frame.start) || offset(frame.end)) {
+        return Ok(());
+    }
+    match window.order_by.as_slice() {
+        [order_by]
+            if !matches!(
+                expression_type(&order_by.expr, scope),
+                Some(SqlTypeInfo::String)
+            ) =>
+        {
+            Ok(())
+        }
+        _ => Err((ExecutionError::RangeFrameOffset, span.into())),
+    }
+}
+
+fn ungrouped_error(column: &ColumnRef, span: Span) -> (ExecutionError, SourceSpan) {
+    (
+        ExecutionError::UngroupedColumn(column.to_string()),
+        span.into(),
+    )
+}
+
+// the columns the tables of `from` bring into the query, checking the join conditions
+// and the derived tables. `outer` is the scope of the query a subquery is nested in
+fn from_scope(
+    tables: &Tables,
+    from: &FromClause,
+    outer: Option<&Scope>,
+) -> Result<Scope<'static>, (ExecutionError, SourceSpan)> {
+    let (left, right, constraint) = match from {
+        FromClause::Table(reference) => {
+            if let Some(columns) = tables.cte(&reference.name) {
+                return Ok(Scope::derived(reference.reference_name().as_str(), columns));
+            }
+            let schema = table_schema(tables.catalog, &reference.name)?;
+            return Ok(Scope::table(&reference.qualifier(), &schema.columns));
+        }
+        FromClause::Subquery { query, alias } => {
+            let columns = validate_query(tables, query, outer)?;
+            return Ok(Scope::derived(alias.as_str(), &columns));
+        }
+        FromClause::Join {
+            left,
+            right,
+            constraint,
+            ..
+        } => (left, right, constraint),
+    };
+
+    let left_scope = from_scope(tables, left, outer)?;
+    let right_scope = from_scope(tables, right, outer)?;
+    let (scope, _) = join_scope(&left_scope, &right_scope, constraint).map_err(|error| {
+        // a USING column is labelled where it is named, a NATURAL join on the right table
+        let span = match (constraint, &error) {
+            (
+                JoinConstraint::Using(names),
+                ResolveError::ColumnNotFound { column, .. } | ResolveError::AmbiguousColumn(column),
+            ) => {
+                names
+                    .iter()
+                    .find(|name| name.as_str() == column)
+                    .unwrap_or(&names[0])
+                    .span
+            }
+            _ => right.reference_names()[0].span,
+        };
+        (error.into(), span.into())
+    })?;
+    if let JoinConstraint::On(predicate) = constraint {
+        check_expression(tables, &scope.clone().with_outer(outer), predicate)?;
+    }
+    Ok(scope)
+}
+
+fn validate_select(tables: &Tables, query: &QueryExpression) -> ValidationResult {
+    validate_query(tables, query, None).map(|_| ())
+}
+
+// checks `query`, a subquery of the query whose scope is `outer` when it has one, and
+// returns the columns it returns
+fn validate_query(
+    tables: &Tables,
+    query: &QueryExpression,
+    outer: Option<&Scope>,
+) -> Result<Vec<ColumnInfo>, (ExecutionError, SourceSpan)> {
+    match query {
+        QueryExpression::Select(select) => validate_select_statement(tables, select, outer),
+        QueryExpression::SetOperation(operation) => {
+            validate_set_operation(tables, operation, outer)
+        }
+        QueryExpression::With(with) => validate_with(tables, with, outer),
+        QueryExpression::Nested(nested) => validate_nested(tables, nested, outer),
+    }
+}
+
+// each CTE is checked with the CTEs before it, then the query with them all
+fn validate_with(
+    tables: &Tables,
+    with: &WithQuery,
+    outer: Option<&Scope>,
+) -> Result<Vec<ColumnInfo>, (ExecutionError, SourceSpan)> {
+    let mut tables = tables.clone();
+    for cte in &with.tables {
+        let columns = match (
+            with.recursive && reads_table(&cte.query, &cte.name),
+            &cte.query,
+        ) {
+            (false, query) => validate_query(&tables, query, outer)?,
+            // the recursive query reads the columns of the anchor query
+            (true, QueryExpression::SetOperation(operation))
+                if operation.op == SetOperator::Union
+                    && operation.order_by.is_empty()
+                    && operation.limit.is_none()
+                    && operation.offset.is_none() =>
+            {
+                let anchor = validate_query(&tables, &operation.left, outer)?;
+                let available = anchor.len();
+                let anchor =
+                    cte_columns(cte, anchor).ok_or_else(|| cte_column_count(cte, available))?;
+                let mut recursive_tables = tables.clone();
+                recursive_tables
+                    .ctes
+                    .push((cte.name.to_string(), anchor.clone()));
+                let right = validate_query(&recursive_tables, &operation.right, outer)?;
+                result_columns(operation.op, anchor, &right)
+                    .map_err(|error| (error.into(), operation.right.span().into()))?
+            }
+            (true, _) => {
+                return Err((
+                    ExecutionError::RecursiveCteForm(cte.name.to_string()),
+                    cte.query.span().into(),
+                ))
+            }
+        };
+        let available = columns.len();
+        let columns = cte_columns(cte, columns).ok_or_else(|| cte_column_count(cte, available))?;
+        tables.ctes.push((cte.name.to_string(), columns));
+    }
+    validate_query(&tables, &with.query, outer)
+}
+
+// a CTE given more column names than its query returns, labelled on the first extra one
+fn cte_column_count(cte: &CommonTableExpression, available: usize) -> (ExecutionError, SourceSpan) {
+    (
+        ExecutionError::CteColumnCount {
+            name: cte.name.to_string(),
+            available,
+            specified: cte.columns.len(),
+        },
+        cte.columns[available].span.into(),
+    )
+}
+
+// the errors about combining the two results are labelled on the right query
+fn validate_set_operation(
+    tables: &Tables,
+    operation: &SetOperation,
+    outer: Option<&Scope>,
+) -> Result<Vec<ColumnInfo>, (ExecutionError, SourceSpan)> {
+    let left = validate_query(tables, &operation.left, outer)?;
+    let right = validate_query(tables, &operation.right, outer)?;
+    let columns = result_columns(operation.op, left, &right)
+        .map_err(|error| (error.into(), operation.right.span().into()))?;
+
+    let output_names: Vec<&str> = columns.iter().map(|column| column.name.as_str()).collect();
+    sort_columns(&operation.order_by, &output_names, || {
+        ExecutionError::SetOperationOrderBy(operation.op)
+    })
+    .map_err(|(error, span)| (error, span.into()))?;
+    Ok(columns)
+}
+
+// the rows of a sorted query in parentheses are sorted again by its result columns
+fn validate_nested(
+    tables: &Tables,
+    nested: &NestedQuery,
+    outer: Option<&Scope>,
+) -> Result<Vec<ColumnInfo>, (ExecutionError, SourceSpan)> {
+    let columns = validate_query(tables, &nested.query, outer)?;
+    let output_names: Vec<&str> = columns.iter().map(|column| column.name.as_str()).collect();
+    sort_columns(&nested.order_by, &output_names, || {
+        ExecutionError::NestedOrderBy
+    })
+    .map_err(|(error, span)| (error, span.into()))?;
+    Ok(columns)
+}
+
+fn validate_select_statement(
+    tables: &Tables,
+    select: &SelectStatement,
+    outer: Option<&Scope>,
+) -> Result<Vec<ColumnInfo>, (ExecutionError, SourceSpan)> {
+    if let Some(name) = duplicate_table_reference(&select.from) {
+        return Err((
+            ExecutionError::DuplicateTableReference(name.to_string()),
+            name.span.into(),
+        ));
+    }
+    let scope = from_scope(tables, &select.from, outer)?.with_outer(outer);
+    let grouped = !select.group_by.is_empty()
+        || select.having.is_some()
+        || select.columns.iter().any(
+            |item| matches!(item, SelectItem::Expression { expr, .. } if contains_aggregate(expr)),
+        )
+        || select
+            .order_by
+            .iter()
+            .any(|order_by| contains_aggregate(&order_by.expr))
+        || matches!(&select.distinct, Some(Distinct::On(exprs)) if exprs.iter().any(contains_aggregate));
+    // in a grouped query the columns that aren't grouped by have no single value
+    let ungrouped = |expr| match grouped {
+        true => ungrouped_column(expr, &select.group_by, &scope),
+        false => None,
+    };
+
+    let mut columns = Vec::new();
+    // the expressions computing the columns
+    let mut selected = Vec::new();
+    for item in &select.columns {
+        match item {
+            SelectItem::Wildcard | SelectItem::QualifiedWildcard(_) => {
+                let indexes = match item {
+                    SelectItem::QualifiedWildcard(qualifier) => scope
+                        .qualified(qualifier)
+                        .map_err(|error| (ExecutionError::from(error), qualifier.span().into()))?,
+                    _ => scope.visible(),
+                };
+                for index in indexes {
+                    let expr = Expression::Column(scope.column_ref(index));
+                    if grouped {
+                        if let Some(column) = ungrouped_column(&expr, &select.group_by, &scope) {
+                            return Err(ungrouped_error(column, select.span));
+                        }
+                    }
+                    let column = &scope.columns()[index];
+                    columns.push(ColumnInfo {
+                        name: column.name.clone(),
+                        type_info: column.type_info.clone(),
+                    });
+                    selected.push(expr);
+                }
+            }
+            SelectItem::Expression { expr, alias } => {
+                check_computed(tables, &scope, expr)?;
+                if let Some(column) = ungrouped(expr) {
+                    return Err(ungrouped_error(column, column.span()));
+                }
+                columns.push(ColumnInfo {
+                    name: output_name(expr, alias.as_ref()),
+                    type_info: expression_type(expr, &scope),
+                });
+                selected.push(expr.clone());
+            }
+        }
+    }
+    if let Some(predicate) = &select.where_clause {
+        check_expression(tables, &scope, predicate)?;
+    }
+    for expr in &select.group_by {
+        check_expression(tables, &scope, expr)?;
+    }
+    if let Some(predicate) = &select.having {
+        check_no_window(predicate)?;
+        check_computed(tables, &scope, predicate)?;
+        if let Some(column) = ungrouped(predicate) {
+            return Err(ungrouped_error(column, column.span()));
+        }
+    }
+
+    let output_names: Vec<&str> = columns.iter().map(|column| column.name.as_str()).collect();
+    let selected: Vec<&Expression> = selected.iter().collect();
+    // ORDER BY and DISTINCT ON keys name a result column or are computed from the row
+    let distinct_on = match &select.distinct {
+        Some(Distinct::On(exprs)) => exprs.as_slice(),
+        _ => &[],
+    };
+    let order_by = select.order_by.iter().map(|order_by| &order_by.expr);
+    let mut keys = Vec::new();
+    for (index, expr) in distinct_on.iter().chain(order_by).enumerate() {
+        let out_of_range = match index < distinct_on.len() {
+            true => ExecutionError::DistinctOnPositionOutOfRange,
+            false => ExecutionError::OrderByPositionOutOfRange,
+        };
+        let key = resolve_sort_key(expr, &output_names);
+        match key {
+            Ok(SortKey::Output(_)) => {}
+            Ok(SortKey::Input(expr)) => {
+                check_computed(tables, &scope, expr)?;
+                if let Some(column) = ungrouped(expr) {
+                    return Err(ungrouped_error(column, column.span()));
+                }
+                // SELECT DISTINCT has no DISTINCT ON keys, these are ORDER BY keys
+                if select.distinct == Some(Distinct::Rows)
+                    && !in_select_list(expr, &selected, &scope)
+                {
+                    return Err((ExecutionError::DistinctOrderBy, expr.span().into()));
+                }
+            }
+            Err(position) => return Err((out_of_range(position), expr.span().into())),
+        }
+        keys.extend(key);
+    }
+    let (distinct_keys, sort_keys) = keys.split_at(distinct_on.len());
+    if sort_keys.is_empty() {
+        return Ok(columns);
+    }
+    // as in Postgres, the rows are sorted by the DISTINCT ON keys before any other, so
+    // the ORDER BY keys start with those of them it has, and has them all when it has
+    // keys of its own
+    let same_key = |left: &SortKey, right: &SortKey| match (*left, *right) {
+        (SortKey::Output(left), SortKey::Output(right)) => left == right,
+        (SortKey::Output(index), SortKey::Input(expr))
+        | (SortKey::Input(expr), SortKey::Output(index)) => {
+            in_select_list(expr, &selected[index..=index], &scope)
+        }
+        (SortKey::Input(left), SortKey::Input(right)) => in_select_list(left, &[right], &scope),
+    };
+    let mut other_keys = false;
+    let mut sorted = vec![false; distinct_on.len()];
+    for sort_key in sort_keys {
+        match distinct_keys.iter().position(|key| same_key(key, sort_key)) {
+            Some(index) if other_keys => {
+                return Err((
+                    ExecutionError::DistinctOnOrderBy,
+                    distinct_on[index].span().into(),
+                ))
+            }
+            Some(index) => sorted[index] = true,
+            None => other_keys = true,
+        }
+    }
+    match sorted.iter().position(|&sorted| !sorted) {
+        Some(index) if other_keys => Err((
+            ExecutionError::DistinctOnOrderBy,
+            distinct_on[index].span().into(),
+        )),
+        _ => Ok(columns),
+    }
+}
+
+fn validate_insert(catalog: &Catalog, insert: &InsertStatement) -> ValidationResult {
+    let schema = table_schema(catalog, &insert.table)?;
+
+    let mut seen = HashSet::new();
+    for column in &insert.columns {
+        check_column(catalog, &insert.table, column)?;
+        if !seen.insert(column.as_str()) {
+            return Err((
+                ExecutionError::DuplicateColumn(column.to_string()),
+                column.span.into(),
+            ));
+        }
+    }
+
+    // the columns the values go into
+    let targets: Vec<&Column> = if insert.columns.is_empty() {
+        schema.columns.iter().collect()
+    } else {
+        insert
+            .columns
+            .iter()
+            .map(|name| {
+                catalog
+                    .column(&insert.table.to_string(), name.as_str())
+                    .unwrap()
+            })
+            .collect()
+    };
+
+    for row in &insert.rows {
+        if row.len() != targets.len() {
+            let row_span = row
+                .iter()
+                .map(|literal| literal.span)
+                .reduce(Span::union)
+                .unwrap_or(insert.span);
+            return Err((
+                ExecutionError::ValueCountMismatch {
+                    table: insert.table.to_string(),
+                    expected: targets.len(),
+                    found: row.len(),
+                },
+                row_span.into(),
+            ));
+        }
+        for (column, literal) in targets.iter().zip(row) {
+            if !is_assignable(&column.type_info, &literal.value) {
+                return Err((
+                    ExecutionError::TypeMismatch {
+                        column: column.name.to_string(),
+                        expected: column.type_info.clone(),
+                        found: literal.value.clone(),
+                    },
+                    literal.span.into(),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn validate_update(tables: &Tables, update: &UpdateStatement) -> ValidationResult {
+    let catalog = tables.catalog;
+    let schema = table_schema(catalog, &update.table)?;
+    let scope = Scope::table(&update.table, &schema.columns);
+
+    let mut seen = HashSet::new();
+    for assignment in &update.assignments {
+        check_column(catalog, &update.table, &assignment.column)?;
+        if !seen.insert(assignment.column.as_str()) {
+            return Err((
+                ExecutionError::DuplicateColumn(assignment.column.to_string()),
+                assignment.column.span.into(),
+            ));
+        }
+        check_expression(tables, &scope, &assignment.value)?;
+
+        // only literals have a type known before the query runs
+        if let Expression::Literal(literal) = &assignment.value {
+            let column = catalog
+                .column(&update.table.to_string(), assignment.column.as_str())
+                .unwrap();
+            if !is_assignable(&column.type_info, &literal.value) {
+                return Err((
+                    ExecutionError::TypeMismatch {
+                        column: column.name.to_string(),
+                        expected: column.type_info.clone(),
+                        found: literal.value.clone(),
+                    },
+                    literal.span.into(),
+                ));
+            }
+        }
+    }
+    match &update.where_clause {
+        Some(predicate) => check_expression(tables, &scope, predicate),
+        None => Ok(()),
+    }
+}
+
+fn validate_delete(tables: &Tables, delete: &DeleteStatement) -> ValidationResult {
+    let schema = table_schema(tables.catalog, &delete.table)?;
+    match &delete.where_clause {
+        Some(predicate) => check_expression(
+            tables,
+            &Scope::table(&delete.table, &schema.columns),
+            predicate,
+        ),
+        None => Ok(()),
+    }
+}
+
+fn validate_alter_table(catalog: &Catalog, alter: &AlterTableStatement) -> ValidationResult {
+    table_schema(catalog, &alter.table)?;
+    let check_new_column =
+        |column: &Ident| match catalog.column(&alter.table.to_string(), column.as_str()) {
+            Some(_) => Err((
+                ExecutionError::ColumnAlreadyExists {
+                    table: alter.table.to_string(),
+                    column: column.to_string(),
+                },
+                column.span.into(),
+            )),
+            None => Ok(()),
+        };
+
+    match &alter.action {
+        AlterTableAction::AddColumn(column) => check_new_column(&column.name),
+        AlterTableAction::DropColumn(column) | AlterTableAction::AlterColumnType { column, .. } => {
+            check_column(catalog, &alter.table, column)
+        }
+        AlterTableAction::RenameColumn { from, to } => {
+            check_column(catalog, &alter.table, from)?;
+            check_new_column(to)
+        }
+        AlterTableAction::RenameTable(name) => match catalog.table(&name.to_string()) {
+            Some(_) => Err((
+                ExecutionError::TableAlreadyExists(name.to_string()),
+                name.span().into(),
+            )),
+            None => Ok(()),
+        },
+    }
+}
+
+fn validate_create(catalog: &Catalog, create: &CreateStatement) -> ValidationResult {
+    if catalog.table(&create.table.to_string()).is_some() && !create.if_not_exists {
+        return Err((
+            ExecutionError::TableAlreadyExists(create.table.to_string()),
+            create.table.span().into(),
+        ));
+    }
+
+    let mut seen = HashSet::new();
+    for column in &create.columns {
+        if !seen.insert(column.name.as_str()) {
+            return Err((
+                ExecutionError::DuplicateColumn(column.name.to_string()),
+                column.name.span.into(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use sql_jr_parser::{types::Parse, value::Value};
+
+    use super::*;
+
+    fn catalog() -> Catalog {
+        let mut catalog = Catalog::new();
+        catalog.add(
+            CreateStatement::parse_from_raw("CREATE TABLE Person (name string, age int)")
+                .unwrap()
+                .1,
+        );
+        catalog.add(
+            CreateStatement::parse_from_raw("CREATE TABLE Home (name string, town string)")
+                .unwrap()
+                .1,
+        );
+        catalog
+    }
+
+    // the error kind and the text its label points at
+    fn validate_raw(raw: &str) -> Option<(ExecutionError, &str)> {
+        let query = SqlQuery::parse_format_error(raw).unwrap();
+        let error = validate(&catalog(), &query, raw).err()?;
+        let label = &raw[error.span.offset()..error.span.offset() + error.span.len()];
+        Some((error.kind, label))
+    }
+
+    #[test]
+    fn test_valid_queries() {
+        assert_eq!(validate_raw("SELECT name FROM Person WHERE age > 1;"), None);
+        assert_eq!(
+            validate_raw("INSERT INTO Person (age) VALUES (1), (NULL);"),
+            None
+        );
+        assert_eq!(validate_raw("CREATE TABLE City (name string);"), None);
+        assert_eq!(
+            validate_raw("UPDATE Person SET age = age + 1 WHERE name = 'Jo';"),
+            None
+        );
+        assert_eq!(validate_raw("DELETE FROM Person WHERE age > 1;"), None);
+        assert_eq!(
+            validate_raw("SELECT *, Person.*, Person.age + 1 AS older FROM Person;"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_unknown_table() {
+        assert_eq!(
+            validate_raw("SELECT nosuchcol FROM NoSuchTable;"),
+            Some((
+                ExecutionError::TableNotFound("nosuchtable".to_string()),
+                "NoSuchTable"
+            ))
+        );
+        assert_eq!(
+            validate_raw("DELETE FROM NoSuchTable;"),
+            Some((
+                ExecutionError::TableNotFound("nosuchtable".to_string()),
+                "NoSuchTable"
+            ))
+        );
+        assert_eq!(
+            validate_raw("SELECT name FROM public.\"Person\";"),
+            Some((
+                ExecutionError::TableNotFound("public.Person".to_string()),
+                "public.\"Person\""
+            ))
+        );
+    }
+
+    #[test]
+    fn test_unknown_selected_column() {
+        assert_eq!(
+            validate_raw("SELECT name, nosuchcol FROM Person;"),
+            Some((
+                ExecutionError::ColumnNotFound {
+                    table: "person".to_string(),
+                    column: "nosuchcol".to_string()
+                },
+                "nosuchcol"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_unknown_qualifier() {
+        assert_eq!(
+            validate_raw("SELECT City.name FROM Person;"),
+            Some((ExecutionError::TableNotInQuery("city".to_string()), "City"))
+        );
+        assert_eq!(
+            validate_raw("SELECT City.* FROM Person;"),
+            Some((ExecutionError::TableNotInQuery("city".to_string()), "City"))
+        );
+    }
+
+    #[test]
+    fn test_joins() {
+        assert_eq!(
+            validate_raw(
+                "SELECT p.name, h.town, name FROM Person p LEFT JOIN Home h USING (name) \
+                 WHERE age > 1;"
+            ),
+            None
+        );
+        assert_eq!(
+            validate_raw("SELECT name FROM Person JOIN Home ON Person.name = Home.name;"),
+            Some((ExecutionError::AmbiguousColumn("name".to_string()), "name"))
+        );
+        assert_eq!(
+            validate_raw("SELECT salary FROM Person, Home;"),
+            Some((
+                ExecutionError::ColumnNotInQuery("salary".to_string()),
+                "salary"
+            ))
+        );
+        assert_eq!(
+            validate_raw("SELECT * FROM Person p JOIN Home h ON p.town = h.town;"),
+            Some((
+                ExecutionError::ColumnNotFound {
+                    table: "p".to_string(),
+                    column: "town".to_string()
+                },
+                "p.town"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_join_errors() {
+        assert_eq!(
+            validate_raw("SELECT * FROM Person JOIN Home USING (age);"),
+            Some((
+                ExecutionError::ColumnNotFound {
+                    table: "home".to_string(),
+                    column: "age".to_string()
+                },
+                "age"
+            ))
+        );
+        assert_eq!(
+            validate_raw("SELECT * FROM Person JOIN Home ON 1 = 1, Person;"),
+            Some((
+                ExecutionError::DuplicateTableReference("person".to_string()),
+                "Person"
+            ))
+        );
+        assert_eq!(
+            validate_raw("SELECT * FROM Person JOIN City ON 1 = 1;"),
+            Some((ExecutionError::TableNotFound("city".to_string()), "City"))
+        );
+    }
+
+    #[test]
+    fn test_subqueries() {
+        assert_eq!(
+            validate_raw(
+                "SELECT name FROM Person p WHERE EXISTS \
+                 (SELECT * FROM Home h WHERE h.name = p.name) \
+                 AND age > (SELECT COUNT(*) FROM Home);"
+            ),
+            None
+        );
+        assert_eq!(
+            validate_raw("SELECT s.n FROM (SELECT name AS n FROM Person) s;"),
+            None
+        );
+        assert_eq!(
+            validate_raw("SELECT s.name FROM (SELECT name AS n FROM Person) s;"),
+            Some((
+                ExecutionError::ColumnNotFound {
+                    table: "s".to_string(),
+                    column: "name".to_string()
+                },
+                "s.name"
+            ))
+        );
+        assert_eq!(
+            validate_raw("SELECT name FROM Person WHERE name IN (SELECT name, town FROM Home);"),
+            Some((
+                ExecutionError::SubqueryColumnCount,
+                "name IN (SELECT name, town FROM Home)"
+            ))
+        );
+        assert_eq!(
+            validate_raw(
+                "SELECT name FROM Person WHERE EXISTS (SELECT * FROM Home WHERE salary > 1);"
+            ),
+            Some((
+                ExecutionError::ColumnNotFound {
+                    table: "home".to_string(),
+                    column: "salary".to_string()
+                },
+                "salary"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_distinct() {
+        assert_eq!(
+            validate_raw("SELECT DISTINCT name FROM Person ORDER BY Person.name;"),
+            None
+        );
+        assert_eq!(
+            validate_raw("SELECT DISTINCT ON (age / 10) name FROM Person ORDER BY age / 10, name;"),
+            None
+        );
+        assert_eq!(
+            validate_raw("SELECT DISTINCT name FROM Person ORDER BY age;"),
+            Some((ExecutionError::DistinctOrderBy, "age"))
+        );
+        assert_eq!(
+            validate_raw(
+                "SELECT DISTINCT ON (age, name) name FROM Person ORDER BY name, Person.age DESC;"
+            ),
+            None
+        );
+        assert_eq!(
+            validate_raw("SELECT DISTINCT ON (age, 1) name FROM Person ORDER BY age;"),
+            None
+        );
+        assert_eq!(
+            validate_raw("SELECT DISTINCT ON (age) name FROM Person ORDER BY name, age;"),
+            Some((ExecutionError::DistinctOnOrderBy, "age"))
+        );
+        assert_eq!(
+            validate_raw("SELECT DISTINCT ON (age, name) name FROM Person ORDER BY age, age + 1;"),
+            Some((ExecutionError::DistinctOnOrderBy, "name"))
+        );
+        assert_eq!(
+            validate_raw("SELECT DISTINCT ON (salary) name FROM Person;"),
+            Some((
+                ExecutionError::ColumnNotFound {
+                    table: "person".to_string(),
+                    column: "salary".to_string()
+                },
+                "salary"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_set_operations() {
+        assert_eq!(
+            validate_raw("SELECT name FROM Person UNION SELECT town FROM Home ORDER BY name;"),
+            None
+        );
+        assert_eq!(
+            validate_raw("SELECT name, age FROM Person UNION SELECT name FROM Home;"),
+            Some((
+                ExecutionError::SetOperationColumnCount(SetOperator::Union),
+                "SELECT name FROM Home"
+            ))
+        );
+        assert_eq!(
+            validate_raw("SELECT age FROM Person INTERSECT SELECT town FROM Home;"),
+            Some((
+                ExecutionError::SetOperationTypeMismatch {
+                    op: SetOperator::Intersect,
+                    left: SqlTypeInfo::Int,
+                    right: SqlTypeInfo::String
+                },
+                "SELECT town FROM Home"
+            ))
+        );
+        assert_eq!(
+            validate_raw("SELECT name FROM Person UNION SELECT name FROM Home ORDER BY age;"),
+            Some((
+                ExecutionError::SetOperationOrderBy(SetOperator::Union),
+                "age"
+            ))
+        );
+        assert_eq!(
+            validate_raw("SELECT name FROM Person EXCEPT SELECT salary FROM Home;"),
+            Some((
+                ExecutionError::ColumnNotFound {
+                    table: "home".to_string(),
+                    column: "salary".to_string()
+                },
+                "salary"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_ctes() {
+        assert_eq!(
+            validate_raw(
+                "WITH old AS (SELECT name FROM Person WHERE age > 60), \
+                 Home(name) AS (SELECT name FROM old) SELECT name FROM Home;"
+            ),
+            None
+        );
+        assert_eq!(
+            validate_raw("WITH old(who) AS (SELECT name FROM Person) SELECT name FROM old;"),
+            Some((
+                ExecutionError::ColumnNotFound {
+                    table: "old".to_string(),
+                    column: "name".to_string()
+                },
+                "name"
+            ))
+        );
+        assert_eq!(
+            validate_raw("WITH old(a, b) AS (SELECT name FROM Person) SELECT a FROM old;"),
+            Some((
+                ExecutionError::CteColumnCount {
+                    name: "old".to_string(),
+                    available: 1,
+                    specified: 2
+                },
+                "b"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_recursive_ctes() {
+        assert_eq!(
+            validate_raw(
+                "WITH RECURSIVE t(n) AS (SELECT age FROM Person \
+                 UNION ALL SELECT n + 1 FROM t WHERE n < 9) SELECT n FROM t;"
+            ),
+            None
+        );
+        assert_eq!(
+            validate_raw(
+                "WITH RECURSIVE t(n) AS (SELECT age FROM Person \
+                 UNION SELECT name FROM t, Home) SELECT n FROM t;"
+            ),
+            Some((
+                ExecutionError::SetOperationTypeMismatch {
+                    op: SetOperator::Union,
+                    left: SqlTypeInfo::Int,
+                    right: SqlTypeInfo::String
+                },
+                "SELECT name FROM t, Home"
+            ))
+        );
+        assert_eq!(
+            validate_raw("WITH RECURSIVE t AS (SELECT age FROM t) SELECT age FROM t;"),
+            Some((
+                ExecutionError::RecursiveCteForm("t".to_string()),
+                "SELECT age FROM t"
+            ))
+        );
+        // without RECURSIVE a CTE can't read itself
+        assert_eq!(
+            validate_raw(
+                "WITH t AS (SELECT age FROM Person UNION SELECT age FROM t) SELECT age FROM t;"
+            ),
+            Some((ExecutionError::TableNotFound("t".to_string()), "t"))
+        );
+    }
+
+    #[test]
+    fn test_window_functions() {
+        assert_eq!(
+            validate_raw(
+                "SELECT name, ROW_NUMBER() OVER (PARTITION BY name ORDER BY age) FROM Person \
+                 ORDER BY LAG(age) OVER (ORDER BY age RANGE BETWEEN 1 PRECEDING AND CURRENT ROW);"
+            ),
+            None
+        );
+        assert_eq!(
+            validate_raw("SELECT name FROM Person WHERE RANK() OVER () > 1;"),
+            Some((ExecutionError::WindowNotAllowed, "RANK() OVER ()"))
+        );
+        assert_eq!(
+            validate_raw("SELECT COUNT(*) FROM Person HAVING RANK() OVER () > 1;"),
+            Some((ExecutionError::WindowNotAllowed, "RANK() OVER ()"))
+        );
+        assert_eq!(
+            validate_raw("SELECT SUM(RANK() OVER ()) OVER () FROM Person;"),
+            Some((ExecutionError::WindowNotAllowed, "RANK() OVER ()"))
+        );
+        assert_eq!(
+            validate_raw(
+                "SELECT age, MAX(age) OVER (PARTITION BY name) FROM Person GROUP BY name;"
+            ),
+            Some((ExecutionError::UngroupedColumn("age".to_string()), "age"))
+        );
+        assert_eq!(
+            validate_raw("SELECT COUNT(*) OVER (ORDER BY name RANGE 1 PRECEDING) FROM Person;"),
+            Some((
+                ExecutionError::RangeFrameOffset,
+                "COUNT(*) OVER (ORDER BY name RANGE 1 PRECEDING)"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_conditional_expressions() {
+        assert_eq!(
+            validate_raw(
+                "SELECT name, CASE WHEN COUNT(*) > 1 THEN MAX(age) ELSE NULL END FROM Person \
+                 WHERE COALESCE(age, 0) > 1 GROUP BY name ORDER BY NULLIF(name, 'Jo');"
+            ),
+            None
+        );
+        assert_eq!(
+            validate_raw("SELECT CASE WHEN age > 1 THEN 1 ELSE 'old' END FROM Person;"),
+            Some((
+                ExecutionError::ResultTypeMismatch {
+                    expression: "CASE".to_string(),
+                    left: SqlTypeInfo::Int,
+                    right: SqlTypeInfo::String
+                },
+                "CASE WHEN age > 1 THEN 1 ELSE 'old' END"
+            ))
+        );
+        assert_eq!(
+            validate_raw("SELECT name FROM Person WHERE GREATEST(age, name) > 1;"),
+            Some((
+                ExecutionError::ResultTypeMismatch {
+                    expression: "GREATEST".to_string(),
+                    left: SqlTypeInfo::Int,
+                    right: SqlTypeInfo::String
+                },
+                "GREATEST(age, name)"
+            ))
+        );
+        assert_eq!(
+            validate_raw("SELECT CASE age WHEN 1 THEN name END FROM Person GROUP BY name;"),
+            Some((ExecutionError::UngroupedColumn("age".to_string()), "age"))
+        );
+        assert_eq!(
+            validate_raw("SELECT name FROM Person WHERE COALESCE(COUNT(*), 0) > 1;"),
+            Some((ExecutionError::AggregateNotAllowed, "COUNT(*)"))
+        );
+    }
+
+    #[test]
+    fn test_order_by() {
+        assert_eq!(
+            validate_raw("SELECT name, age + 1 AS older FROM Person ORDER BY older, 1, age;"),
+            None
+        );
+        assert_eq!(
+            validate_raw("SELECT name FROM Person ORDER BY salary;"),
+            Some((
+                ExecutionError::ColumnNotFound {
+                    table: "person".to_string(),
+                    column: "salary".to_string()
+                },
+                "salary"
+            ))
+        );
+        assert_eq!(
+            validate_raw("SELECT * FROM Person ORDER BY 3;"),
+            Some((ExecutionError::OrderByPositionOutOfRange(3), "3"))
+        );
+    }
+
+    #[test]
+    fn test_group_by() {
+        assert_eq!(
+            validate_raw(
+                "SELECT name, COUNT(*), MAX(age) + 1 FROM Person GROUP BY name HAVING COUNT(*) > 1 ORDER BY SUM(age);"
+            ),
+            None
+        );
+        assert_eq!(
+            validate_raw("SELECT name, COUNT(*) FROM Person;"),
+            Some((ExecutionError::UngroupedColumn("name".to_string()), "name"))
+        );
+        assert_eq!(
+            validate_raw("SELECT name FROM Person GROUP BY name HAVING age > 1;"),
+            Some((ExecutionError::UngroupedColumn("age".to_string()), "age"))
+        );
+    }
+
+    #[test]
+    fn test_misplaced_aggregates() {
+        assert_eq!(
+            validate_raw("SELECT name FROM Person WHERE COUNT(*) > 1;"),
+            Some((ExecutionError::AggregateNotAllowed, "COUNT(*)"))
+        );
+        assert_eq!(
+            validate_raw("SELECT SUM(MAX(age)) FROM Person;"),
+            Some((ExecutionError::AggregateNotAllowed, "MAX(age)"))
+        );
+    }
+
+    #[test]
+    fn test_unknown_column_in_where_clause() {
+        assert_eq!(
+            validate_raw("SELECT name FROM Person WHERE salary > 10;"),
+            Some((
+                ExecutionError::ColumnNotFound {
+                    table: "person".to_string(),
+                    column: "salary".to_string()
+                },
+                "salary"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_insert_value_count_mismatch() {
+        assert_eq!(
+            validate_raw("INSERT INTO Person VALUES ('Jo');"),
+            Some((
+                ExecutionError::ValueCountMismatch {
+                    table: "person".to_string(),
+                    expected: 2,
+                    found: 1
+                },
+                "'Jo'"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_insert_type_mismatch() {
+        assert_eq!(
+            validate_raw("INSERT INTO Person (age, name) VALUES ('old', 'Jo');"),
+            Some((
+                ExecutionError::TypeMismatch {
+                    column: "age".to_string(),
+                    expected: SqlTypeInfo::Int,
+                    found: Value::String("old".to_string())
+                },
+                "'old'"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_insert_duplicate_column() {
+        assert_eq!(
+            validate_raw("INSERT INTO Person (age, age) VALUES (1, 2);"),
+            Some((ExecutionError::DuplicateColumn("age".to_string()), "age"))
+        );
+    }
+
+    #[test]
+    fn test_create_existing_table() {
+        assert_eq!(
+            validate_raw("CREATE TABLE Person (name string);"),
+            Some((
+                ExecutionError::TableAlreadyExists("person".to_string()),
+                "Person"
+            ))
+        );
+        assert_eq!(
+            validate_raw("CREATE TABLE IF NOT EXISTS Person (name string);"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_drop_and_truncate_missing_table() {
+        assert_eq!(
+            validate_raw("DROP TABLE Person, City;"),
+            Some((ExecutionError::TableNotFound("city".to_string()), "City"))
+        );
+        assert_eq!(
+            validate_raw("TRUNCATE Person, City;"),
+            Some((ExecutionError::TableNotFound("city".to_string()), "City"))
+        );
+        assert_eq!(validate_raw("DROP TABLE IF EXISTS Person, City;"), None);
+    }
+
+    #[test]
+    fn test_update_errors() {
+        assert_eq!(
+            validate_raw("UPDATE Person SET salary = 1;"),
+            Some((
+                ExecutionError::ColumnNotFound {
+                    table: "person".to_string(),
+                    column: "salary".to_string()
+                },
+                "salary"
+            ))
+        );
+        assert_eq!(
+            validate_raw("UPDATE Person SET age = 1, age = 2;"),
+            Some((ExecutionError::DuplicateColumn("age".to_string()), "age"))
+        );
+        assert_eq!(
+            validate_raw("UPDATE Person SET age = 'old';"),
+            Some((
+                ExecutionError::TypeMismatch {
+                    column: "age".to_string(),
+                    expected: SqlTypeInfo::Int,
+                    found: Value::String("old".to_string())
+                },
+                "'old'"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_alter_table_errors() {
+        assert_eq!(
+            validate_raw("ALTER TABLE Person ADD COLUMN age int;"),
+            Some((
+                ExecutionError::ColumnAlreadyExists {
+                    table: "person".to_string(),
+                    column: "age".to_string()
+                },
+                "age"
+            ))
+        );
+        assert_eq!(
+            validate_raw("ALTER TABLE Person DROP COLUMN salary;"),
+            Some((
+                ExecutionError::ColumnNotFound {
+                    table: "person".to_string(),
+                    column: "salary".to_string()
+                },
+                "salary"
+            ))
+        );
+        assert_eq!(
+            validate_raw("ALTER TABLE Person RENAME TO Person;"),
+            Some((
+                ExecutionError::TableAlreadyExists("person".to_string()),
+                "Person"
+            ))
+        );
+        assert_eq!(
+            validate_raw("ALTER TABLE Person RENAME age TO years;"),
+            None
+        );
+    }
+
+    // the subqueries of `raw` found not to read the row of an enclosing query
+    fn uncorrelated(raw: &str) -> HashSet<QueryExpression> {
+        let query = SqlQuery::parse_format_error(raw).unwrap();
+        check_query(&catalog(), &query).unwrap().uncorrelated
+    }
+
+    fn subquery(raw: &str) -> QueryExpression {
+        QueryExpression::parse_from_raw(raw).unwrap().1
+    }
+
+    #[test]
+    fn test_correlated_subqueries() {
+        assert_eq!(
+            uncorrelated(
+                "SELECT name FROM Person WHERE age IN (SELECT age FROM Person) \
+                 AND EXISTS (SELECT town FROM Home WHERE Home.name = Person.name);"
+            ),
+            HashSet::from([subquery("SELECT age FROM Person")])
+        );
+        // only the innermost subquery reads a row from outside of it
+        assert_eq!(
+            uncorrelated(
+                "SELECT (SELECT max(age) FROM Person AS p WHERE EXISTS \
+                 (SELECT town FROM Home WHERE Home.name = p.name)) FROM Home;"
+            ),
+            HashSet::from([subquery(
+                "SELECT max(age) FROM Person AS p WHERE EXISTS \
+                 (SELECT town FROM Home WHERE Home.name = p.name)"
+            )])
+        );
+        // the same subquery written twice is run for every row when either is
+        // correlated, here where `c` has no `town` column
+        assert_eq!(
+            uncorrelated(
+                "WITH c AS (SELECT town FROM Home) SELECT (SELECT town FROM c), name IN \
+                 (WITH c AS (SELECT name FROM Person) \
+                 SELECT name FROM Home WHERE town IN (SELECT town FROM c)) FROM Home;"
+            ),
+            HashSet::from([subquery(
+                "WITH c AS (SELECT name FROM Person) \
+                 SELECT name FROM Home WHERE town IN (SELECT town FROM c)"
+            )])
+        );
+    }
+}
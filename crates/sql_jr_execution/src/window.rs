@@ -0,0 +1,422 @@
+use std::{collections::HashMap, ops::Range};
+
+use sql_jr_parser::{
+    expression::Expression,
+    value::Value,
+    window::{FrameBound, FrameUnits, WindowFrame, WindowFunction, WindowSpec},
+};
+
+use crate::{
+    aggregate::{Accumulator, Group},
+    error::{ExecutionError, ExecutionResult},
+    evaluation::{as_float, evaluate, RowContext},
+    scope::Scope,
+    select::Executor,
+    sort::{compare_keys, SortOrder},
+};
+
+/// Add the window function calls of `expression` to `windows`, a call already there
+/// isn't added again so it is only computed once
+pub(crate) fn collect_windows<'a>(expression: &'a Expression, windows: &mut Vec<&'a Expression>) {
+    match expression {
+        // the windows of a subquery are computed over its own rows, and an aggregate
+        // is computed before any window
+        Expression::Column(_)
+        | Expression::Literal(_)
+        | Expression::Aggregate { .. }
+        | Expression::Subquery { .. }
+        | Expression::Exists { .. } => {}
+        Expression::Unary { expr, .. }
+        | Expression::InSubquery { expr, .. }
+        | Expression::IsNull { expr, .. } => collect_windows(expr, windows),
+        Expression::Binary { left, right, .. } => {
+            collect_windows(left, windows);
+            collect_windows(right, windows);
+        }
+        Expression::Case { .. } | Expression::Conditional { .. } => {
+            for operand in expression.conditional_operands() {
+                collect_windows(operand, windows);
+            }
+        }
+        Expression::Window { .. } => {
+            if !windows.contains(&expression) {
+                windows.push(expression);
+            }
+        }
+    }
+}
+
+/// Compute the window function calls `windows` for each of `rows`, the rows or groups
+/// left once filtered, and add their values to the row's aggregate values
+pub(crate) fn compute_windows<'a>(
+    scope: &Scope,
+    windows: &[&'a Expression],
+    rows: &mut [Group<'a>],
+    outer: Option<&RowContext>,
+    executor: &Executor,
+) -> ExecutionResult<()> {
+    for &call in windows {
+        let Expression::Window {
+            function, window, ..
+        } = call
+        else {
+            unreachable!("only window function calls are computed");
+        };
+        let values = window_values(function, window, scope, rows, outer, executor)?;
+        for ((_, computed), value) in rows.iter_mut().zip(values) {
+            computed.push((call, value));
+        }
+    }
+    Ok(())
+}
+
+// the value of `function` for each of `rows`: each partition is sorted by the ORDER BY
+// keys then scanned, computing the values of its rows in turn
+fn window_values(
+    function: &WindowFunction,
+    window: &WindowSpec,
+    scope: &Scope,
+    rows: &[Group],
+    outer: Option<&RowContext>,
+    executor: &Executor,
+) -> ExecutionResult<Vec<Value>> {
+    let context = |index: usize| RowContext {
+        scope,
+        row: &rows[index].0,
+        aggregates: &rows[index].1,
+        outer,
+        executor,
+    };
+
+    // the rows of each partition, partitions in the order their first row comes in
+    let mut partitions: Vec<Vec<usize>> = Vec::new();
+    let mut positions: HashMap<Vec<Value>, usize> = HashMap::new();
+    let mut order_keys = Vec::with_capacity(rows.len());
+    for index in 0..rows.len() {
+        let row = context(index);
+        let partition_key = window
+            .partition_by
+            .iter()
+            .map(|expr| evaluate(expr, &row))
+            .collect::<ExecutionResult<Vec<Value>>>()?;
+        order_keys.push(
+            window
+                .order_by
+                .iter()
+                .map(|order_by| evaluate(&order_by.expr, &row))
+                .collect::<ExecutionResult<Vec<Value>>>()?,
+        );
+        let position = *positions.entry(partition_key).or_insert_with(|| {
+            partitions.push(Vec::new());
+            partitions.len() - 1
+        });
+        partitions[position].push(index);
+    }
+
+    let orders: Vec<SortOrder> = window.order_by.iter().map(SortOrder::from).collect();
+    let frame = window.frame.unwrap_or_default();
+    let mut values = vec![Value::Null; rows.len()];
+    for mut partition in partitions {
+        // the sort is stable, rows tied on every key keep the order they came in
+        partition.sort_by(|&a, &b| compare_keys(&orders, &order_keys[a], &order_keys[b]));
+        let keys: Vec<&[Value]> = partition
+            .iter()
+            .map(|&index| order_keys[index].as_slice())
+            .collect();
+
+        let mut peers = 0..0;
+        let mut dense_rank = 0;
+        for (position, &index) in partition.iter().enumerate() {
+            // the rows tied with the row on the ORDER BY keys, all of them without any
+            if position == peers.end {
+                let tied = keys[position..]
+                    .iter()
+                    .take_while(|key| compare_keys(&orders, key, keys[position]).is_eq())
+                    .count();
+                peers = position..position + tied;
+                dense_rank += 1;
+            }
+            let frame_rows = || frame_rows(&frame, &orders, &keys, position, &peers);
+
+            values[index] = match function {
+                WindowFunction::RowNumber => Value::Int(position as i64 + 1),
+                WindowFunction::Rank => Value::Int(peers.start as i64 + 1),
+                WindowFunction::DenseRank => Value::Int(dense_rank),
+                WindowFunction::Lag(args) | WindowFunction::Lead(args) => {
+                    let offset = match &args.offset {
+                        Some(offset) => evaluate(offset, &context(index))?,
+                        None => Value::Int(1),
+                    };
+                    let offset = match offset {
+                        Value::Int(offset) => offset,
+                        // no row is NULL rows away, the value stays NULL
+                        Value::Null => continue,
+                        other => return Err(ExecutionError::InvalidWindowOffset(other)),
+                    };
+                    let offset = match function {
+                        WindowFunction::Lag(_) => offset.checked_neg(),
+                        _ => Some(offset),
+                    };
+                    // past either end of the partition there is no row to read
+                    let target = offset
+                        .and_then(|offset| (position as i64).checked_add(offset))
+                        .and_then(|target| usize::try_from(target).ok())
+                        .and_then(|target| partition.get(target));
+                    match (target, &args.default) {
+                        (Some(&target), _) => evaluate(&args.expr, &context(target))?,
+                        (None, Some(default)) => evaluate(default, &context(index))?,
+                        (None, None) => Value::Null,
+                    }
+                }
+                WindowFunction::FirstValue(expr) => match frame_rows()?.next() {
+                    Some(first) => evaluate(expr, &context(partition[first]))?,
+                    None => Value::Null,
+                },
+                WindowFunction::Aggregate {
+                    function,
+                    distinct,
+                    arg,
+                } => {
+                    let mut accumulator = Accumulator::from_parts(*function, *distinct, arg);
+                    for framed in frame_rows()? {
+                        accumulator.update(&context(partition[framed]))?;
+                    }
+                    accumulator.finish()
+                }
+            };
+        }
+    }
+    Ok(values)
+}
+
+// the positions in the sorted partition of the rows in the frame of the row at
+// `position`, whose peers are the rows tied with it
+fn frame_rows(
+    frame: &WindowFrame,
+    orders: &[SortOrder],
+    keys: &[&[Value]],
+    position: usize,
+    peers: &Range<usize>,
+) -> ExecutionResult<Range<usize>> {
+    let len = keys.len();
+    let bound = |bound: FrameBound, is_end: bool| -> ExecutionResult<usize> {
+        // the end of the frame is past the last row it holds
+        let past = usize::from(is_end);
+        let rows = |offset: u64| usize::try_from(offset).unwrap_or(usize::MAX);
+        Ok(match (frame.units, bound) {
+            (_, FrameBound::UnboundedPreceding) => 0,
+            (_, FrameBound::UnboundedFollowing) => len,
+            (FrameUnits::Rows, FrameBound::CurrentRow) => position + past,
+            (FrameUnits::Rows, FrameBound::Preceding(offset)) => {
+                (position + past).saturating_sub(rows(offset))
+            }
+            (FrameUnits::Rows, FrameBound::Following(offset)) => {
+                (position + past).saturating_add(rows(offset)).min(len)
+            }
+            (FrameUnits::Range, FrameBound::CurrentRow) if is_end => peers.end,
+            (FrameUnits::Range, FrameBound::CurrentRow) => peers.start,
+            (FrameUnits::Range, FrameBound::Preceding(offset)) => {
+                range_bound(orders, keys, position, peers, -(offset as f64), is_end)?
+            }
+            (FrameUnits::Range, FrameBound::Following(offset)) => {
+                range_bound(orders, keys, position, peers, offset as f64, is_end)?
+            }
+        })
+    };
+    let start = bound(frame.start, false)?;
+    let end = bound(frame.end, true)?;
+    Ok(start..end.max(start))
+}
+
+// the bound of a RANGE frame `offset` away from the row's ORDER BY value, which has to
+// be the only one and a number. Starting there the frame holds the first row at least
+// that far along in sort order, ending there the rows up to that far
+fn range_bound(
+    orders: &[SortOrder],
+    keys: &[&[Value]],
+    position: usize,
+    peers: &Range<usize>,
+    offset: f64,
+    is_end: bool,
+) -> ExecutionResult<usize> {
+    let [order] = orders else {
+        return Err(ExecutionError::RangeFrameOffset);
+    };
+    let numeric = |value: &Value| match value {
+        Value::Int(_) | Value::Float(_) | Value::Null => Ok(()),
+        _ => Err(ExecutionError::RangeFrameOffset),
+    };
+    let value = &keys[position][0];
+    numeric(value)?;
+    // a NULL is no distance from any value, only from the other NULLs it is tied with
+    if *value == Value::Null {
+        return Ok(if is_end { peers.end } else { peers.start });
+    }
+
+    // how far along in sort order each row's value is from the row's, NULLs are sorted
+    // before or after every value
+    let mut distances = Vec::with_capacity(keys.len());
+    for (other, key) in keys.iter().enumerate() {
+        numeric(&key[0])?;
+        distances.push(match &key[0] {
+            Value::Null if other < position => f64::NEG_INFINITY,
+            Value::Null => f64::INFINITY,
+            key if order.is_descending() => as_float(value) - as_float(key),
+            key => as_float(key) - as_float(value),
+        });
+    }
+    Ok(distances
+        .iter()
+        .position(|&distance| match is_end {
+            true => distance > offset,
+            false => distance >= offset,
+        })
+        .unwrap_or(keys.len()))
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use sql_jr_parser::{
+        create_statement::{Column, SqlTypeInfo},
+        types::{Parse, Span},
+    };
+
+    use super::*;
+    use crate::select::TableRows;
+
+    // the values of the window function call `raw` over rows with the columns
+    // `(g int, x int)`
+    fn window(raw: &str, rows: &[(i64, Option<i64>)]) -> ExecutionResult<Vec<Value>> {
+        let call = Expression::parse_from_raw(raw).unwrap().1;
+        let scope = Scope::table(
+            &"t".into(),
+            &["g", "x"].map(|name| Column {
+                name: name.into(),
+                type_info: SqlTypeInfo::Int,
+                span: Span::default(),
+            }),
+        );
+        let tables = TableRows::new();
+        let uncorrelated = HashSet::new();
+        let executor = Executor::new(&tables, &uncorrelated);
+        let mut rows: Vec<Group> = rows
+            .iter()
+            .map(|&(g, x)| {
+                let x = x.map_or(Value::Null, Value::Int);
+                (vec![Value::Int(g), x], Vec::new())
+            })
+            .collect();
+        compute_windows(&scope, &[&call], &mut rows, None, &executor)?;
+        Ok(rows
+            .into_iter()
+            .map(|(_, mut values)| values.remove(0).1)
+            .collect())
+    }
+
+    fn ints(values: &[i64]) -> ExecutionResult<Vec<Value>> {
+        Ok(values.iter().map(|value| Value::Int(*value)).collect())
+    }
+
+    const ROWS: [(i64, Option<i64>); 5] = [
+        (1, Some(3)),
+        (2, Some(1)),
+        (1, Some(1)),
+        (1, Some(3)),
+        (2, None),
+    ];
+
+    #[test]
+    fn test_ranking_functions() {
+        assert_eq!(
+            window("ROW_NUMBER() OVER ()", &ROWS),
+            ints(&[1, 2, 3, 4, 5])
+        );
+        assert_eq!(
+            window("ROW_NUMBER() OVER (PARTITION BY g ORDER BY x)", &ROWS),
+            ints(&[2, 1, 1, 3, 2])
+        );
+        assert_eq!(
+            window("RANK() OVER (ORDER BY x)", &ROWS),
+            ints(&[3, 1, 1, 3, 5])
+        );
+        assert_eq!(
+            window("DENSE_RANK() OVER (ORDER BY x)", &ROWS),
+            ints(&[2, 1, 1, 2, 3])
+        );
+        assert_eq!(window("RANK() OVER ()", &ROWS), ints(&[1, 1, 1, 1, 1]));
+    }
+
+    #[test]
+    fn test_lag_and_lead() {
+        let rows = [(1, Some(10)), (1, Some(20)), (1, Some(30))];
+
+        assert_eq!(
+            window("LAG(x) OVER (ORDER BY x)", &rows),
+            Ok(vec![Value::Null, Value::Int(10), Value::Int(20)])
+        );
+        assert_eq!(
+            window("LEAD(x, 2, 0) OVER (ORDER BY x)", &rows),
+            ints(&[30, 0, 0])
+        );
+        assert_eq!(
+            window("LAG(x, -1, g) OVER (ORDER BY x)", &rows),
+            ints(&[20, 30, 1])
+        );
+        assert_eq!(
+            window("LAG(x, 'a') OVER ()", &rows),
+            Err(ExecutionError::InvalidWindowOffset(Value::String(
+                "a".to_string()
+            )))
+        );
+    }
+
+    #[test]
+    fn test_aggregates_over_frames() {
+        let rows = [1, 2, 2, 4, 8].map(|x| (1, Some(x)));
+
+        // by default up to the rows tied with the row
+        assert_eq!(
+            window("SUM(x) OVER (ORDER BY x)", &rows),
+            ints(&[1, 5, 5, 9, 17])
+        );
+        assert_eq!(window("COUNT(*) OVER ()", &rows), ints(&[5, 5, 5, 5, 5]));
+        assert_eq!(
+            window(
+                "SUM(x) OVER (ORDER BY x ROWS BETWEEN 1 PRECEDING AND 1 FOLLOWING)",
+                &rows
+            ),
+            ints(&[3, 5, 8, 14, 12])
+        );
+        assert_eq!(
+            window(
+                "SUM(x) OVER (ORDER BY x RANGE BETWEEN 2 PRECEDING AND CURRENT ROW)",
+                &rows
+            ),
+            ints(&[1, 5, 5, 8, 8])
+        );
+        assert_eq!(
+            window(
+                "COUNT(*) OVER (ORDER BY x DESC RANGE BETWEEN CURRENT ROW AND 2 FOLLOWING)",
+                &rows
+            ),
+            ints(&[1, 3, 3, 3, 1])
+        );
+        assert_eq!(
+            window(
+                "FIRST_VALUE(x) OVER (ORDER BY x ROWS BETWEEN 1 FOLLOWING AND UNBOUNDED FOLLOWING)",
+                &rows
+            ),
+            Ok([2, 2, 4, 8]
+                .map(Value::Int)
+                .into_iter()
+                .chain([Value::Null])
+                .collect())
+        );
+        assert_eq!(
+            window("SUM(x) OVER (ORDER BY g, x RANGE 1 PRECEDING)", &rows),
+            Err(ExecutionError::RangeFrameOffset)
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use nom::{
+    branch::alt,
+    bytes::complete::tag_no_case,
+    combinator::{map, opt},
+    error::context,
+    sequence::{preceded, separated_pair, tuple},
+};
+use nom_supreme::ParserExt;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common_parsers::{identifier, keyword, object_name, spanned, whitespace1},
+    create_statement::{Column, SqlTypeInfo},
+    ident::{Ident, ObjectName},
+    types::{Parse, ParserResult, RawSpan, Span},
+};
+
+/// The change an ALTER TABLE makes to its table
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum AlterTableAction {
+    /// `ADD [COLUMN] <name> <type>`, existing rows get NULL for it
+    AddColumn(Column),
+    /// `DROP [COLUMN] <name>`
+    DropColumn(Ident),
+    /// `RENAME [COLUMN] <from> TO <to>`
+    RenameColumn { from: Ident, to: Ident },
+    /// `RENAME TO <name>`
+    RenameTable(ObjectName),
+    /// `ALTER [COLUMN] <name> [SET DATA] TYPE <type>`, converting the stored values
+    AlterColumnType {
+        column: Ident,
+        type_info: SqlTypeInfo,
+    },
+}
+
+/// The table to change and how
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct AlterTableStatement {
+    pub table: ObjectName,
+    pub action: AlterTableAction,
+    #[serde(skip)]
+    pub span: Span,
+}
+
+fn parse_table_name(input: RawSpan<'_>) -> ParserResult<'_, ObjectName> {
+    let alter_table = tuple((
+        tag_no_case("alter"),
+        whitespace1,
+        tag_no_case("table"),
+        whitespace1,
+    ));
+    preceded(alter_table, object_name.context("Table Name"))(input)
+}
+
+// parses "<action> [COLUMN] ", where COLUMN is optional
+fn column_action<'a>(action: &'static str) -> impl FnMut(RawSpan<'a>) -> ParserResult<'a, ()> {
+    map(
+        tuple((
+            keyword(action),
+            whitespace1,
+            opt(tuple((keyword("column"), whitespace1))),
+        )),
+        |_| (),
+    )
+}
+
+fn add_column(input: RawSpan<'_>) -> ParserResult<'_, AlterTableAction> {
+    map(
+        preceded(column_action("add"), Column::parse),
+        AlterTableAction::AddColumn,
+    )(input)
+}
+
+fn drop_column(input: RawSpan<'_>) -> ParserResult<'_, AlterTableAction> {
+    map(
+        preceded(column_action("drop"), identifier.context("Column Name")),
+        AlterTableAction::DropColumn,
+    )(input)
+}
+
+fn rename_table(input: RawSpan<'_>) -> ParserResult<'_, AlterTableAction> {
+    map(
+        preceded(
+            tuple((keyword("rename"), whitespace1, keyword("to"), whitespace1)),
+            object_name.context("Table Name"),
+        ),
+        AlterTableAction::RenameTable,
+    )(input)
+}
+
+fn rename_column(input: RawSpan<'_>) -> ParserResult<'_, AlterTableAction> {
+    map(
+        preceded(
+            column_action("rename"),
+            separated_pair(
+                identifier.context("Column Name"),
+                tuple((whitespace1, keyword("to"), whitespace1)),
+                identifier.context("Column Name"),
+            ),
+        ),
+        |(from, to)| AlterTableAction::RenameColumn { from, to },
+    )(input)
+}
+
+fn alter_column_type(input: RawSpan<'_>) -> ParserResult<'_, AlterTableAction> {
+    let set_data_type = tuple((
+        opt(tuple((
+            keyword("set"),
+            whitespace1,
+            keyword("data"),
+            whitespace1,
+        ))),
+        keyword("type"),
+        whitespace1,
+    ));
+    map(
+        preceded(
+            column_action("alter"),
+            separated_pair(
+                identifier.context("Column Name"),
+                whitespace1,
+                preceded(set_data_type, SqlTypeInfo::parse),
+            ),
+        ),
+        |(column, type_info)| AlterTableAction::AlterColumnType { column, type_info },
+    )(input)
+}
+
+impl<'a> Parse<'a> for AlterTableAction {
+    fn parse(input: RawSpan<'a>) -> ParserResult<'a, Self> {
+        context(
+            "Alter Table Action",
+            // RENAME TO has to be tried before RENAME <column> TO
+            alt((
+                add_column,
+                drop_column,
+                rename_table,
+                rename_column,
+                alter_column_type,
+            )),
+        )(input)
+    }
+}
+
+impl<'a> Parse<'a> for AlterTableStatement {
+    fn parse(input: RawSpan<'a>) -> ParserResult<'a, Self> {
+        map(
+            spanned(separated_pair(
+                parse_table_name,
+                whitespace1,
+                AlterTableAction::parse,
+            )),
+            |((table, action), span)| Self {
+                table,
+                action,
+                span,
+            },
+        )(input)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn action(raw: &str) -> AlterTableAction {
+        AlterTableStatement::parse_from_raw(raw).unwrap().1.action
+    }
+
+    #[test]
+    fn test_parse_add_column() {
+        assert_eq!(
+            action("ALTER TABLE Person ADD COLUMN age int"),
+            AlterTableAction::AddColumn(Column {
+                name: "age".into(),
+                type_info: SqlTypeInfo::Int,
+                span: Span::default(),
+            })
+        );
+        assert_eq!(
+            action("alter table Person add age int"),
+            action("ALTER TABLE Person ADD COLUMN age int")
+        );
+    }
+
+    #[test]
+    fn test_parse_drop_column() {
+        assert_eq!(
+            action("ALTER TABLE Person DROP COLUMN age;"),
+            AlterTableAction::DropColumn("age".into())
+        );
+    }
+
+    #[test]
+    fn test_parse_renames() {
+        assert_eq!(
+            action("ALTER TABLE Person RENAME TO People"),
+            AlterTableAction::RenameTable("people".into())
+        );
+        assert_eq!(
+            action("ALTER TABLE Person RENAME COLUMN age TO years"),
+            AlterTableAction::RenameColumn {
+                from: "age".into(),
+                to: "years".into()
+            }
+        );
+        assert_eq!(
+            action("ALTER TABLE Person RENAME age TO years"),
+            action("ALTER TABLE Person RENAME COLUMN age TO years")
+        );
+    }
+
+    #[test]
+    fn test_parse_alter_column_type() {
+        let expected = AlterTableAction::AlterColumnType {
+            column: "age".into(),
+            type_info: SqlTypeInfo::String,
+        };
+
+        assert_eq!(
+            action("ALTER TABLE Person ALTER COLUMN age TYPE string"),
+            expected
+        );
+        assert_eq!(
+            action("ALTER TABLE Person ALTER age SET DATA TYPE string"),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_parse_unknown_action_is_an_error() {
+        assert!(AlterTableStatement::parse_from_raw("ALTER TABLE Person MOVE age").is_err());
+    }
+}
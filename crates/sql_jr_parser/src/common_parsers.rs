@@ -1,17 +1,184 @@
 use nom::{
     branch::alt,
-    bytes::complete::{tag, take_while, take_while1},
-    combinator::map,
-    sequence::delimited,
+    bytes::complete::{is_not, tag, tag_no_case, take_until, take_while},
+    character::complete::{char, digit1, multispace1, satisfy},
+    combinator::{consumed, cut, map, map_res, not, opt, recognize, value, verify},
+    error::context,
+    multi::{fold_many0, many0_count, many1_count, separated_list1},
+    sequence::{delimited, pair, preceded, terminated, tuple},
 };
+use nom_supreme::ParserExt;
 
-use crate::types::{ParserResult, RawSpan};
+use crate::{
+    ident::{Ident, ObjectName},
+    types::{ParserResult, RawSpan, Span},
+    value::{Literal, Value},
+};
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+// a word starting with a letter or `_`, going on with letters, digits, `_` and `$`.
+// It is folded to lower case, so `Person` and `person` are the same name
+fn unquoted_identifier(i: RawSpan) -> ParserResult<Ident> {
+    map(
+        recognize(pair(
+            satisfy(|c: char| c.is_alphabetic() || c == '_'),
+            take_while(is_identifier_char),
+        )),
+        |s: RawSpan| Ident::new(s.fragment().to_lowercase(), s.into()),
+    )(i)
+}
+
+// any text between two `quote`s, a quote in the name being written twice
+fn quoted_with<'a>(quote: &'static str) -> impl FnMut(RawSpan<'a>) -> ParserResult<'a, Ident> {
+    let name = fold_many0(
+        alt((
+            map(is_not(quote), |s: RawSpan| *s.fragment()),
+            value(quote, pair(tag(quote), tag(quote))),
+        )),
+        String::new,
+        |mut name, part| {
+            name.push_str(part);
+            name
+        },
+    );
+    map(
+        spanned(preceded(
+            tag(quote),
+            cut(terminated(
+                verify(name, |name: &str| !name.is_empty()),
+                tag(quote),
+            ))
+            .context("Quoted Identifier"),
+        )),
+        |(name, span)| Ident::new(name, span),
+    )
+}
+
+/// Parse an identifier quoted with `"` or with backticks, which keeps the case it is
+/// written in and may be a keyword or contain any character
+pub(crate) fn quoted_identifier(i: RawSpan) -> ParserResult<Ident> {
+    alt((quoted_with("\""), quoted_with("`")))(i)
+}
+
+/// Parse a sql identifier, either a word of letters, digits, `_` and `$` that doesn't
+/// start with a digit or `$`, which is folded to lower case, or a quoted identifier
+/// kept as written
+pub(crate) fn identifier(i: RawSpan) -> ParserResult<Ident> {
+    alt((quoted_identifier, unquoted_identifier))(i)
+}
+
+/// Parse a table name, which may be qualified as in `public.person`
+pub(crate) fn object_name(i: RawSpan) -> ParserResult<ObjectName> {
+    map(separated_list1(char('.'), identifier), ObjectName)(i)
+}
+
+// a `--` comment running to the end of the line
+fn line_comment(i: RawSpan) -> ParserResult<RawSpan> {
+    recognize(pair(tag("--"), opt(is_not("\r\n"))))(i)
+}
+
+// a `/* */` comment, which may span several lines
+fn block_comment(i: RawSpan) -> ParserResult<RawSpan> {
+    recognize(tuple((
+        tag("/*"),
+        take_until("*/").context("Block Comment End"),
+        tag("*/"),
+    )))(i)
+}
+
+fn whitespace_or_comment(i: RawSpan) -> ParserResult<RawSpan> {
+    alt((multispace1, line_comment, block_comment))(i)
+}
+
+/// Skip any amount of whitespace and comments
+pub(crate) fn whitespace0(i: RawSpan) -> ParserResult<RawSpan> {
+    recognize(many0_count(whitespace_or_comment))(i)
+}
+
+/// Skip whitespace and comments, requiring at least one of them
+pub(crate) fn whitespace1(i: RawSpan) -> ParserResult<RawSpan> {
+    recognize(many1_count(whitespace_or_comment))(i)
+}
+
+/// Run `parser` and also return the span of the input it consumed
+pub(crate) fn spanned<'a, O>(
+    parser: impl FnMut(RawSpan<'a>) -> ParserResult<'a, O>,
+) -> impl FnMut(RawSpan<'a>) -> ParserResult<'a, (O, Span)> {
+    map(consumed(parser), |(source, output)| (output, source.into()))
+}
+
+/// Parse a case insensitive keyword that is not just the prefix of a longer word,
+/// so `or` does not match the start of `order`
+pub(crate) fn keyword<'a>(
+    word: &'static str,
+) -> impl FnMut(RawSpan<'a>) -> ParserResult<'a, RawSpan<'a>> {
+    terminated(tag_no_case(word), not(satisfy(is_identifier_char)))
+}
 
-/// Parse a unquoted sql identifier
-pub(crate) fn identifier(i: RawSpan) -> ParserResult<String> {
-    let take_aplphanumeric = take_while1(|c: char| c.is_alphanumeric());
-    let to_string = |s: RawSpan| s.fragment().to_string();
-    map(take_aplphanumeric, to_string)(i)
+// words that follow a select list or a table name, so they can't be read as an alias
+// written without AS
+const RESERVED_KEYWORDS: &[&str] = &[
+    "as",
+    "cross",
+    "except",
+    "fetch",
+    "from",
+    "full",
+    "group",
+    "having",
+    "inner",
+    "intersect",
+    "join",
+    "left",
+    "limit",
+    "natural",
+    "offset",
+    "on",
+    "order",
+    "right",
+    "union",
+    "using",
+    "where",
+];
+
+/// Parse an alias, `AS name` or just `name` when it is quoted or isn't a reserved keyword
+pub(crate) fn alias(i: RawSpan) -> ParserResult<Ident> {
+    alt((
+        preceded(
+            pair(keyword("as"), whitespace1),
+            identifier.context("Alias"),
+        ),
+        quoted_identifier,
+        verify(unquoted_identifier, |ident: &Ident| {
+            !RESERVED_KEYWORDS
+                .iter()
+                .any(|word| ident.as_str().eq_ignore_ascii_case(word))
+        }),
+    ))(i)
+}
+
+/// Parse an optional `IF EXISTS`, returning whether it was there
+pub(crate) fn if_exists(i: RawSpan) -> ParserResult<bool> {
+    map(
+        opt(tuple((
+            keyword("if"),
+            whitespace1,
+            keyword("exists"),
+            whitespace1,
+        ))),
+        |parsed| parsed.is_some(),
+    )(i)
+}
+
+/// Parse a comma separated list of table names
+pub(crate) fn table_names(i: RawSpan) -> ParserResult<Vec<ObjectName>> {
+    separated_list1(
+        tuple((whitespace0, char(','), whitespace0)),
+        object_name.context("Table Name"),
+    )(i)
 }
 
 pub(crate) fn parse_single_quote_str(i: RawSpan) -> ParserResult<String> {
@@ -21,15 +188,40 @@ pub(crate) fn parse_single_quote_str(i: RawSpan) -> ParserResult<String> {
     )(i)
 }
 
-pub(crate) fn parse_double_quote_str(i: RawSpan) -> ParserResult<String> {
-    map(
-        delimited(tag("\""), take_while(|c| c != '"'), tag("\"")),
-        |s: RawSpan| s.fragment().to_string(),
+// parses an optionally negative integer or decimal number
+fn parse_number(i: RawSpan) -> ParserResult<Value> {
+    map_res(
+        recognize(tuple((
+            opt(char('-')),
+            digit1,
+            opt(pair(char('.'), digit1)),
+        ))),
+        |number: RawSpan| {
+            let number = *number.fragment();
+            if number.contains('.') {
+                number.parse().map(Value::Float).map_err(|e| e.to_string())
+            } else {
+                number.parse().map(Value::Int).map_err(|e| e.to_string())
+            }
+        },
     )(i)
 }
 
-pub(crate) fn parse_string(i: RawSpan) -> ParserResult<String> {
-    alt((parse_single_quote_str, parse_double_quote_str))(i)
+/// Parse a literal value: a number, a quoted string, `true`, `false` or `null`
+pub(crate) fn parse_literal(i: RawSpan) -> ParserResult<Literal> {
+    context(
+        "Literal",
+        map(
+            spanned(alt((
+                map(keyword("null"), |_| Value::Null),
+                map(keyword("true"), |_| Value::Boolean(true)),
+                map(keyword("false"), |_| Value::Boolean(false)),
+                parse_number,
+                map(parse_single_quote_str, Value::String),
+            ))),
+            |(value, span)| Literal { value, span },
+        ),
+    )(i)
 }
 
 #[cfg(test)]
@@ -42,10 +234,113 @@ mod test {
     fn test_parse_identifier() {
         let (remaining, parsed) = identifier(LocatedSpan::new("aVariable10 = aValue")).unwrap();
 
-        assert_eq!(parsed, "aVariable10".to_string());
+        assert_eq!(parsed, "avariable10".to_string());
+        assert_eq!(parsed.span.offset, 0);
+        assert_eq!(parsed.span.length, 11);
         assert_eq!(*remaining.fragment(), " = aValue");
     }
 
+    #[test]
+    fn test_parse_identifier_with_underscores_and_dollars() {
+        let (remaining, parsed) = identifier(LocatedSpan::new("_first_name$2 FROM")).unwrap();
+
+        assert_eq!(parsed, "_first_name$2".to_string());
+        assert_eq!(*remaining.fragment(), " FROM");
+        assert!(identifier(LocatedSpan::new("2nd")).is_err());
+        assert!(identifier(LocatedSpan::new("$name")).is_err());
+    }
+
+    #[test]
+    fn test_parse_quoted_identifier() {
+        let (remaining, parsed) =
+            identifier(LocatedSpan::new("\"Order \"\"Details\"\"\" AS o")).unwrap();
+
+        assert_eq!(parsed, "Order \"Details\"".to_string());
+        assert_eq!(parsed.span.offset, 0);
+        assert_eq!(parsed.span.length, 19);
+        assert_eq!(*remaining.fragment(), " AS o");
+    }
+
+    #[test]
+    fn test_parse_backtick_quoted_identifier() {
+        let (_, parsed) = identifier(LocatedSpan::new("`select``s`")).unwrap();
+
+        assert_eq!(parsed, "select`s".to_string());
+    }
+
+    #[test]
+    fn test_empty_or_unterminated_quoted_identifier_is_an_error() {
+        assert!(identifier(LocatedSpan::new("\"\"")).is_err());
+        assert!(identifier(LocatedSpan::new("\"Order Details")).is_err());
+    }
+
+    #[test]
+    fn test_unquoted_identifiers_are_folded_to_lower_case() {
+        let parse = |raw| identifier(LocatedSpan::new(raw)).unwrap().1;
+
+        assert_eq!(parse("Person"), "person".to_string());
+        assert_eq!(parse("Person"), parse("person"));
+        assert_eq!(parse("PERSON"), parse("person"));
+        assert_eq!(parse("\"Person\""), "Person".to_string());
+        assert_ne!(parse("\"Person\""), parse("Person"));
+        assert_eq!(parse("\"person\""), parse("Person"));
+    }
+
+    #[test]
+    fn test_parse_object_name() {
+        let (remaining, parsed) = object_name(LocatedSpan::new("public.\"Person\".*")).unwrap();
+
+        assert_eq!(parsed, ObjectName(vec!["public".into(), "Person".into()]));
+        assert_eq!(parsed.base(), "Person");
+        assert_eq!(parsed.span().length, 15);
+        assert_eq!(*remaining.fragment(), ".*");
+    }
+
+    #[test]
+    fn test_quoted_alias_may_be_a_keyword() {
+        let (_, parsed) = alias(LocatedSpan::new("\"from\"")).unwrap();
+
+        assert_eq!(parsed, "from".to_string());
+        assert!(alias(LocatedSpan::new("from")).is_err());
+    }
+
+    #[test]
+    fn test_parse_keyword() {
+        let (remaining, _) = keyword("and")(LocatedSpan::new("AND b")).unwrap();
+
+        assert_eq!(*remaining.fragment(), " b");
+        assert!(keyword("and")(LocatedSpan::new("android")).is_err());
+    }
+
+    #[test]
+    fn test_parse_if_exists() {
+        let (remaining, parsed) = if_exists(LocatedSpan::new("IF EXISTS Person")).unwrap();
+
+        assert!(parsed);
+        assert_eq!(*remaining.fragment(), "Person");
+        assert!(!if_exists(LocatedSpan::new("ifs")).unwrap().1);
+    }
+
+    #[test]
+    fn test_parse_table_names() {
+        let (_, parsed) = table_names(LocatedSpan::new("Person ,City,Country")).unwrap();
+
+        assert_eq!(
+            parsed,
+            vec![ObjectName::from("person"), "city".into(), "country".into()]
+        );
+    }
+
+    #[test]
+    fn test_dotted_and_qualified_names_are_written_differently() {
+        let (_, parsed) =
+            table_names(LocatedSpan::new("\"a.b\", a.b, \"say \"\"hi\"\"\"")).unwrap();
+        let written: Vec<_> = parsed.iter().map(ToString::to_string).collect();
+
+        assert_eq!(written, vec!["\"a.b\"", "a.b", "\"say \"\"hi\"\"\""]);
+        assert_ne!(parsed[0], parsed[1]);
+    }
+
     #[test]
     fn test_parse_single_quote_string() {
         let (_, parsed) = parse_single_quote_str(LocatedSpan::new("'First', 'Second'")).unwrap();
@@ -62,18 +357,81 @@ mod test {
     }
 
     #[test]
-    fn test_parse_double_quote_string() {
-        let (_, parsed) =
-            parse_double_quote_str(LocatedSpan::new("\"First\", \"Second\"")).unwrap();
+    fn test_double_quotes_are_not_a_string_literal() {
+        assert!(parse_literal(LocatedSpan::new("\"First\"")).is_err());
+    }
 
-        assert_eq!(parsed, "First".to_string())
+    #[test]
+    fn test_parse_integer_literal() {
+        let (_, positive) = parse_literal(LocatedSpan::new("42")).unwrap();
+        let (_, negative) = parse_literal(LocatedSpan::new("-7")).unwrap();
+
+        assert_eq!(positive, Value::Int(42));
+        assert_eq!(negative, Value::Int(-7));
     }
 
     #[test]
-    fn test_parse_double_quote_string_containing_multiple_words() {
-        let (_, parsed) =
-            parse_double_quote_str(LocatedSpan::new("\"First And, Only\", \"Second\"")).unwrap();
+    fn test_parse_float_literal() {
+        let (_, parsed) = parse_literal(LocatedSpan::new("-3.25")).unwrap();
 
-        assert_eq!(parsed, "First And, Only".to_string())
+        assert_eq!(parsed, Value::Float(-3.25));
+    }
+
+    #[test]
+    fn test_parse_out_of_range_integer_literal() {
+        assert!(parse_literal(LocatedSpan::new("99999999999999999999")).is_err());
+    }
+
+    #[test]
+    fn test_parse_keyword_literals() {
+        let (_, null) = parse_literal(LocatedSpan::new("NULL")).unwrap();
+        let (_, yes) = parse_literal(LocatedSpan::new("true")).unwrap();
+        let (_, no) = parse_literal(LocatedSpan::new("False")).unwrap();
+
+        assert_eq!(null, Value::Null);
+        assert_eq!(yes, Value::Boolean(true));
+        assert_eq!(no, Value::Boolean(false));
+    }
+
+    #[test]
+    fn test_parse_string_literal() {
+        let (_, parsed) = parse_literal(LocatedSpan::new("'Oslo'")).unwrap();
+
+        assert_eq!(parsed, Value::String("Oslo".to_string()));
+        assert_eq!(parsed.span.offset, 0);
+        assert_eq!(parsed.span.length, 6);
+    }
+
+    #[test]
+    fn test_keyword_prefixed_identifier_is_not_a_literal() {
+        assert!(parse_literal(LocatedSpan::new("nullable")).is_err());
+    }
+
+    #[test]
+    fn test_whitespace_skips_comments() {
+        let (remaining, _) = whitespace0(LocatedSpan::new(
+            " -- a line comment\n /* a block\n comment */\tSELECT",
+        ))
+        .unwrap();
+
+        assert_eq!(*remaining.fragment(), "SELECT");
+    }
+
+    #[test]
+    fn test_line_comment_at_end_of_input() {
+        let (remaining, _) = whitespace1(LocatedSpan::new("-- the end")).unwrap();
+
+        assert_eq!(*remaining.fragment(), "");
+    }
+
+    #[test]
+    fn test_whitespace1_requires_whitespace_or_comment() {
+        assert!(whitespace1(LocatedSpan::new("SELECT")).is_err());
+        assert!(whitespace1(LocatedSpan::new("/**/SELECT")).is_ok());
+    }
+
+    #[test]
+    fn test_unterminated_block_comment_is_an_error() {
+        assert!(whitespace1(LocatedSpan::new("/* never closed")).is_err());
     }
 }
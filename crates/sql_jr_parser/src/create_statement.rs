@@ -1,15 +1,18 @@
+use std::fmt::{self, Display, Formatter};
+
 use nom::branch::alt;
 use nom::bytes::complete::tag_no_case;
-use nom::character::complete::{char, multispace0, multispace1};
-use nom::combinator::map;
+use nom::character::complete::char;
+use nom::combinator::{map, opt};
 use nom::error::context;
 use nom::multi::separated_list1;
 use nom::sequence::{preceded, separated_pair, tuple};
 use nom_supreme::ParserExt;
 use serde::{Deserialize, Serialize};
 
-use crate::common_parsers::identifier;
-use crate::types::{Parse, ParserResult, RawSpan};
+use crate::common_parsers::{identifier, keyword, object_name, spanned, whitespace0, whitespace1};
+use crate::ident::{Ident, ObjectName};
+use crate::types::{Parse, ParserResult, RawSpan, Span};
 
 /// A colum's type
 #[derive(Debug, Clone, Eq, Hash, PartialEq, PartialOrd, Serialize, Deserialize)]
@@ -32,11 +35,22 @@ impl<'a> Parse<'a> for SqlTypeInfo {
     }
 }
 
+impl Display for SqlTypeInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::String => write!(f, "string"),
+            Self::Int => write!(f, "int"),
+        }
+    }
+}
+
 /// A column's name + type
 #[derive(Debug, Clone, Eq, Hash, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct Column {
-    pub name: String,
+    pub name: Ident,
     pub type_info: SqlTypeInfo,
+    #[serde(skip)]
+    pub span: Span,
 }
 
 // parses "<colName> <colType>"
@@ -45,12 +59,16 @@ impl<'a> Parse<'a> for Column {
         context(
             "Create Column",
             map(
-                separated_pair(
+                spanned(separated_pair(
                     identifier.context("Column Name"),
-                    multispace1,
+                    whitespace1,
                     SqlTypeInfo::parse,
-                ),
-                |(name, type_info)| Self { name, type_info },
+                )),
+                |((name, type_info), span)| Self {
+                    name,
+                    type_info,
+                    span,
+                },
             ),
         )(input)
     }
@@ -59,8 +77,13 @@ impl<'a> Parse<'a> for Column {
 /// The table and its columns to create
 #[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub struct CreateStatement {
-    pub table: String,
+    pub table: ObjectName,
     pub columns: Vec<Column>,
+    /// Creating a table that already exists does nothing instead of failing
+    #[serde(default)]
+    pub if_not_exists: bool,
+    #[serde(skip)]
+    pub span: Span,
 }
 
 // parses a comma seperated list of column definitions contained in parens
@@ -70,7 +93,7 @@ fn column_definitions(input: RawSpan<'_>) -> ParserResult<'_, Vec<Column>> {
         map(
             tuple((
                 char('('),
-                separated_list1(tuple((multispace0, char(','), multispace0)), Column::parse),
+                separated_list1(tuple((whitespace0, char(','), whitespace0)), Column::parse),
                 char(')'),
             )),
             |(_, cols, _)| cols,
@@ -78,24 +101,44 @@ fn column_definitions(input: RawSpan<'_>) -> ParserResult<'_, Vec<Column>> {
     )(input)
 }
 
+// parses an optional "IF NOT EXISTS", returning whether it was there
+fn if_not_exists(input: RawSpan<'_>) -> ParserResult<'_, bool> {
+    map(
+        opt(tuple((
+            keyword("if"),
+            whitespace1,
+            keyword("not"),
+            whitespace1,
+            keyword("exists"),
+            whitespace1,
+        ))),
+        |parsed| parsed.is_some(),
+    )(input)
+}
+
 impl<'a> Parse<'a> for CreateStatement {
     fn parse(input: RawSpan<'a>) -> ParserResult<'a, Self> {
         map(
-            separated_pair(
+            spanned(separated_pair(
                 preceded(
                     tuple((
                         tag_no_case("create"),
-                        multispace1,
+                        whitespace1,
                         tag_no_case("table"),
-                        multispace1,
+                        whitespace1,
                     )),
-                    identifier.context("Table Name"),
+                    tuple((if_not_exists, object_name.context("Table Name"))),
                 ),
-                multispace1,
+                whitespace1,
                 column_definitions,
-            )
+            ))
             .context("Create Table"),
-            |(table, columns)| Self { table, columns },
+            |(((if_not_exists, table), columns), span)| Self {
+                table,
+                columns,
+                if_not_exists,
+                span,
+            },
         )(input)
     }
 }
@@ -127,8 +170,9 @@ mod test {
         assert_eq!(
             column,
             Column {
-                name: "age".to_string(),
-                type_info: SqlTypeInfo::Int
+                name: "age".into(),
+                type_info: SqlTypeInfo::Int,
+                span: Span::default(),
             }
         );
     }
@@ -140,8 +184,9 @@ mod test {
         assert_eq!(
             column,
             Column {
-                name: "address".to_string(),
-                type_info: SqlTypeInfo::String
+                name: "address".into(),
+                type_info: SqlTypeInfo::String,
+                span: Span::default(),
             }
         );
     }
@@ -155,12 +200,14 @@ mod test {
             column,
             vec![
                 Column {
-                    name: "address".to_string(),
-                    type_info: SqlTypeInfo::String
+                    name: "address".into(),
+                    type_info: SqlTypeInfo::String,
+                    span: Span::default(),
                 },
                 Column {
-                    name: "age".to_string(),
-                    type_info: SqlTypeInfo::Int
+                    name: "age".into(),
+                    type_info: SqlTypeInfo::Int,
+                    span: Span::default(),
                 }
             ]
         );
@@ -174,18 +221,32 @@ mod test {
         assert_eq!(
             statement,
             CreateStatement {
-                table: "Person".to_string(),
+                table: "person".into(),
                 columns: vec![
                     Column {
-                        name: "name".to_string(),
-                        type_info: SqlTypeInfo::String
+                        name: "name".into(),
+                        type_info: SqlTypeInfo::String,
+                        span: Span::default(),
                     },
                     Column {
-                        name: "age".to_string(),
-                        type_info: SqlTypeInfo::Int
+                        name: "age".into(),
+                        type_info: SqlTypeInfo::Int,
+                        span: Span::default(),
                     }
-                ]
+                ],
+                if_not_exists: false,
+                span: Span::default(),
             }
         )
     }
+
+    #[test]
+    fn test_parse_create_statement_if_not_exists() {
+        let (_, statement) =
+            CreateStatement::parse_from_raw("CREATE TABLE IF NOT EXISTS Person (name string)")
+                .unwrap();
+
+        assert_eq!(statement.table, "person".into());
+        assert!(statement.if_not_exists);
+    }
 }
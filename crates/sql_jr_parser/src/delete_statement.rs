@@ -0,0 +1,99 @@
+use nom::{
+    bytes::complete::tag_no_case,
+    combinator::{map, opt},
+    sequence::{preceded, tuple},
+};
+use nom_supreme::ParserExt;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common_parsers::{object_name, spanned, whitespace1},
+    expression::Expression,
+    ident::ObjectName,
+    select_statement::parse_where_clause,
+    types::{Parse, ParserResult, RawSpan, Span},
+};
+
+/// The table to delete rows from, all of them unless a WHERE clause picks some
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct DeleteStatement {
+    pub table: ObjectName,
+    pub where_clause: Option<Expression>,
+    #[serde(skip)]
+    pub span: Span,
+}
+
+fn parse_table_name(input: RawSpan<'_>) -> ParserResult<'_, ObjectName> {
+    let delete_from = tuple((
+        tag_no_case("delete"),
+        whitespace1,
+        tag_no_case("from"),
+        whitespace1,
+    ));
+    preceded(delete_from, object_name.context("Table Name"))(input)
+}
+
+impl<'a> Parse<'a> for DeleteStatement {
+    fn parse(input: RawSpan<'a>) -> ParserResult<'a, Self> {
+        map(
+            spanned(tuple((parse_table_name, opt(parse_where_clause)))),
+            |((table, where_clause), span)| Self {
+                table,
+                where_clause,
+                span,
+            },
+        )(input)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nom_locate::LocatedSpan;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_table_name() {
+        let (_, parsed) = parse_table_name(LocatedSpan::new("delete from Person")).unwrap();
+
+        assert_eq!(parsed, "person".into());
+    }
+
+    #[test]
+    fn test_parse_delete_statement() {
+        let (_, statement) =
+            DeleteStatement::parse_from_raw("DELETE FROM Person WHERE age < 18;").unwrap();
+
+        assert_eq!(
+            statement,
+            DeleteStatement {
+                table: "person".into(),
+                where_clause: Some(Expression::parse_from_raw("age < 18").unwrap().1),
+                span: Span::default(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_delete_statement_where_is_null() {
+        let statement =
+            DeleteStatement::parse_format_error("DELETE FROM Person WHERE age IS NULL").unwrap();
+
+        assert_eq!(
+            statement.where_clause,
+            Some(Expression::IsNull {
+                expr: Box::new(Expression::parse_from_raw("age").unwrap().1),
+                negated: false,
+                span: Span::default(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_delete_statement_without_where_clause() {
+        let (_, statement) = DeleteStatement::parse_from_raw("DELETE FROM Person;").unwrap();
+
+        assert_eq!(statement.table, "person".into());
+        assert_eq!(statement.where_clause, None);
+    }
+}
@@ -0,0 +1,75 @@
+use nom::{
+    bytes::complete::tag_no_case,
+    combinator::map,
+    sequence::{preceded, tuple},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common_parsers::{if_exists, spanned, table_names, whitespace1},
+    ident::ObjectName,
+    types::{Parse, ParserResult, RawSpan, Span},
+};
+
+/// The tables to remove, along with their rows
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct DropStatement {
+    pub tables: Vec<ObjectName>,
+    /// Tables that don't exist are skipped instead of failing the statement
+    pub if_exists: bool,
+    #[serde(skip)]
+    pub span: Span,
+}
+
+impl<'a> Parse<'a> for DropStatement {
+    fn parse(input: RawSpan<'a>) -> ParserResult<'a, Self> {
+        let drop_table = tuple((
+            tag_no_case("drop"),
+            whitespace1,
+            tag_no_case("table"),
+            whitespace1,
+        ));
+        map(
+            spanned(preceded(drop_table, tuple((if_exists, table_names)))),
+            |((if_exists, tables), span)| Self {
+                tables,
+                if_exists,
+                span,
+            },
+        )(input)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_drop_statement() {
+        let (_, statement) = DropStatement::parse_from_raw("DROP TABLE Person;").unwrap();
+
+        assert_eq!(
+            statement,
+            DropStatement {
+                tables: vec!["person".into()],
+                if_exists: false,
+                span: Span::default(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_drop_statement_with_several_tables_if_they_exist() {
+        let (_, statement) =
+            DropStatement::parse_from_raw("drop table if exists Person, City").unwrap();
+
+        assert_eq!(
+            statement,
+            DropStatement {
+                tables: vec!["person".into(), "city".into()],
+                if_exists: true,
+                span: Span::default(),
+            }
+        );
+    }
+}
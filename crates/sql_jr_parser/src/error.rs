@@ -2,7 +2,7 @@ use miette::{Diagnostic, SourceSpan};
 use nom_supreme::error::{BaseErrorKind, GenericErrorTree, StackContext};
 use thiserror::Error;
 
-use crate::types::MyParseError;
+use crate::types::{MyParseError, RawSpan};
 
 #[derive(Debug, Error, Diagnostic)]
 #[error("parse error")]
@@ -11,10 +11,10 @@ pub struct FormattedError<'b> {
     #[source_code]
     src: &'b str,
 
-    #[label("{kind}")]
+    #[label("{label}")]
     span: SourceSpan,
 
-    kind: BaseErrorKind<&'b str, Box<dyn std::error::Error + Send + Sync + 'static>>,
+    label: String,
 
     #[related]
     others: Vec<FormattedErrorContext<'b>>,
@@ -32,6 +32,29 @@ pub struct FormattedErrorContext<'b> {
     context: StackContext<&'b str>,
 }
 
+/// An error found in input that was otherwise well formed, e.g. a VALUES row with the
+/// wrong number of values. Unlike nom's errors it covers a whole region of the input
+#[derive(Debug, Error)]
+#[error("{message}")]
+pub(crate) struct SpannedParseError {
+    message: String,
+    length: usize,
+}
+
+/// Fail the parse with `message`, labelling all of `source`
+pub(crate) fn spanned_failure<'a>(
+    source: RawSpan<'a>,
+    message: String,
+) -> nom::Err<MyParseError<'a>> {
+    nom::Err::Failure(GenericErrorTree::Base {
+        location: source,
+        kind: BaseErrorKind::External(Box::new(SpannedParseError {
+            message,
+            length: source.fragment().len(),
+        })),
+    })
+}
+
 pub fn format_parse_error<'a>(input: &'a str, e: MyParseError<'a>) -> FormattedError<'a> {
     match e {
         // a "normal" error like unexpected charcter
@@ -40,10 +63,18 @@ pub fn format_parse_error<'a>(input: &'a str, e: MyParseError<'a>) -> FormattedE
             // Might be nice to just use our own span/make a wrapper to implement
             // From<OurSpan> for miette::SourceSpan
             let offset = location.location_offset().into();
+            let spanned = match &kind {
+                BaseErrorKind::External(e) => e.downcast_ref::<SpannedParseError>(),
+                _ => None,
+            };
+            let (length, label) = match spanned {
+                Some(spanned) => (spanned.length, spanned.message.clone()),
+                None => (0, kind.to_string()),
+            };
             FormattedError {
                 src: input,
-                span: miette::SourceSpan::new(offset, 0.into()),
-                kind,
+                span: miette::SourceSpan::new(offset, length.into()),
+                label,
                 others: Vec::new(),
             }
         }
@@ -0,0 +1,1162 @@
+use std::fmt::{self, Display, Formatter};
+
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::char,
+    combinator::{cut, flat_map, map, not, opt, verify},
+    error::context,
+    multi::{many0, many1, separated_list1},
+    sequence::{delimited, pair, preceded, terminated, tuple},
+};
+use nom_supreme::ParserExt;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common_parsers::{identifier, keyword, parse_literal, spanned, whitespace0, whitespace1},
+    ident::{Ident, ObjectName},
+    query_expression::{parse_subquery, QueryExpression},
+    types::{Parse, ParserResult, RawSpan, Span},
+    value::Literal,
+    window::{parse_window_call, WindowFunction, WindowSpec},
+};
+
+/// An operator taking a single operand
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum UnaryOperator {
+    Not,
+    Minus,
+}
+
+/// An operator taking a left and a right operand
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum BinaryOperator {
+    Or,
+    And,
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    Plus,
+    Minus,
+    Multiply,
+    Divide,
+}
+
+impl Display for UnaryOperator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Not => write!(f, "NOT"),
+            Self::Minus => write!(f, "-"),
+        }
+    }
+}
+
+impl Display for BinaryOperator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Self::Or => "OR",
+            Self::And => "AND",
+            Self::Eq => "=",
+            Self::NotEq => "<>",
+            Self::Lt => "<",
+            Self::LtEq => "<=",
+            Self::Gt => ">",
+            Self::GtEq => ">=",
+            Self::Plus => "+",
+            Self::Minus => "-",
+            Self::Multiply => "*",
+            Self::Divide => "/",
+        };
+        write!(f, "{symbol}")
+    }
+}
+
+/// A function computing a single value from the rows of a group
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum AggregateFunction {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+impl Display for AggregateFunction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Count => "COUNT",
+            Self::Sum => "SUM",
+            Self::Avg => "AVG",
+            Self::Min => "MIN",
+            Self::Max => "MAX",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// What an aggregate function is computed over
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum AggregateArg {
+    /// `*` in `COUNT(*)`, every row of the group
+    Wildcard,
+    /// The value of an expression for each row, NULLs are skipped
+    Expression(Box<Expression>),
+}
+
+/// A function choosing between the values of its arguments
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum ConditionalFunction {
+    /// The first argument that isn't NULL
+    Coalesce,
+    /// NULL when its two arguments are equal, else the first one
+    NullIf,
+    /// The largest argument, NULLs are skipped
+    Greatest,
+    /// The smallest argument, NULLs are skipped
+    Least,
+}
+
+impl Display for ConditionalFunction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Coalesce => "COALESCE",
+            Self::NullIf => "NULLIF",
+            Self::Greatest => "GREATEST",
+            Self::Least => "LEAST",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// One `WHEN condition THEN result` of a CASE
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct WhenClause {
+    /// A predicate, or the value the CASE operand is compared to when it has one
+    pub condition: Expression,
+    pub result: Expression,
+}
+
+/// A column, optionally qualified by its table as in `Person.name` or
+/// `public.person.name`
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct ColumnRef {
+    pub table: Option<ObjectName>,
+    pub column: Ident,
+}
+
+impl ColumnRef {
+    /// Where the column was written in the query, including its table
+    pub fn span(&self) -> Span {
+        match &self.table {
+            Some(table) => table.span().union(self.column.span),
+            None => self.column.span,
+        }
+    }
+}
+
+impl Display for ColumnRef {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.table {
+            Some(table) => write!(f, "{table}.{}", self.column),
+            None => write!(f, "{}", self.column),
+        }
+    }
+}
+
+impl From<&str> for ColumnRef {
+    fn from(column: &str) -> Self {
+        Self {
+            table: None,
+            column: column.into(),
+        }
+    }
+}
+
+// parses `column`, or `table.column` where the table name may be qualified too
+fn column_ref(input: RawSpan<'_>) -> ParserResult<'_, ColumnRef> {
+    map(
+        separated_list1(char('.'), identifier.context("Column Name")),
+        |mut parts| {
+            let column = parts
+                .pop()
+                .expect("a column reference has at least one part");
+            ColumnRef {
+                table: (!parts.is_empty()).then_some(ObjectName(parts)),
+                column,
+            }
+        },
+    )(input)
+}
+
+/// A boolean or arithmetic expression, e.g. the predicate of a WHERE clause
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum Expression {
+    Column(ColumnRef),
+    Literal(Literal),
+    Unary {
+        op: UnaryOperator,
+        expr: Box<Expression>,
+        #[serde(skip)]
+        span: Span,
+    },
+    Binary {
+        left: Box<Expression>,
+        op: BinaryOperator,
+        right: Box<Expression>,
+        #[serde(skip)]
+        span: Span,
+    },
+    /// An aggregate call such as `COUNT(*)` or `SUM(DISTINCT age)`
+    Aggregate {
+        function: AggregateFunction,
+        /// Only distinct values of the argument are aggregated
+        distinct: bool,
+        arg: AggregateArg,
+        #[serde(skip)]
+        span: Span,
+    },
+    /// `(SELECT ...)` used as a value, the single column of its single row or NULL
+    /// when it returns no row
+    Subquery {
+        subquery: QueryExpression,
+        #[serde(skip)]
+        span: Span,
+    },
+    /// `EXISTS (SELECT ...)`, whether the subquery returns any row
+    Exists {
+        subquery: QueryExpression,
+        #[serde(skip)]
+        span: Span,
+    },
+    /// `expr [NOT] IN (SELECT ...)`, whether the value is one of the rows of the subquery
+    InSubquery {
+        expr: Box<Expression>,
+        subquery: QueryExpression,
+        negated: bool,
+        #[serde(skip)]
+        span: Span,
+    },
+    /// `expr IS [NOT] NULL`, whether the value is NULL, which is never NULL itself
+    IsNull {
+        expr: Box<Expression>,
+        negated: bool,
+        #[serde(skip)]
+        span: Span,
+    },
+    /// `CASE [operand] WHEN condition THEN result ... [ELSE result] END`, the result
+    /// of the first condition that holds or is equal to the operand, else the ELSE
+    /// result or NULL
+    Case {
+        operand: Option<Box<Expression>>,
+        when_clauses: Vec<WhenClause>,
+        else_result: Option<Box<Expression>>,
+        #[serde(skip)]
+        span: Span,
+    },
+    /// A call of COALESCE, NULLIF, GREATEST or LEAST
+    Conditional {
+        function: ConditionalFunction,
+        args: Vec<Expression>,
+        #[serde(skip)]
+        span: Span,
+    },
+    /// A window function call such as `RANK() OVER (ORDER BY age)`, computed from the
+    /// rows of the row's window
+    Window {
+        function: WindowFunction,
+        window: WindowSpec,
+        #[serde(skip)]
+        span: Span,
+    },
+}
+
+impl Expression {
+    // `op_span` is where the operator was written, the expression spans it and its operand
+    fn unary(op: UnaryOperator, op_span: Span, expr: Expression) -> Self {
+        Self::Unary {
+            op,
+            span: op_span.union(expr.span()),
+            expr: Box::new(expr),
+        }
+    }
+
+    fn binary(left: Expression, op: BinaryOperator, right: Expression) -> Self {
+        Self::Binary {
+            span: left.span().union(right.span()),
+            left: Box::new(left),
+            op,
+            right: Box::new(right),
+        }
+    }
+
+    /// Where the expression was written in the query
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Column(column) => column.span(),
+            Self::Literal(literal) => literal.span,
+            Self::Unary { span, .. }
+            | Self::Binary { span, .. }
+            | Self::Aggregate { span, .. }
+            | Self::Subquery { span, .. }
+            | Self::Exists { span, .. }
+            | Self::InSubquery { span, .. }
+            | Self::IsNull { span, .. }
+            | Self::Case { span, .. }
+            | Self::Conditional { span, .. }
+            | Self::Window { span, .. } => *span,
+        }
+    }
+
+    /// The operands of a CASE or a conditional function call in the order they are
+    /// written, none for the other expressions
+    pub fn conditional_operands(&self) -> Vec<&Expression> {
+        match self {
+            Self::Case {
+                operand,
+                when_clauses,
+                else_result,
+                ..
+            } => operand
+                .iter()
+                .map(|operand| &**operand)
+                .chain(
+                    when_clauses
+                        .iter()
+                        .flat_map(|clause| [&clause.condition, &clause.result]),
+                )
+                .chain(else_result.as_deref())
+                .collect(),
+            Self::Conditional { args, .. } => args.iter().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The expressions the value of a CASE or a conditional function call is one of
+    pub fn conditional_results(&self) -> Vec<&Expression> {
+        match self {
+            Self::Case {
+                when_clauses,
+                else_result,
+                ..
+            } => when_clauses
+                .iter()
+                .map(|clause| &clause.result)
+                .chain(else_result.as_deref())
+                .collect(),
+            Self::Conditional {
+                function: ConditionalFunction::NullIf,
+                args,
+                ..
+            } => args.iter().take(1).collect(),
+            Self::Conditional { args, .. } => args.iter().collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+type ExpressionParser<'a> = fn(RawSpan<'a>) -> ParserResult<'a, Expression>;
+
+// parses `operand (operator operand)*` and folds the operands to the left,
+// so `a - b - c` becomes `(a - b) - c`
+fn left_associative<'a>(
+    input: RawSpan<'a>,
+    operand: ExpressionParser<'a>,
+    operator: impl FnMut(RawSpan<'a>) -> ParserResult<'a, BinaryOperator>,
+) -> ParserResult<'a, Expression> {
+    let (rest, (first, others)) = pair(
+        operand,
+        many0(pair(
+            preceded(whitespace0, operator),
+            preceded(whitespace0, operand),
+        )),
+    )(input)?;
+
+    let expression = others.into_iter().fold(first, |left, (op, right)| {
+        Expression::binary(left, op, right)
+    });
+    Ok((rest, expression))
+}
+
+fn aggregate_function(input: RawSpan<'_>) -> ParserResult<'_, AggregateFunction> {
+    alt((
+        map(keyword("count"), |_| AggregateFunction::Count),
+        map(keyword("sum"), |_| AggregateFunction::Sum),
+        map(keyword("avg"), |_| AggregateFunction::Avg),
+        map(keyword("min"), |_| AggregateFunction::Min),
+        map(keyword("max"), |_| AggregateFunction::Max),
+    ))(input)
+}
+
+// the argument of `function` and whether it is DISTINCT, only COUNT takes `*`
+fn aggregate_arg<'a>(
+    function: AggregateFunction,
+) -> impl FnMut(RawSpan<'a>) -> ParserResult<'a, (bool, AggregateArg)> {
+    alt((
+        map(
+            verify(char('*'), move |_| function == AggregateFunction::Count),
+            |_| (false, AggregateArg::Wildcard),
+        ),
+        map(
+            pair(
+                opt(terminated(keyword("distinct"), whitespace1)),
+                Expression::parse,
+            ),
+            |(distinct, expr)| (distinct.is_some(), AggregateArg::Expression(Box::new(expr))),
+        ),
+    ))
+}
+
+// `COUNT(*)`, `SUM(age)` or `COUNT(DISTINCT city)`
+pub(crate) fn parse_aggregate(input: RawSpan<'_>) -> ParserResult<'_, Expression> {
+    map(
+        spanned(flat_map(
+            terminated(aggregate_function, pair(whitespace0, char('('))),
+            |function| {
+                // `count(` can't start a column, so a bad argument fails the parse
+                map(
+                    cut(delimited(
+                        whitespace0,
+                        aggregate_arg(function),
+                        pair(whitespace0, char(')')),
+                    ))
+                    .context("Aggregate Argument"),
+                    move |(distinct, arg)| (function, distinct, arg),
+                )
+            },
+        )),
+        |((function, distinct, arg), span)| Expression::Aggregate {
+            function,
+            distinct,
+            arg,
+            span,
+        },
+    )(input)
+}
+
+// `CASE [operand] WHEN condition THEN result ... [ELSE result] END`
+fn parse_case(input: RawSpan<'_>) -> ParserResult<'_, Expression> {
+    let when_clause = map(
+        tuple((
+            preceded(pair(keyword("when"), whitespace0), Expression::parse),
+            preceded(
+                tuple((whitespace0, keyword("then"), whitespace0)),
+                Expression::parse,
+            ),
+        )),
+        |(condition, result)| WhenClause { condition, result },
+    );
+    map(
+        spanned(preceded(
+            keyword("case"),
+            // there is no other use of CASE
+            cut(tuple((
+                // a searched CASE has no operand, its conditions are predicates
+                opt(preceded(
+                    pair(whitespace0, not(keyword("when"))),
+                    Expression::parse,
+                )),
+                many1(preceded(whitespace0, when_clause)),
+                opt(preceded(
+                    tuple((whitespace0, keyword("else"), whitespace0)),
+                    Expression::parse,
+                )),
+                preceded(whitespace0, keyword("end")),
+            )))
+            .context("Case"),
+        )),
+        |((operand, when_clauses, else_result, _), span)| Expression::Case {
+            operand: operand.map(Box::new),
+            when_clauses,
+            else_result: else_result.map(Box::new),
+            span,
+        },
+    )(input)
+}
+
+fn conditional_function(input: RawSpan<'_>) -> ParserResult<'_, ConditionalFunction> {
+    alt((
+        map(keyword("coalesce"), |_| ConditionalFunction::Coalesce),
+        map(keyword("nullif"), |_| ConditionalFunction::NullIf),
+        map(keyword("greatest"), |_| ConditionalFunction::Greatest),
+        map(keyword("least"), |_| ConditionalFunction::Least),
+    ))(input)
+}
+
+// `COALESCE(a, b, ...)`, NULLIF takes exactly two arguments
+fn parse_conditional(input: RawSpan<'_>) -> ParserResult<'_, Expression> {
+    map(
+        spanned(flat_map(
+            terminated(conditional_function, pair(whitespace0, char('('))),
+            |function| {
+                // a NULLIF of one or three arguments is an error, not an expression
+                // to parse some other way
+                map(
+                    cut(verify(
+                        delimited(
+                            whitespace0,
+                            separated_list1(
+                                tuple((whitespace0, char(','), whitespace0)),
+                                Expression::parse,
+                            ),
+                            pair(whitespace0, char(')')),
+                        ),
+                        move |args: &Vec<Expression>| {
+                            function != ConditionalFunction::NullIf || args.len() == 2
+                        },
+                    ))
+                    .context("Function Arguments"),
+                    move |args| (function, args),
+                )
+            },
+        )),
+        |((function, args), span)| Expression::Conditional {
+            function,
+            args,
+            span,
+        },
+    )(input)
+}
+
+// `EXISTS (SELECT ...)`
+fn parse_exists(input: RawSpan<'_>) -> ParserResult<'_, Expression> {
+    map(
+        spanned(preceded(
+            pair(keyword("exists"), whitespace0),
+            parse_subquery,
+        )),
+        |(subquery, span)| Expression::Exists { subquery, span },
+    )(input)
+}
+
+// a literal, a CASE, a function call, a subquery, a column or a parenthesised
+// expression
+fn parse_primary(input: RawSpan<'_>) -> ParserResult<'_, Expression> {
+    alt((
+        map(spanned(parse_subquery), |(subquery, span)| {
+            Expression::Subquery { subquery, span }
+        }),
+        parse_exists,
+        delimited(
+            pair(char('('), whitespace0),
+            Expression::parse,
+            pair(whitespace0, char(')')),
+        ),
+        map(parse_literal, Expression::Literal),
+        parse_case,
+        parse_conditional,
+        parse_window_call,
+        parse_aggregate,
+        map(column_ref, Expression::Column),
+    ))(input)
+}
+
+fn parse_negation(input: RawSpan<'_>) -> ParserResult<'_, Expression> {
+    alt((
+        map(
+            pair(spanned(char('-')), preceded(whitespace0, parse_negation)),
+            |((_, span), expr)| Expression::unary(UnaryOperator::Minus, span, expr),
+        ),
+        parse_primary,
+    ))(input)
+}
+
+fn parse_multiplicative(input: RawSpan<'_>) -> ParserResult<'_, Expression> {
+    left_associative(
+        input,
+        parse_negation,
+        alt((
+            map(char('*'), |_| BinaryOperator::Multiply),
+            map(char('/'), |_| BinaryOperator::Divide),
+        )),
+    )
+}
+
+fn parse_additive(input: RawSpan<'_>) -> ParserResult<'_, Expression> {
+    left_associative(
+        input,
+        parse_multiplicative,
+        alt((
+            map(char('+'), |_| BinaryOperator::Plus),
+            map(char('-'), |_| BinaryOperator::Minus),
+        )),
+    )
+}
+
+fn comparison_operator(input: RawSpan<'_>) -> ParserResult<'_, BinaryOperator> {
+    // the two character operators have to be tried before their one character prefixes
+    alt((
+        map(tag("<="), |_| BinaryOperator::LtEq),
+        map(tag(">="), |_| BinaryOperator::GtEq),
+        map(tag("<>"), |_| BinaryOperator::NotEq),
+        map(tag("!="), |_| BinaryOperator::NotEq),
+        map(char('='), |_| BinaryOperator::Eq),
+        map(char('<'), |_| BinaryOperator::Lt),
+        map(char('>'), |_| BinaryOperator::Gt),
+    ))(input)
+}
+
+// what follows the left operand of a comparison
+enum Comparison {
+    Operator(BinaryOperator, Expression),
+    In {
+        negated: bool,
+        subquery: QueryExpression,
+        span: Span,
+    },
+    IsNull {
+        negated: bool,
+        span: Span,
+    },
+}
+
+// `[NOT] IN (SELECT ...)`
+fn in_subquery(input: RawSpan<'_>) -> ParserResult<'_, Comparison> {
+    map(
+        pair(
+            opt(terminated(keyword("not"), whitespace1)),
+            preceded(pair(keyword("in"), whitespace0), spanned(parse_subquery)),
+        ),
+        |(not, (subquery, span))| Comparison::In {
+            negated: not.is_some(),
+            subquery,
+            span,
+        },
+    )(input)
+}
+
+// `IS [NOT] NULL`
+fn is_null(input: RawSpan<'_>) -> ParserResult<'_, Comparison> {
+    map(
+        spanned(preceded(
+            pair(keyword("is"), whitespace1),
+            terminated(
+                opt(terminated(keyword("not"), whitespace1)),
+                keyword("null"),
+            ),
+        )),
+        |(not, span)| Comparison::IsNull {
+            negated: not.is_some(),
+            span,
+        },
+    )(input)
+}
+
+// comparisons don't chain, `a < b < c` is not a valid expression
+fn parse_comparison(input: RawSpan<'_>) -> ParserResult<'_, Expression> {
+    map(
+        pair(
+            parse_additive,
+            opt(preceded(
+                whitespace0,
+                alt((
+                    map(
+                        pair(comparison_operator, preceded(whitespace0, parse_additive)),
+                        |(op, right)| Comparison::Operator(op, right),
+                    ),
+                    in_subquery,
+                    is_null,
+                )),
+            )),
+        ),
+        |(left, comparison)| match comparison {
+            Some(Comparison::Operator(op, right)) => Expression::binary(left, op, right),
+            Some(Comparison::In {
+                negated,
+                subquery,
+                span,
+            }) => Expression::InSubquery {
+                span: left.span().union(span),
+                expr: Box::new(left),
+                subquery,
+                negated,
+            },
+            Some(Comparison::IsNull { negated, span }) => Expression::IsNull {
+                span: left.span().union(span),
+                expr: Box::new(left),
+                negated,
+            },
+            None => left,
+        },
+    )(input)
+}
+
+fn parse_not(input: RawSpan<'_>) -> ParserResult<'_, Expression> {
+    alt((
+        map(
+            pair(spanned(keyword("not")), preceded(whitespace0, parse_not)),
+            |((_, span), expr)| Expression::unary(UnaryOperator::Not, span, expr),
+        ),
+        parse_comparison,
+    ))(input)
+}
+
+fn parse_and(input: RawSpan<'_>) -> ParserResult<'_, Expression> {
+    left_associative(
+        input,
+        parse_not,
+        map(keyword("and"), |_| BinaryOperator::And),
+    )
+}
+
+fn parse_or(input: RawSpan<'_>) -> ParserResult<'_, Expression> {
+    left_associative(input, parse_and, map(keyword("or"), |_| BinaryOperator::Or))
+}
+
+// precedence from loosest to tightest binding:
+// OR, AND, NOT, comparisons, + and -, * and /, unary -
+impl<'a> Parse<'a> for Expression {
+    fn parse(input: RawSpan<'a>) -> ParserResult<'a, Self> {
+        context("Expression", parse_or)(input)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nom_locate::LocatedSpan;
+
+    use super::*;
+    use crate::value::Value;
+
+    fn column(name: &str) -> Expression {
+        Expression::Column(name.into())
+    }
+
+    fn int(value: i64) -> Expression {
+        Expression::Literal(Value::Int(value).into())
+    }
+
+    #[test]
+    fn test_parse_column_expression() {
+        let (_, expression) = Expression::parse_from_raw("age").unwrap();
+
+        assert_eq!(expression, column("age"));
+    }
+
+    #[test]
+    fn test_parse_aggregates() {
+        let aggregate = |raw| Expression::parse_from_raw(raw).unwrap().1;
+
+        assert_eq!(
+            aggregate("count( * )"),
+            Expression::Aggregate {
+                function: AggregateFunction::Count,
+                distinct: false,
+                arg: AggregateArg::Wildcard,
+                span: Span::default(),
+            }
+        );
+        assert_eq!(
+            aggregate("COUNT(DISTINCT city)"),
+            Expression::Aggregate {
+                function: AggregateFunction::Count,
+                distinct: true,
+                arg: AggregateArg::Expression(Box::new(column("city"))),
+                span: Span::default(),
+            }
+        );
+        assert_eq!(
+            aggregate("AVG(age) + 1"),
+            Expression::binary(
+                Expression::Aggregate {
+                    function: AggregateFunction::Avg,
+                    distinct: false,
+                    arg: AggregateArg::Expression(Box::new(column("age"))),
+                    span: Span::default(),
+                },
+                BinaryOperator::Plus,
+                int(1)
+            )
+        );
+    }
+
+    #[test]
+    fn test_aggregate_names_are_columns_without_parentheses() {
+        let (_, expression) = Expression::parse_from_raw("count").unwrap();
+
+        assert_eq!(expression, column("count"));
+    }
+
+    #[test]
+    fn test_invalid_aggregate_argument_is_an_error() {
+        assert!(matches!(
+            Expression::parse_from_raw("SUM(*)"),
+            Err(nom::Err::Failure(_))
+        ));
+        assert!(matches!(
+            Expression::parse_from_raw("MAX(age"),
+            Err(nom::Err::Failure(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_case() {
+        let (_, searched) =
+            Expression::parse_from_raw("CASE WHEN age < 18 THEN 'minor' ELSE 'adult' END").unwrap();
+        let (_, simple) =
+            Expression::parse_from_raw("case(age)when 1 then 2 when 3 then 4 end").unwrap();
+
+        assert_eq!(
+            searched,
+            Expression::Case {
+                operand: None,
+                when_clauses: vec![WhenClause {
+                    condition: Expression::binary(column("age"), BinaryOperator::Lt, int(18)),
+                    result: Expression::Literal(Value::String("minor".to_string()).into()),
+                }],
+                else_result: Some(Box::new(Expression::Literal(
+                    Value::String("adult".to_string()).into()
+                ))),
+                span: Span::default(),
+            }
+        );
+        assert_eq!(
+            simple,
+            Expression::Case {
+                operand: Some(Box::new(column("age"))),
+                when_clauses: vec![
+                    WhenClause {
+                        condition: int(1),
+                        result: int(2),
+                    },
+                    WhenClause {
+                        condition: int(3),
+                        result: int(4),
+                    },
+                ],
+                else_result: None,
+                span: Span::default(),
+            }
+        );
+        for raw in ["CASE END", "CASE WHEN x THEN 1", "CASE x ELSE 1 END"] {
+            assert!(
+                matches!(Expression::parse_from_raw(raw), Err(nom::Err::Failure(_))),
+                "{raw}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_conditional_functions() {
+        let (_, expression) = Expression::parse_from_raw("COALESCE(a, NULLIF(b, 0), 1)").unwrap();
+
+        assert_eq!(
+            expression,
+            Expression::Conditional {
+                function: ConditionalFunction::Coalesce,
+                args: vec![
+                    column("a"),
+                    Expression::Conditional {
+                        function: ConditionalFunction::NullIf,
+                        args: vec![column("b"), int(0)],
+                        span: Span::default(),
+                    },
+                    int(1),
+                ],
+                span: Span::default(),
+            }
+        );
+        assert_eq!(
+            Expression::parse_from_raw("least").unwrap().1,
+            column("least")
+        );
+        assert!(matches!(
+            Expression::parse_from_raw("NULLIF(a)"),
+            Err(nom::Err::Failure(_))
+        ));
+        assert!(matches!(
+            Expression::parse_from_raw("GREATEST()"),
+            Err(nom::Err::Failure(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_qualified_column_expression() {
+        let (_, expression) = Expression::parse_from_raw("Person.age").unwrap();
+
+        assert_eq!(
+            expression,
+            Expression::Column(ColumnRef {
+                table: Some("person".into()),
+                column: "age".into()
+            })
+        );
+        assert_eq!(expression.span().length, "Person.age".len());
+    }
+
+    #[test]
+    fn test_parse_column_of_qualified_table() {
+        let (_, expression) = Expression::parse_from_raw("public.person.age").unwrap();
+
+        assert_eq!(
+            expression,
+            Expression::Column(ColumnRef {
+                table: Some(ObjectName(vec!["public".into(), "person".into()])),
+                column: "age".into()
+            })
+        );
+        assert_eq!(expression.span().length, "public.person.age".len());
+        if let Expression::Column(column) = expression {
+            assert_eq!(column.to_string(), "public.person.age");
+        }
+    }
+
+    #[test]
+    fn test_parse_quoted_and_underscored_columns() {
+        let (_, expression) =
+            Expression::parse_from_raw("\"Order Details\".first_name = \"Last Name\"").unwrap();
+
+        assert_eq!(
+            expression,
+            Expression::Binary {
+                left: Box::new(Expression::Column(ColumnRef {
+                    table: Some("Order Details".into()),
+                    column: "first_name".into()
+                })),
+                op: BinaryOperator::Eq,
+                right: Box::new(Expression::Column("Last Name".into())),
+                span: Span::default(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_literal_expressions() {
+        let (_, number) = Expression::parse_from_raw("42").unwrap();
+        let (_, string) = Expression::parse_from_raw("'Oslo'").unwrap();
+
+        assert_eq!(number, int(42));
+        assert_eq!(
+            string,
+            Expression::Literal(Value::String("Oslo".to_string()).into())
+        );
+    }
+
+    #[test]
+    fn test_parse_comparison_operators() {
+        let operators = [
+            ("=", BinaryOperator::Eq),
+            ("<>", BinaryOperator::NotEq),
+            ("!=", BinaryOperator::NotEq),
+            ("<", BinaryOperator::Lt),
+            ("<=", BinaryOperator::LtEq),
+            (">", BinaryOperator::Gt),
+            (">=", BinaryOperator::GtEq),
+        ];
+
+        for (raw, op) in operators {
+            let input = format!("age {raw} 18");
+            let (_, expression) = Expression::parse_from_raw(&input).unwrap();
+
+            assert_eq!(expression, Expression::binary(column("age"), op, int(18)));
+        }
+    }
+
+    #[test]
+    fn test_parse_is_null() {
+        let (_, expression) =
+            Expression::parse_from_raw("a + 1 IS NULL OR NOT b is not null").unwrap();
+
+        assert_eq!(
+            expression,
+            Expression::binary(
+                Expression::IsNull {
+                    expr: Box::new(Expression::binary(
+                        column("a"),
+                        BinaryOperator::Plus,
+                        int(1)
+                    )),
+                    negated: false,
+                    span: Span::default(),
+                },
+                BinaryOperator::Or,
+                Expression::unary(
+                    UnaryOperator::Not,
+                    Span::default(),
+                    Expression::IsNull {
+                        expr: Box::new(column("b")),
+                        negated: true,
+                        span: Span::default(),
+                    }
+                )
+            )
+        );
+    }
+
+    #[test]
+    fn test_multiplication_binds_tighter_than_addition() {
+        let (_, expression) = Expression::parse_from_raw("a + b * 2").unwrap();
+
+        assert_eq!(
+            expression,
+            Expression::binary(
+                column("a"),
+                BinaryOperator::Plus,
+                Expression::binary(column("b"), BinaryOperator::Multiply, int(2))
+            )
+        );
+    }
+
+    #[test]
+    fn test_arithmetic_is_left_associative() {
+        let (_, expression) = Expression::parse_from_raw("a - b - c").unwrap();
+
+        assert_eq!(
+            expression,
+            Expression::binary(
+                Expression::binary(column("a"), BinaryOperator::Minus, column("b")),
+                BinaryOperator::Minus,
+                column("c")
+            )
+        );
+    }
+
+    #[test]
+    fn test_parentheses_override_precedence() {
+        let (_, expression) = Expression::parse_from_raw("(a + b) * 2").unwrap();
+
+        assert_eq!(
+            expression,
+            Expression::binary(
+                Expression::binary(column("a"), BinaryOperator::Plus, column("b")),
+                BinaryOperator::Multiply,
+                int(2)
+            )
+        );
+    }
+
+    #[test]
+    fn test_and_binds_tighter_than_or() {
+        let (_, expression) = Expression::parse_from_raw("a = 1 OR b = 2 AND c = 3").unwrap();
+
+        assert_eq!(
+            expression,
+            Expression::binary(
+                Expression::binary(column("a"), BinaryOperator::Eq, int(1)),
+                BinaryOperator::Or,
+                Expression::binary(
+                    Expression::binary(column("b"), BinaryOperator::Eq, int(2)),
+                    BinaryOperator::And,
+                    Expression::binary(column("c"), BinaryOperator::Eq, int(3))
+                )
+            )
+        );
+    }
+
+    #[test]
+    fn test_not_binds_tighter_than_and() {
+        let (_, expression) = Expression::parse_from_raw("NOT a > 1 and b").unwrap();
+
+        assert_eq!(
+            expression,
+            Expression::binary(
+                Expression::unary(
+                    UnaryOperator::Not,
+                    Span::default(),
+                    Expression::binary(column("a"), BinaryOperator::Gt, int(1))
+                ),
+                BinaryOperator::And,
+                column("b")
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_unary_minus() {
+        let (_, expression) = Expression::parse_from_raw("-a * 2").unwrap();
+
+        assert_eq!(
+            expression,
+            Expression::binary(
+                Expression::unary(UnaryOperator::Minus, Span::default(), column("a")),
+                BinaryOperator::Multiply,
+                int(2)
+            )
+        );
+    }
+
+    #[test]
+    fn test_keyword_prefixed_column_is_not_an_operator() {
+        let (remaining, expression) =
+            Expression::parse(LocatedSpan::new("a = 1 ORDER BY a")).unwrap();
+
+        assert_eq!(
+            expression,
+            Expression::binary(column("a"), BinaryOperator::Eq, int(1))
+        );
+        assert_eq!(*remaining.fragment(), " ORDER BY a");
+    }
+
+    fn select(raw: &str) -> QueryExpression {
+        QueryExpression::parse_from_raw(raw).unwrap().1
+    }
+
+    #[test]
+    fn test_parse_subqueries() {
+        let (_, expression) = Expression::parse_from_raw(
+            "id NOT IN (SELECT id FROM t) AND NOT EXISTS (SELECT * FROM u) OR (SELECT MAX(x) FROM t) > 1",
+        )
+        .unwrap();
+
+        assert_eq!(
+            expression,
+            Expression::binary(
+                Expression::binary(
+                    Expression::InSubquery {
+                        expr: Box::new(column("id")),
+                        subquery: select("SELECT id FROM t"),
+                        negated: true,
+                        span: Span::default(),
+                    },
+                    BinaryOperator::And,
+                    Expression::unary(
+                        UnaryOperator::Not,
+                        Span::default(),
+                        Expression::Exists {
+                            subquery: select("SELECT * FROM u"),
+                            span: Span::default(),
+                        }
+                    )
+                ),
+                BinaryOperator::Or,
+                Expression::binary(
+                    Expression::Subquery {
+                        subquery: select("SELECT MAX(x) FROM t"),
+                        span: Span::default(),
+                    },
+                    BinaryOperator::Gt,
+                    int(1)
+                )
+            )
+        );
+    }
+
+    #[test]
+    fn test_broken_subquery_is_an_error() {
+        assert!(matches!(
+            Expression::parse_from_raw("a IN (SELECT FROM t)"),
+            Err(nom::Err::Failure(_))
+        ));
+    }
+
+    #[test]
+    fn test_unclosed_parenthesis_is_an_error() {
+        assert!(Expression::parse_format_error("(a + 1").is_err());
+    }
+
+    #[test]
+    fn test_expression_spans() {
+        let raw = "NOT a > 1 AND b * 10 = 20";
+        let (_, expression) = Expression::parse_from_raw(raw).unwrap();
+        let Expression::Binary { left, right, .. } = &expression else {
+            panic!("expected a binary expression");
+        };
+        let text = |span: Span| &raw[span.offset..span.offset + span.length];
+
+        assert_eq!(text(expression.span()), raw);
+        assert_eq!(text(left.span()), "NOT a > 1");
+        assert_eq!(text(right.span()), "b * 10 = 20");
+    }
+}
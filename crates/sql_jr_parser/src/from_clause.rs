@@ -0,0 +1,437 @@
+use nom::{
+    branch::alt,
+    character::complete::char,
+    combinator::{cut, map, opt},
+    multi::{many0, separated_list1},
+    sequence::{delimited, pair, preceded, terminated, tuple},
+};
+use nom_supreme::ParserExt;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common_parsers::{alias, identifier, keyword, object_name, whitespace0, whitespace1},
+    expression::Expression,
+    ident::{Ident, ObjectName},
+    query_expression::{parse_subquery, QueryExpression},
+    types::{Parse, ParserResult, RawSpan},
+};
+
+/// A table named in a FROM clause, optionally renamed for the rest of the query
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct TableReference {
+    pub name: ObjectName,
+    pub alias: Option<Ident>,
+}
+
+impl TableReference {
+    /// The name the query refers to the table by: its alias when it has one, else the
+    /// last part of its name
+    pub fn reference_name(&self) -> &Ident {
+        self.alias.as_ref().unwrap_or(self.name.base())
+    }
+
+    /// The name that qualifies the table's columns: its alias when it has one, else its
+    /// whole name, whose last parts may be left out
+    pub fn qualifier(&self) -> ObjectName {
+        match &self.alias {
+            Some(alias) => alias.clone().into(),
+            None => self.name.clone(),
+        }
+    }
+}
+
+impl From<&str> for TableReference {
+    fn from(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            alias: None,
+        }
+    }
+}
+
+/// Which rows of a join are kept
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum JoinKind {
+    /// Only the pairs of rows matching the constraint
+    Inner,
+    /// The matching pairs and the left rows matching nothing
+    Left,
+    /// The matching pairs and the right rows matching nothing
+    Right,
+    /// The matching pairs and the rows of either side matching nothing
+    Full,
+    /// Every pair of rows
+    Cross,
+}
+
+/// How the rows of the two sides of a join are matched
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum JoinConstraint {
+    /// `ON expr`
+    On(Expression),
+    /// `USING (a, b)`, the columns both sides have equal values for
+    Using(Vec<Ident>),
+    /// `NATURAL JOIN`, like USING with every column both sides have
+    Natural,
+    /// A cross join or a comma join
+    None,
+}
+
+/// The tables a query reads from, joined together
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum FromClause {
+    Table(TableReference),
+    /// `(SELECT ...) AS alias`, a derived table: the rows of a query read like a table's
+    Subquery {
+        query: QueryExpression,
+        alias: Ident,
+    },
+    Join {
+        left: Box<FromClause>,
+        kind: JoinKind,
+        right: Box<FromClause>,
+        constraint: JoinConstraint,
+    },
+}
+
+impl Default for FromClause {
+    fn default() -> Self {
+        Self::Table(TableReference::default())
+    }
+}
+
+impl From<&str> for FromClause {
+    fn from(name: &str) -> Self {
+        Self::Table(name.into())
+    }
+}
+
+impl FromClause {
+    /// The tables joined, from left to right, without the derived tables
+    pub fn tables(&self) -> Vec<&TableReference> {
+        match self {
+            Self::Table(table) => vec![table],
+            Self::Subquery { .. } => Vec::new(),
+            Self::Join { left, right, .. } => {
+                let mut tables = left.tables();
+                tables.extend(right.tables());
+                tables
+            }
+        }
+    }
+
+    /// The names the query refers to the tables and derived tables joined by, from
+    /// left to right
+    pub fn reference_names(&self) -> Vec<&Ident> {
+        match self {
+            Self::Table(table) => vec![table.reference_name()],
+            Self::Subquery { alias, .. } => vec![alias],
+            Self::Join { left, right, .. } => {
+                let mut names = left.reference_names();
+                names.extend(right.reference_names());
+                names
+            }
+        }
+    }
+}
+
+fn table_reference(input: RawSpan<'_>) -> ParserResult<'_, TableReference> {
+    map(
+        pair(
+            object_name.context("Table Name"),
+            opt(preceded(whitespace1, alias)),
+        ),
+        |(name, alias)| TableReference { name, alias },
+    )(input)
+}
+
+// `(SELECT ...) [AS] alias`, a derived table can't do without a name
+fn derived_table(input: RawSpan<'_>) -> ParserResult<'_, FromClause> {
+    map(
+        pair(
+            parse_subquery,
+            cut(preceded(whitespace0, alias)).context("Subquery Alias"),
+        ),
+        |(query, alias)| FromClause::Subquery { query, alias },
+    )(input)
+}
+
+// a table, a derived table or a parenthesised join
+fn table_factor(input: RawSpan<'_>) -> ParserResult<'_, FromClause> {
+    alt((
+        derived_table,
+        delimited(
+            pair(char('('), whitespace0),
+            FromClause::parse,
+            pair(whitespace0, char(')')),
+        ),
+        map(table_reference, FromClause::Table),
+    ))(input)
+}
+
+// `[INNER] JOIN`, `{LEFT | RIGHT | FULL} [OUTER] JOIN` or `CROSS JOIN`
+fn join_kind(input: RawSpan<'_>) -> ParserResult<'_, JoinKind> {
+    let outer = |kind| {
+        map(
+            pair(keyword(kind), opt(pair(whitespace1, keyword("outer")))),
+            |_| (),
+        )
+    };
+    terminated(
+        alt((
+            map(terminated(keyword("inner"), whitespace1), |_| {
+                JoinKind::Inner
+            }),
+            map(terminated(outer("left"), whitespace1), |_| JoinKind::Left),
+            map(terminated(outer("right"), whitespace1), |_| JoinKind::Right),
+            map(terminated(outer("full"), whitespace1), |_| JoinKind::Full),
+            map(terminated(keyword("cross"), whitespace1), |_| {
+                JoinKind::Cross
+            }),
+            map(whitespace0, |_| JoinKind::Inner),
+        )),
+        keyword("join"),
+    )(input)
+}
+
+fn using_columns(input: RawSpan<'_>) -> ParserResult<'_, Vec<Ident>> {
+    preceded(
+        pair(keyword("using"), whitespace0),
+        cut(delimited(
+            pair(char('('), whitespace0),
+            separated_list1(
+                tuple((whitespace0, char(','), whitespace0)),
+                identifier.context("Column Name"),
+            ),
+            pair(whitespace0, char(')')),
+        ))
+        .context("Using Columns"),
+    )(input)
+}
+
+fn join_condition(input: RawSpan<'_>) -> ParserResult<'_, JoinConstraint> {
+    preceded(
+        whitespace1,
+        alt((
+            map(
+                preceded(
+                    pair(keyword("on"), whitespace1),
+                    cut(Expression::parse.context("Join Condition")),
+                ),
+                JoinConstraint::On,
+            ),
+            map(using_columns, JoinConstraint::Using),
+        )),
+    )(input)
+}
+
+// `[NATURAL] <kind> JOIN table [ON expr | USING (columns)]`
+fn qualified_join(input: RawSpan<'_>) -> ParserResult<'_, (JoinKind, FromClause, JoinConstraint)> {
+    let (rest, (natural, kind)) = preceded(
+        whitespace1,
+        pair(opt(terminated(keyword("natural"), whitespace1)), join_kind),
+    )(input)?;
+    let (rest, right) = cut(preceded(whitespace1, table_factor))(rest)?;
+
+    let (rest, constraint) = match (natural, kind) {
+        (Some(_), _) => (rest, JoinConstraint::Natural),
+        (None, JoinKind::Cross) => (rest, JoinConstraint::None),
+        // the other joins can't do without a condition
+        (None, _) => cut(join_condition)(rest)?,
+    };
+    Ok((rest, (kind, right, constraint)))
+}
+
+// the right side of a join and how it is joined, a comma is a cross join
+fn join(input: RawSpan<'_>) -> ParserResult<'_, (JoinKind, FromClause, JoinConstraint)> {
+    alt((
+        map(
+            preceded(tuple((whitespace0, char(','), whitespace0)), table_factor),
+            |right| (JoinKind::Cross, right, JoinConstraint::None),
+        ),
+        qualified_join,
+    ))(input)
+}
+
+impl<'a> Parse<'a> for FromClause {
+    fn parse(input: RawSpan<'a>) -> ParserResult<'a, Self> {
+        let (rest, (first, joins)) = pair(table_factor, many0(join))(input)?;
+        // joins are evaluated left to right, `a JOIN b JOIN c` is `(a JOIN b) JOIN c`
+        let from = joins
+            .into_iter()
+            .fold(first, |left, (kind, right, constraint)| Self::Join {
+                left: Box::new(left),
+                kind,
+                right: Box::new(right),
+                constraint,
+            });
+        Ok((rest, from))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn join(
+        left: FromClause,
+        kind: JoinKind,
+        right: FromClause,
+        constraint: JoinConstraint,
+    ) -> FromClause {
+        FromClause::Join {
+            left: Box::new(left),
+            kind,
+            right: Box::new(right),
+            constraint,
+        }
+    }
+
+    fn aliased(name: &str, alias: &str) -> FromClause {
+        FromClause::Table(TableReference {
+            name: name.into(),
+            alias: Some(alias.into()),
+        })
+    }
+
+    #[test]
+    fn test_parse_table_with_alias() {
+        assert_eq!(
+            FromClause::parse_from_raw("Person AS p").unwrap().1,
+            aliased("person", "p")
+        );
+        assert_eq!(
+            FromClause::parse_from_raw("Person p").unwrap().1,
+            aliased("person", "p")
+        );
+    }
+
+    #[test]
+    fn test_parse_qualified_and_quoted_tables() {
+        let (_, from) =
+            FromClause::parse_from_raw("public.person, \"Order Details\" \"order\"").unwrap();
+
+        assert_eq!(
+            from,
+            join(
+                FromClause::Table(TableReference {
+                    name: ObjectName(vec!["public".into(), "person".into()]),
+                    alias: None,
+                }),
+                JoinKind::Cross,
+                aliased("Order Details", "order"),
+                JoinConstraint::None,
+            )
+        );
+        assert_eq!(from.reference_names(), vec!["person", "order"]);
+    }
+
+    #[test]
+    fn test_parse_joins_left_to_right() {
+        let (_, from) = FromClause::parse_from_raw(
+            "Person p LEFT OUTER JOIN City c ON p.city = c.name JOIN Country USING (code), Planet",
+        )
+        .unwrap();
+
+        assert_eq!(
+            from,
+            join(
+                join(
+                    join(
+                        aliased("person", "p"),
+                        JoinKind::Left,
+                        aliased("city", "c"),
+                        JoinConstraint::On(
+                            Expression::parse_from_raw("p.city = c.name").unwrap().1
+                        )
+                    ),
+                    JoinKind::Inner,
+                    "country".into(),
+                    JoinConstraint::Using(vec!["code".into()])
+                ),
+                JoinKind::Cross,
+                "planet".into(),
+                JoinConstraint::None
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_natural_and_cross_joins() {
+        assert_eq!(
+            FromClause::parse_from_raw("a NATURAL FULL JOIN b CROSS JOIN c")
+                .unwrap()
+                .1,
+            join(
+                join(
+                    "a".into(),
+                    JoinKind::Full,
+                    "b".into(),
+                    JoinConstraint::Natural
+                ),
+                JoinKind::Cross,
+                "c".into(),
+                JoinConstraint::None
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_parenthesised_join() {
+        assert_eq!(
+            FromClause::parse_from_raw("a JOIN (b RIGHT JOIN c USING (id)) USING (id)")
+                .unwrap()
+                .1,
+            join(
+                "a".into(),
+                JoinKind::Inner,
+                join(
+                    "b".into(),
+                    JoinKind::Right,
+                    "c".into(),
+                    JoinConstraint::Using(vec!["id".into()])
+                ),
+                JoinConstraint::Using(vec!["id".into()])
+            )
+        );
+    }
+
+    #[test]
+    fn test_join_without_condition_is_an_error() {
+        assert!(matches!(
+            FromClause::parse_from_raw("a JOIN b WHERE"),
+            Err(nom::Err::Failure(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_derived_table() {
+        let (_, from) =
+            FromClause::parse_from_raw("(SELECT name FROM Person) AS p JOIN City c ON 1 = 1")
+                .unwrap();
+
+        let names: Vec<&str> = from
+            .reference_names()
+            .into_iter()
+            .map(Ident::as_str)
+            .collect();
+        assert_eq!(names, vec!["p", "c"]);
+        assert_eq!(from.tables().len(), 1);
+        assert!(matches!(
+            FromClause::parse_from_raw("(SELECT name FROM Person) WHERE"),
+            Err(nom::Err::Failure(_))
+        ));
+    }
+
+    #[test]
+    fn test_tables() {
+        let (_, from) = FromClause::parse_from_raw("a x, b JOIN c ON true").unwrap();
+
+        let names: Vec<&str> = from
+            .tables()
+            .into_iter()
+            .map(|table| table.reference_name().as_str())
+            .collect();
+        assert_eq!(names, vec!["x", "b", "c"]);
+    }
+}
@@ -0,0 +1,117 @@
+use std::fmt::{self, Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::Span;
+
+/// A table or column name and where it was written in the query
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct Ident {
+    pub value: String,
+    // positions only make sense for the query being parsed, so they aren't stored
+    #[serde(skip)]
+    pub span: Span,
+}
+
+impl Ident {
+    pub fn new(value: impl Into<String>, span: Span) -> Self {
+        Self {
+            value: value.into(),
+            span,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.value
+    }
+}
+
+impl Display for Ident {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+impl From<&str> for Ident {
+    fn from(value: &str) -> Self {
+        Self::new(value, Span::default())
+    }
+}
+
+impl PartialEq<str> for Ident {
+    fn eq(&self, other: &str) -> bool {
+        self.value == other
+    }
+}
+
+impl PartialEq<String> for Ident {
+    fn eq(&self, other: &String) -> bool {
+        &self.value == other
+    }
+}
+
+/// A table name, possibly qualified by the names it belongs to as in `public.person`
+#[derive(Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct ObjectName(pub Vec<Ident>);
+
+impl ObjectName {
+    /// The last part of the name, naming the object itself
+    pub fn base(&self) -> &Ident {
+        self.0.last().expect("a name has at least one part")
+    }
+
+    /// The only part of the name, when it isn't qualified
+    pub fn as_unqualified(&self) -> Option<&Ident> {
+        match self.0.as_slice() {
+            [ident] => Some(ident),
+            _ => None,
+        }
+    }
+
+    /// Whether the last parts of the name are `suffix`, as `public.person` ends with
+    /// `person`
+    pub fn ends_with(&self, suffix: &ObjectName) -> bool {
+        self.0.ends_with(&suffix.0)
+    }
+
+    /// Where the name was written in the query, from its first part to its last
+    pub fn span(&self) -> Span {
+        self.0[0].span.union(self.base().span)
+    }
+}
+
+impl Default for ObjectName {
+    fn default() -> Self {
+        Self(vec![Ident::default()])
+    }
+}
+
+// a part with a dot or a quote in it is written quoted, so two different names are
+// never written the same way
+impl Display for ObjectName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (index, part) in self.0.iter().enumerate() {
+            if index > 0 {
+                write!(f, ".")?;
+            }
+            match part.value.contains(['.', '"']) {
+                true => write!(f, "\"{}\"", part.value.replace('"', "\"\""))?,
+                false => write!(f, "{part}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<Ident> for ObjectName {
+    fn from(ident: Ident) -> Self {
+        Self(vec![ident])
+    }
+}
+
+// a name of a single part, even if it has dots in it
+impl From<&str> for ObjectName {
+    fn from(value: &str) -> Self {
+        Ident::from(value).into()
+    }
+}
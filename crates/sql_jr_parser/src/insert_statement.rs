@@ -1,48 +1,52 @@
 use nom::{
     branch::alt,
     bytes::complete::tag_no_case,
-    character::complete::{char, multispace0, multispace1},
-    combinator::map,
+    character::complete::char,
+    combinator::{consumed, map},
     error::context,
-    multi::separated_list0,
+    multi::{separated_list0, separated_list1},
     sequence::{preceded, tuple},
 };
 use nom_supreme::ParserExt;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    common_parsers::{identifier, parse_string},
-    types::{Parse, ParserResult, RawSpan},
+    common_parsers::{identifier, object_name, parse_literal, whitespace0, whitespace1},
+    error::spanned_failure,
+    ident::{Ident, ObjectName},
+    types::{Parse, ParserResult, RawSpan, Span},
+    value::Literal,
 };
 
 #[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub struct InsertStatement {
-    table: String,
-    columns: Vec<String>,
-    // TODO: Modify this to support other types of values
-    values: Vec<String>,
+    pub table: ObjectName,
+    pub columns: Vec<Ident>,
+    pub rows: Vec<Vec<Literal>>,
+    #[serde(skip)]
+    pub span: Span,
 }
 
-fn parse_table_name(input: RawSpan<'_>) -> ParserResult<'_, String> {
+fn parse_table_name(input: RawSpan<'_>) -> ParserResult<'_, ObjectName> {
     let insert_into = tuple((
         tag_no_case("insert"),
-        multispace1,
+        whitespace1,
         tag_no_case("into"),
-        multispace1,
+        whitespace1,
     ));
-    preceded(insert_into, identifier.context("Table Name"))(input)
+    preceded(insert_into, object_name.context("Table Name"))(input)
 }
 
-fn parse_column_names(input: RawSpan<'_>) -> ParserResult<'_, Vec<String>> {
+fn parse_column_names(input: RawSpan<'_>) -> ParserResult<'_, Vec<Ident>> {
     let column_names = map(
         tuple((
             char('('),
-            multispace0,
+            whitespace0,
             separated_list0(
-                tuple((multispace0, char(','), multispace1)),
+                tuple((whitespace0, char(','), whitespace1)),
                 identifier.context("Column Name"),
             ),
-            multispace0,
+            whitespace0,
             char(')'),
         )),
         |(_, _, columns, _, _)| columns,
@@ -53,54 +57,100 @@ fn parse_column_names(input: RawSpan<'_>) -> ParserResult<'_, Vec<String>> {
     context("Column Names", alt((column_names, empty_col_parser)))(input)
 }
 
-fn parse_column_values(input: RawSpan<'_>) -> ParserResult<'_, Vec<String>> {
+// parses a single parenthesised tuple of values
+fn parse_row(input: RawSpan<'_>) -> ParserResult<'_, Vec<Literal>> {
+    map(
+        tuple((
+            char('('),
+            whitespace0,
+            separated_list0(tuple((whitespace0, char(','), whitespace0)), parse_literal),
+            whitespace0,
+            char(')'),
+        )),
+        |(_, _, values, _, _)| values,
+    )(input)
+}
+
+// parses the comma separated tuples after VALUES, keeping the source of each
+// tuple so an arity mismatch can be reported on the offending row
+fn parse_column_values(input: RawSpan<'_>) -> ParserResult<'_, Vec<(RawSpan<'_>, Vec<Literal>)>> {
     context(
         "Values",
-        map(
-            tuple((
-                tag_no_case("values"),
-                multispace0,
-                char('('),
-                multispace0,
-                separated_list0(tuple((multispace0, char(','), multispace1)), parse_string),
-                multispace0,
-                char(')'),
-            )),
-            |(_, _, _, _, column_value, _, _)| column_value,
+        preceded(
+            tuple((tag_no_case("values"), whitespace0)),
+            separated_list1(
+                tuple((whitespace0, char(','), whitespace0)),
+                consumed(parse_row.context("Row")),
+            ),
         ),
     )(input)
 }
 
+// every row needs as many values as the column list, or as the first row when
+// no columns are named
+fn check_row_arity<'a>(
+    columns: &[Ident],
+    rows: Vec<(RawSpan<'a>, Vec<Literal>)>,
+) -> Result<Vec<Vec<Literal>>, nom::Err<crate::types::MyParseError<'a>>> {
+    let expected = match columns.len() {
+        0 => rows.first().map_or(0, |(_, row)| row.len()),
+        n => n,
+    };
+
+    for (source, row) in &rows {
+        if row.len() != expected {
+            let reason = if columns.is_empty() {
+                "the first row"
+            } else {
+                "the column list"
+            };
+            return Err(spanned_failure(
+                *source,
+                format!("row has {} values but {reason} has {expected}", row.len()),
+            ));
+        }
+    }
+
+    Ok(rows.into_iter().map(|(_, row)| row).collect())
+}
+
 impl<'a> Parse<'a> for InsertStatement {
     fn parse(input: RawSpan<'a>) -> ParserResult<'a, Self> {
-        map(
-            tuple((
-                parse_table_name,
-                multispace0,
-                parse_column_names,
-                multispace0,
-                parse_column_values,
-            )),
-            |(table, _, columns, _, values)| InsertStatement {
+        let (rest, (source, (table, _, columns, _, rows))) = consumed(tuple((
+            parse_table_name,
+            whitespace0,
+            parse_column_names,
+            whitespace0,
+            parse_column_values,
+        )))(input)?;
+        let rows = check_row_arity(&columns, rows)?;
+
+        Ok((
+            rest,
+            InsertStatement {
                 table,
                 columns,
-                values,
+                rows,
+                span: source.into(),
             },
-        )(input)
+        ))
     }
 }
 
 #[cfg(test)]
 mod test {
+    use miette::Diagnostic;
     use nom_locate::LocatedSpan;
+    use nom_supreme::error::GenericErrorTree;
 
     use super::*;
+    use crate::value::Value;
 
     #[test]
     fn test_parse_table_name() {
         let (_, parsed) = parse_table_name(LocatedSpan::new("insert into Person")).unwrap();
 
-        assert_eq!(parsed, "Person".to_string());
+        assert_eq!(parsed, "person".into());
     }
 
     #[test]
@@ -111,9 +161,9 @@ mod test {
         assert_eq!(
             parsed,
             vec![
-                "CustomerName".to_string(),
-                "ContactName".to_string(),
-                "Address".to_string(),
+                "customername".to_string(),
+                "contactname".to_string(),
+                "address".to_string(),
             ]
         );
     }
@@ -126,9 +176,9 @@ mod test {
         assert_eq!(
             parsed,
             vec![
-                "CustomerName".to_string(),
-                "ContactName".to_string(),
-                "Address".to_string(),
+                "customername".to_string(),
+                "contactname".to_string(),
+                "address".to_string(),
             ]
         );
     }
@@ -136,17 +186,36 @@ mod test {
     #[test]
     fn test_parse_column_values() {
         let (_, parsed) = parse_column_values(LocatedSpan::new(
-            "VALUES ( \"CustomerName\", \"ContactName\", \"Address\" )",
+            "VALUES ( 'CustomerName', 'ContactName', 'Address' )",
         ))
         .unwrap();
+        let rows: Vec<Vec<Literal>> = parsed.into_iter().map(|(_, row)| row).collect();
 
         assert_eq!(
-            parsed,
-            vec![
-                "CustomerName".to_string(),
-                "ContactName".to_string(),
-                "Address".to_string(),
-            ]
+            rows,
+            vec![vec![
+                Value::String("CustomerName".to_string()),
+                Value::String("ContactName".to_string()),
+                Value::String("Address".to_string()),
+            ]]
+        );
+    }
+
+    #[test]
+    fn test_parse_typed_column_values() {
+        let (_, parsed) =
+            parse_column_values(LocatedSpan::new("VALUES (42,-1.5, 'Oslo', true, NULL)")).unwrap();
+        let rows: Vec<Vec<Literal>> = parsed.into_iter().map(|(_, row)| row).collect();
+
+        assert_eq!(
+            rows,
+            vec![vec![
+                Value::Int(42),
+                Value::Float(-1.5),
+                Value::String("Oslo".to_string()),
+                Value::Boolean(true),
+                Value::Null,
+            ]]
         );
     }
 
@@ -160,17 +229,18 @@ mod test {
         assert_eq!(
             statement,
             InsertStatement {
-                table: "Customers".to_string(),
+                table: "customers".into(),
                 columns: vec![
-                    "CustomerName".to_string(),
-                    "ContactName".to_string(),
-                    "Address".to_string()
+                    "customername".into(),
+                    "contactname".into(),
+                    "address".into()
                 ],
-                values: vec![
-                    "Cardinal".to_string(),
-                    "Tom B. Erichsen".to_string(),
-                    "Skagen 21".to_string(),
-                ]
+                rows: vec![vec![
+                    Value::String("Cardinal".to_string()).into(),
+                    Value::String("Tom B. Erichsen".to_string()).into(),
+                    Value::String("Skagen 21".to_string()).into(),
+                ]],
+                span: Span::default(),
             }
         )
     }
@@ -185,14 +255,80 @@ mod test {
         assert_eq!(
             statement,
             InsertStatement {
-                table: "Customers".to_string(),
+                table: "customers".into(),
                 columns: vec![],
-                values: vec![
-                    "Cardinal".to_string(),
-                    "Tom B. Erichsen".to_string(),
-                    "Skagen 21".to_string(),
-                ]
+                rows: vec![vec![
+                    Value::String("Cardinal".to_string()).into(),
+                    Value::String("Tom B. Erichsen".to_string()).into(),
+                    Value::String("Skagen 21".to_string()).into(),
+                ]],
+                span: Span::default(),
+            }
+        )
+    }
+
+    #[test]
+    fn test_parse_insert_statement_with_int_value() {
+        let (_, statement) =
+            InsertStatement::parse_from_raw("INSERT INTO Person (name, age) VALUES ('Jo', 42);")
+                .unwrap();
+
+        assert_eq!(
+            statement,
+            InsertStatement {
+                table: "person".into(),
+                columns: vec!["name".into(), "age".into()],
+                rows: vec![vec![
+                    Value::String("Jo".to_string()).into(),
+                    Value::Int(42).into()
+                ]],
+                span: Span::default(),
             }
         )
     }
+
+    #[test]
+    fn test_parse_insert_statement_with_multiple_rows() {
+        let (_, statement) = InsertStatement::parse_from_raw(
+            "INSERT INTO Person (name, age) VALUES ('Jo', 42), ('Al', 7),('Bo', NULL);",
+        )
+        .unwrap();
+
+        assert_eq!(
+            statement.rows,
+            vec![
+                vec![Value::String("Jo".to_string()), Value::Int(42)],
+                vec![Value::String("Al".to_string()), Value::Int(7)],
+                vec![Value::String("Bo".to_string()), Value::Null],
+            ]
+        )
+    }
+
+    #[test]
+    fn test_row_with_different_arity_than_first_row_is_an_error() {
+        let raw = "INSERT INTO Person VALUES ('Jo', 42), ('Al')";
+
+        let Err(nom::Err::Failure(GenericErrorTree::Base { location, kind })) =
+            InsertStatement::parse_from_raw(raw)
+        else {
+            panic!("expected an arity failure");
+        };
+
+        assert_eq!(location.location_offset(), raw.find("('Al')").unwrap());
+        assert!(kind
+            .to_string()
+            .contains("row has 1 values but the first row has 2"));
+    }
+
+    #[test]
+    fn test_row_with_different_arity_than_column_list_is_an_error() {
+        let error = InsertStatement::parse_format_error(
+            "INSERT INTO Person (name, age) VALUES ('Jo', 42, 'Oslo')",
+        )
+        .unwrap_err();
+        let label = error.labels().unwrap().next().unwrap();
+
+        assert_eq!(label.offset(), 38);
+        assert_eq!(label.len(), 18);
+    }
 }
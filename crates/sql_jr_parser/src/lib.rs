@@ -1,7 +1,18 @@
+pub mod alter_table_statement;
 mod common_parsers;
-mod create_statement;
+pub mod create_statement;
+pub mod delete_statement;
+pub mod drop_statement;
 mod error;
-mod insert_statement;
+pub mod expression;
+pub mod from_clause;
+pub mod ident;
+pub mod insert_statement;
 pub mod query;
-mod select_statement;
+pub mod query_expression;
+pub mod select_statement;
+pub mod truncate_statement;
 pub mod types;
+pub mod update_statement;
+pub mod value;
+pub mod window;
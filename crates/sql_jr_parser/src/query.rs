@@ -1,6 +1,6 @@
 use nom::{
     branch::alt,
-    character::complete::{char, multispace0},
+    character::complete::char,
     combinator::map,
     error::context,
     sequence::{preceded, tuple},
@@ -8,16 +8,45 @@ use nom::{
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    create_statement::CreateStatement, insert_statement::InsertStatement,
-    select_statement::SelectStatement, types::Parse,
+    alter_table_statement::AlterTableStatement,
+    common_parsers::whitespace0,
+    create_statement::CreateStatement,
+    delete_statement::DeleteStatement,
+    drop_statement::DropStatement,
+    insert_statement::InsertStatement,
+    query_expression::QueryExpression,
+    truncate_statement::TruncateStatement,
+    types::{Parse, Span},
+    update_statement::UpdateStatement,
 };
 
 /// All possible commands
 #[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub enum SqlQuery {
-    Select(SelectStatement),
+    Select(QueryExpression),
     Insert(InsertStatement),
     Create(CreateStatement),
+    Update(UpdateStatement),
+    Delete(DeleteStatement),
+    Drop(DropStatement),
+    Truncate(TruncateStatement),
+    AlterTable(AlterTableStatement),
+}
+
+impl SqlQuery {
+    /// Where the statement was written in the parsed input, without the trailing `;`
+    pub fn span(&self) -> Span {
+        match self {
+            SqlQuery::Select(query) => query.span(),
+            SqlQuery::Insert(insert) => insert.span,
+            SqlQuery::Create(create) => create.span,
+            SqlQuery::Update(update) => update.span,
+            SqlQuery::Delete(delete) => delete.span,
+            SqlQuery::Drop(drop) => drop.span,
+            SqlQuery::Truncate(truncate) => truncate.span,
+            SqlQuery::AlterTable(alter) => alter.span,
+        }
+    }
 }
 
 impl<'a> Parse<'a> for SqlQuery {
@@ -25,16 +54,21 @@ impl<'a> Parse<'a> for SqlQuery {
         let (rest, (query, _, _, _)) = context(
             "Query",
             preceded(
-                multispace0,
+                whitespace0,
                 tuple((
                     alt((
-                        map(SelectStatement::parse, SqlQuery::Select),
+                        map(QueryExpression::parse, SqlQuery::Select),
                         map(InsertStatement::parse, SqlQuery::Insert),
                         map(CreateStatement::parse, SqlQuery::Create),
+                        map(UpdateStatement::parse, SqlQuery::Update),
+                        map(DeleteStatement::parse, SqlQuery::Delete),
+                        map(DropStatement::parse, SqlQuery::Drop),
+                        map(TruncateStatement::parse, SqlQuery::Truncate),
+                        map(AlterTableStatement::parse, SqlQuery::AlterTable),
                     )),
-                    multispace0,
+                    whitespace0,
                     char(';'),
-                    multispace0,
+                    whitespace0,
                 )),
             ),
         )(input)?;
@@ -43,9 +77,33 @@ impl<'a> Parse<'a> for SqlQuery {
     }
 }
 
+/// A sequence of `;` terminated queries, e.g. the contents of a migration file
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct SqlScript {
+    pub queries: Vec<SqlQuery>,
+}
+
+impl<'a> Parse<'a> for SqlScript {
+    fn parse(input: crate::types::RawSpan<'a>) -> crate::types::ParserResult<'a, Self> {
+        let (mut rest, _) = whitespace0(input)?;
+        let mut queries = Vec::new();
+
+        // parse until the input runs out rather than until a query fails to parse,
+        // so the error of a broken query isn't swallowed
+        while !rest.fragment().is_empty() {
+            let (remaining, query) = SqlQuery::parse(rest)?;
+            queries.push(query);
+            rest = remaining;
+        }
+
+        Ok((rest, Self { queries }))
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::select_statement::SelectStatement;
 
     #[test]
     fn test_parse_select_query() {
@@ -58,6 +116,7 @@ mod test {
                 SelectStatement::parse_from_raw("SELECT CustomerName, City FROM Customers;")
                     .unwrap()
                     .1
+                    .into()
             )
         )
     }
@@ -68,7 +127,7 @@ mod test {
             "INSERT INTO Customers (CustomerName, ContactName, Address, City, PostalCode, Country)
 VALUES ('Cardinal', 'Tom B. Erichsen', 'Skagen 21', 'Stavanger', '4006', 'Norway');";
 
-        let (_, query) = SqlQuery::parse_from_raw(&raw_query).unwrap();
+        let (_, query) = SqlQuery::parse_from_raw(raw_query).unwrap();
 
         assert_eq!(
             query,
@@ -81,11 +140,110 @@ VALUES ('Cardinal', 'Tom B. Erichsen', 'Skagen 21', 'Stavanger', '4006', 'Norway
         let raw_query = "CREATE TABLE Persons (PersonID int, 
             LastName string);";
 
-        let (_, query) = SqlQuery::parse_from_raw(&raw_query).unwrap();
+        let (_, query) = SqlQuery::parse_from_raw(raw_query).unwrap();
 
         assert_eq!(
             query,
             SqlQuery::Create(CreateStatement::parse_from_raw(raw_query).unwrap().1)
         )
     }
+
+    #[test]
+    fn test_parse_update_query() {
+        let raw_query = "UPDATE Person SET age = age + 1 WHERE name = 'Jo';";
+
+        let (_, query) = SqlQuery::parse_from_raw(raw_query).unwrap();
+
+        assert_eq!(
+            query,
+            SqlQuery::Update(UpdateStatement::parse_from_raw(raw_query).unwrap().1)
+        )
+    }
+
+    #[test]
+    fn test_parse_delete_query() {
+        let raw_query = "DELETE FROM Person WHERE name = 'Jo';";
+
+        let (_, query) = SqlQuery::parse_from_raw(raw_query).unwrap();
+
+        assert_eq!(
+            query,
+            SqlQuery::Delete(DeleteStatement::parse_from_raw(raw_query).unwrap().1)
+        )
+    }
+
+    #[test]
+    fn test_parse_drop_and_truncate_queries() {
+        let (_, drop) = SqlQuery::parse_from_raw("DROP TABLE IF EXISTS Person;").unwrap();
+        let (_, truncate) = SqlQuery::parse_from_raw("TRUNCATE TABLE Person;").unwrap();
+
+        assert!(matches!(drop, SqlQuery::Drop(_)));
+        assert!(matches!(truncate, SqlQuery::Truncate(_)));
+    }
+
+    #[test]
+    fn test_parse_alter_table_query() {
+        let raw_query = "ALTER TABLE Person ADD COLUMN age int;";
+
+        let (_, query) = SqlQuery::parse_from_raw(raw_query).unwrap();
+
+        assert_eq!(
+            query,
+            SqlQuery::AlterTable(AlterTableStatement::parse_from_raw(raw_query).unwrap().1)
+        )
+    }
+
+    #[test]
+    fn test_query_span_excludes_surrounding_whitespace_and_semicolon() {
+        let raw_query = "  SELECT name FROM Person ;";
+
+        let (_, query) = SqlQuery::parse_from_raw(raw_query).unwrap();
+
+        assert_eq!(query.span().offset, 2);
+        assert_eq!(query.span().length, "SELECT name FROM Person".len());
+    }
+
+    #[test]
+    fn test_parse_script() {
+        let raw_script = "-- create the table first
+CREATE TABLE Person (name string, /* years */ age int);
+INSERT INTO Person VALUES ('Jo', 42);
+
+/* and read it back */ SELECT name FROM Person;
+";
+
+        let script = SqlScript::parse_format_error(raw_script).unwrap();
+
+        assert_eq!(
+            script.queries,
+            vec![
+                SqlQuery::parse_from_raw("CREATE TABLE Person (name string, age int);")
+                    .unwrap()
+                    .1,
+                SqlQuery::parse_from_raw("INSERT INTO Person VALUES ('Jo', 42);")
+                    .unwrap()
+                    .1,
+                SqlQuery::parse_from_raw("SELECT name FROM Person;")
+                    .unwrap()
+                    .1,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_empty_script() {
+        let script = SqlScript::parse_format_error("  -- nothing to see here\n").unwrap();
+
+        assert!(script.queries.is_empty());
+    }
+
+    #[test]
+    fn test_parse_script_reports_broken_statement() {
+        let raw_script = "SELECT name FROM Person; SELECT FROM Person;";
+
+        let error = SqlScript::parse_format_error(raw_script).unwrap_err();
+        let label = miette::Diagnostic::labels(&error).unwrap().next().unwrap();
+
+        assert!(label.offset() > raw_script.find(';').unwrap());
+    }
 }
@@ -0,0 +1,527 @@
+use std::fmt::{self, Display, Formatter};
+
+use nom::{
+    branch::alt,
+    character::complete::char,
+    combinator::{cut, map, opt, peek},
+    multi::{many0, separated_list1},
+    sequence::{delimited, pair, preceded, terminated, tuple},
+};
+use nom_supreme::ParserExt;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common_parsers::{identifier, keyword, spanned, whitespace0, whitespace1},
+    ident::Ident,
+    select_statement::{
+        parse_order_by, parse_pagination, parse_select_core, OrderByExpr, SelectStatement,
+    },
+    types::{Parse, ParserResult, RawSpan, Span},
+};
+
+/// How the rows of the two queries of a set operation are combined
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum SetOperator {
+    /// The rows of either query
+    Union,
+    /// The rows of both queries
+    Intersect,
+    /// The rows of the left query the right one doesn't return
+    Except,
+}
+
+impl Display for SetOperator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Union => "UNION",
+            Self::Intersect => "INTERSECT",
+            Self::Except => "EXCEPT",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Two queries returning the same number of columns, whose rows are combined and
+/// then sorted and paginated
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct SetOperation {
+    pub left: QueryExpression,
+    pub op: SetOperator,
+    /// Duplicate rows are kept, `UNION ALL`
+    pub all: bool,
+    pub right: QueryExpression,
+    /// Sorts the combined rows, by result columns only
+    pub order_by: Vec<OrderByExpr>,
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+    #[serde(skip)]
+    pub span: Span,
+}
+
+/// A query in parentheses whose rows are sorted or paginated again, such as
+/// `(SELECT a FROM t LIMIT 5) ORDER BY a LIMIT 2`
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct NestedQuery {
+    pub query: QueryExpression,
+    /// Sorts the rows of the query, by result columns only
+    pub order_by: Vec<OrderByExpr>,
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+    #[serde(skip)]
+    pub span: Span,
+}
+
+/// A query named by a WITH clause, which the queries after it read like a table
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct CommonTableExpression {
+    pub name: Ident,
+    /// New names for the result columns of the query, the first ones when there
+    /// are fewer names than columns
+    pub columns: Vec<Ident>,
+    pub query: QueryExpression,
+}
+
+/// `WITH [RECURSIVE] name AS (query), ...` and the query reading them
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct WithQuery {
+    /// The queries of the WITH clause may read their own rows
+    pub recursive: bool,
+    pub tables: Vec<CommonTableExpression>,
+    pub query: QueryExpression,
+    #[serde(skip)]
+    pub span: Span,
+}
+
+/// A query returning rows: a SELECT, a set operation combining queries, a query with
+/// a WITH clause or a sorted query sorted again
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum QueryExpression {
+    Select(Box<SelectStatement>),
+    SetOperation(Box<SetOperation>),
+    With(Box<WithQuery>),
+    Nested(Box<NestedQuery>),
+}
+
+impl From<SelectStatement> for QueryExpression {
+    fn from(select: SelectStatement) -> Self {
+        Self::Select(Box::new(select))
+    }
+}
+
+impl QueryExpression {
+    /// Where the query was written in the parsed input
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Select(select) => select.span,
+            Self::SetOperation(operation) => operation.span,
+            Self::With(with) => with.span,
+            Self::Nested(nested) => nested.span,
+        }
+    }
+
+    // the ORDER BY, LIMIT and OFFSET applying to the rows of the whole query
+    fn sort_and_pagination(
+        &mut self,
+    ) -> (&mut Vec<OrderByExpr>, &mut Option<u64>, &mut Option<u64>) {
+        match self {
+            Self::Select(select) => (&mut select.order_by, &mut select.limit, &mut select.offset),
+            Self::SetOperation(operation) => (
+                &mut operation.order_by,
+                &mut operation.limit,
+                &mut operation.offset,
+            ),
+            Self::With(with) => with.query.sort_and_pagination(),
+            Self::Nested(nested) => (&mut nested.order_by, &mut nested.limit, &mut nested.offset),
+        }
+    }
+}
+
+/// A query in parentheses, nested in an expression or a FROM clause. Once `(SELECT`
+/// or `(WITH` is seen it can only be a subquery
+pub(crate) fn parse_subquery(input: RawSpan<'_>) -> ParserResult<'_, QueryExpression> {
+    preceded(
+        tuple((
+            char('('),
+            whitespace0,
+            peek(alt((keyword("select"), keyword("with")))),
+        )),
+        cut(terminated(
+            QueryExpression::parse,
+            pair(whitespace0, char(')')),
+        ))
+        .context("Subquery"),
+    )(input)
+}
+
+// a SELECT without ORDER BY and LIMIT, which would apply to the whole query, or a
+// query in parentheses
+fn operand(input: RawSpan<'_>) -> ParserResult<'_, QueryExpression> {
+    alt((
+        map(parse_select_core, QueryExpression::from),
+        delimited(
+            pair(char('('), whitespace0),
+            QueryExpression::parse,
+            pair(whitespace0, char(')')),
+        ),
+    ))(input)
+}
+
+// `op [ALL | DISTINCT]`, returning whether duplicates are kept
+fn set_operator<'a>(
+    operators: impl FnMut(RawSpan<'a>) -> ParserResult<'a, SetOperator>,
+) -> impl FnMut(RawSpan<'a>) -> ParserResult<'a, (SetOperator, bool)> {
+    pair(
+        preceded(whitespace0, operators),
+        map(
+            opt(preceded(
+                whitespace1,
+                alt((
+                    map(keyword("all"), |_| true),
+                    map(keyword("distinct"), |_| false),
+                )),
+            )),
+            |all| all.unwrap_or(false),
+        ),
+    )
+}
+
+type OperandParser<'a> = fn(RawSpan<'a>) -> ParserResult<'a, QueryExpression>;
+
+// parses `operand (operator operand)*` and folds the operands to the left, so
+// `a EXCEPT b UNION c` becomes `(a EXCEPT b) UNION c`
+fn left_associative<'a>(
+    input: RawSpan<'a>,
+    operand: OperandParser<'a>,
+    operators: impl FnMut(RawSpan<'a>) -> ParserResult<'a, SetOperator>,
+) -> ParserResult<'a, QueryExpression> {
+    let (rest, (first, others)) = pair(
+        operand,
+        many0(pair(
+            set_operator(operators),
+            preceded(whitespace0, cut(operand).context("Set Operation")),
+        )),
+    )(input)?;
+    let query = others.into_iter().fold(first, |left, ((op, all), right)| {
+        QueryExpression::SetOperation(Box::new(SetOperation {
+            span: left.span().union(right.span()),
+            left,
+            op,
+            all,
+            right,
+            order_by: Vec::new(),
+            limit: None,
+            offset: None,
+        }))
+    });
+    Ok((rest, query))
+}
+
+// INTERSECT binds tighter than UNION and EXCEPT
+fn parse_intersect(input: RawSpan<'_>) -> ParserResult<'_, QueryExpression> {
+    left_associative(
+        input,
+        operand,
+        map(keyword("intersect"), |_| SetOperator::Intersect),
+    )
+}
+
+fn parse_union(input: RawSpan<'_>) -> ParserResult<'_, QueryExpression> {
+    left_associative(
+        input,
+        parse_intersect,
+        alt((
+            map(keyword("union"), |_| SetOperator::Union),
+            map(keyword("except"), |_| SetOperator::Except),
+        )),
+    )
+}
+
+// `name [(column, ...)] AS (query)`
+fn common_table_expression(input: RawSpan<'_>) -> ParserResult<'_, CommonTableExpression> {
+    map(
+        tuple((
+            identifier.context("Table Name"),
+            opt(preceded(
+                whitespace0,
+                delimited(
+                    pair(char('('), whitespace0),
+                    separated_list1(
+                        tuple((whitespace0, char(','), whitespace0)),
+                        identifier.context("Column Name"),
+                    ),
+                    pair(whitespace0, char(')')),
+                ),
+            )),
+            preceded(
+                tuple((whitespace0, keyword("as"), whitespace0)),
+                cut(parse_subquery).context("With Query"),
+            ),
+        )),
+        |(name, columns, query)| CommonTableExpression {
+            name,
+            columns: columns.unwrap_or_default(),
+            query,
+        },
+    )(input)
+}
+
+// `WITH [RECURSIVE] name AS (query), ... query`
+fn parse_with(input: RawSpan<'_>) -> ParserResult<'_, QueryExpression> {
+    map(
+        spanned(tuple((
+            preceded(
+                pair(keyword("with"), whitespace1),
+                opt(terminated(keyword("recursive"), whitespace1)),
+            ),
+            cut(separated_list1(
+                tuple((whitespace0, char(','), whitespace0)),
+                common_table_expression,
+            ))
+            .context("With Clause"),
+            preceded(whitespace0, cut(QueryExpression::parse)),
+        ))),
+        |((recursive, tables, query), span)| {
+            QueryExpression::With(Box::new(WithQuery {
+                recursive: recursive.is_some(),
+                tables,
+                query,
+                span,
+            }))
+        },
+    )(input)
+}
+
+// a SELECT or a set operation, with the ORDER BY and pagination of all its rows
+fn parse_sorted_query(input: RawSpan<'_>) -> ParserResult<'_, QueryExpression> {
+    map(
+        spanned(|input| {
+            let (rest, mut query) = parse_union(input)?;
+            let (rest, (sorted_by, pagination)) =
+                pair(opt(parse_order_by), opt(parse_pagination))(rest)?;
+            if sorted_by.is_none() && pagination.is_none() {
+                return Ok((rest, query));
+            }
+            // a query in parentheses that is sorted or paginated already sorts and
+            // paginates its rows first
+            let (order_by, limit, offset) = query.sort_and_pagination();
+            if !order_by.is_empty() || limit.is_some() || offset.is_some() {
+                query = QueryExpression::Nested(Box::new(NestedQuery {
+                    span: query.span(),
+                    query,
+                    order_by: Vec::new(),
+                    limit: None,
+                    offset: None,
+                }));
+            }
+            let (order_by, limit, offset) = query.sort_and_pagination();
+            *order_by = sorted_by.unwrap_or_default();
+            (*limit, *offset) = pagination.unwrap_or_default();
+            Ok((rest, query))
+        }),
+        |(mut query, span)| {
+            match &mut query {
+                QueryExpression::Select(select) => select.span = span,
+                QueryExpression::SetOperation(operation) => operation.span = span,
+                QueryExpression::With(with) => with.span = span,
+                QueryExpression::Nested(nested) => nested.span = span,
+            }
+            query
+        },
+    )(input)
+}
+
+impl<'a> Parse<'a> for QueryExpression {
+    fn parse(input: RawSpan<'a>) -> ParserResult<'a, Self> {
+        alt((parse_with, parse_sorted_query))(input)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn select(raw: &str) -> QueryExpression {
+        SelectStatement::parse_from_raw(raw).unwrap().1.into()
+    }
+
+    fn operation(
+        left: QueryExpression,
+        op: SetOperator,
+        all: bool,
+        right: QueryExpression,
+    ) -> QueryExpression {
+        QueryExpression::SetOperation(Box::new(SetOperation {
+            left,
+            op,
+            all,
+            right,
+            order_by: Vec::new(),
+            limit: None,
+            offset: None,
+            span: Span::default(),
+        }))
+    }
+
+    #[test]
+    fn test_parse_single_select() {
+        let (_, query) =
+            QueryExpression::parse_from_raw("SELECT a FROM t ORDER BY a LIMIT 2").unwrap();
+
+        assert_eq!(query, select("SELECT a FROM t ORDER BY a LIMIT 2"));
+    }
+
+    #[test]
+    fn test_intersect_binds_tighter_than_union() {
+        let (_, query) = QueryExpression::parse_from_raw(
+            "SELECT a FROM t UNION ALL SELECT a FROM u INTERSECT SELECT a FROM v EXCEPT DISTINCT SELECT a FROM w",
+        )
+        .unwrap();
+
+        assert_eq!(
+            query,
+            operation(
+                operation(
+                    select("SELECT a FROM t"),
+                    SetOperator::Union,
+                    true,
+                    operation(
+                        select("SELECT a FROM u"),
+                        SetOperator::Intersect,
+                        false,
+                        select("SELECT a FROM v")
+                    )
+                ),
+                SetOperator::Except,
+                false,
+                select("SELECT a FROM w")
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_parenthesised_operands() {
+        let (_, query) = QueryExpression::parse_from_raw(
+            "(SELECT a FROM t UNION SELECT a FROM u) INTERSECT (SELECT a FROM v LIMIT 1)",
+        )
+        .unwrap();
+
+        assert_eq!(
+            query,
+            operation(
+                operation(
+                    select("SELECT a FROM t"),
+                    SetOperator::Union,
+                    false,
+                    select("SELECT a FROM u")
+                ),
+                SetOperator::Intersect,
+                false,
+                select("SELECT a FROM v LIMIT 1")
+            )
+        );
+    }
+
+    #[test]
+    fn test_order_by_and_limit_apply_to_the_whole_query() {
+        let (_, query) = QueryExpression::parse_from_raw(
+            "SELECT a FROM t WHERE a > 1 UNION SELECT b FROM u ORDER BY 1 DESC LIMIT 3 OFFSET 1",
+        )
+        .unwrap();
+
+        let QueryExpression::SetOperation(operation) = query else {
+            panic!("expected a set operation");
+        };
+        assert_eq!(operation.right, select("SELECT b FROM u"));
+        assert_eq!(operation.order_by.len(), 1);
+        assert_eq!((operation.limit, operation.offset), (Some(3), Some(1)));
+    }
+
+    #[test]
+    fn test_sorted_query_in_parentheses_is_sorted_again() {
+        let (_, query) =
+            QueryExpression::parse_from_raw("(SELECT a FROM t LIMIT 1) LIMIT 2").unwrap();
+
+        assert_eq!(
+            query,
+            QueryExpression::Nested(Box::new(NestedQuery {
+                query: select("SELECT a FROM t LIMIT 1"),
+                order_by: Vec::new(),
+                limit: Some(2),
+                offset: None,
+                span: Span::default(),
+            }))
+        );
+
+        let (_, query) = QueryExpression::parse_from_raw("(SELECT a FROM t) LIMIT 2").unwrap();
+
+        assert_eq!(query, select("SELECT a FROM t LIMIT 2"));
+    }
+
+    #[test]
+    fn test_sorted_operand_keeps_its_order_by() {
+        let (_, query) = QueryExpression::parse_from_raw(
+            "(SELECT a, b FROM t ORDER BY a LIMIT 3) UNION SELECT a, b FROM u ORDER BY b",
+        )
+        .unwrap();
+
+        let QueryExpression::SetOperation(operation) = query else {
+            panic!("expected a set operation");
+        };
+        assert_eq!(
+            operation.left,
+            select("SELECT a, b FROM t ORDER BY a LIMIT 3")
+        );
+        assert_eq!(operation.right, select("SELECT a, b FROM u"));
+        let (_, sorted) = SelectStatement::parse_from_raw("SELECT a FROM t ORDER BY b").unwrap();
+        assert_eq!(operation.order_by, sorted.order_by);
+    }
+
+    #[test]
+    fn test_parse_with() {
+        let (_, query) = QueryExpression::parse_from_raw(
+            "WITH RECURSIVE a (n) AS (SELECT n FROM t), b AS (SELECT n FROM a) SELECT n FROM b",
+        )
+        .unwrap();
+
+        assert_eq!(
+            query,
+            QueryExpression::With(Box::new(WithQuery {
+                recursive: true,
+                tables: vec![
+                    CommonTableExpression {
+                        name: "a".into(),
+                        columns: vec!["n".into()],
+                        query: select("SELECT n FROM t"),
+                    },
+                    CommonTableExpression {
+                        name: "b".into(),
+                        columns: Vec::new(),
+                        query: select("SELECT n FROM a"),
+                    },
+                ],
+                query: select("SELECT n FROM b"),
+                span: Span::default(),
+            }))
+        );
+    }
+
+    #[test]
+    fn test_with_without_query_is_an_error() {
+        assert!(matches!(
+            QueryExpression::parse_from_raw("WITH a AS SELECT n FROM t"),
+            Err(nom::Err::Failure(_))
+        ));
+        assert!(matches!(
+            QueryExpression::parse_from_raw("WITH a AS (SELECT n FROM t)"),
+            Err(nom::Err::Failure(_))
+        ));
+    }
+
+    #[test]
+    fn test_missing_operand_is_an_error() {
+        assert!(matches!(
+            QueryExpression::parse_from_raw("SELECT a FROM t UNION ORDER BY a"),
+            Err(nom::Err::Failure(_))
+        ));
+    }
+}
@@ -1,47 +1,336 @@
 use nom::{
+    branch::alt,
     bytes::complete::tag_no_case,
-    character::complete::{char, multispace0, multispace1},
-    combinator::map,
+    character::complete::{char, u64},
+    combinator::{cut, map, opt},
+    error::context,
     multi::separated_list1,
-    sequence::{preceded, tuple},
+    sequence::{delimited, pair, preceded, terminated, tuple},
 };
 use nom_supreme::ParserExt;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    common_parsers::identifier,
-    types::{Parse, ParserResult, RawSpan},
+    common_parsers::{alias, keyword, object_name, spanned, whitespace0, whitespace1},
+    expression::Expression,
+    from_clause::FromClause,
+    ident::{Ident, ObjectName},
+    types::{Parse, ParserResult, RawSpan, Span},
 };
 
-/// The table and its columns to select
+/// One item of the select list
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum SelectItem {
+    /// `*`, every column of the table
+    Wildcard,
+    /// `t.*`, every column of the table `t`
+    QualifiedWildcard(ObjectName),
+    /// An expression, named after its alias when it has one
+    Expression {
+        expr: Expression,
+        alias: Option<Ident>,
+    },
+}
+
+impl From<&str> for SelectItem {
+    fn from(column: &str) -> Self {
+        Self::Expression {
+            expr: Expression::Column(column.into()),
+            alias: None,
+        }
+    }
+}
+
+/// Which rows of a SELECT are left out as duplicates
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum Distinct {
+    /// `DISTINCT`, the rows equal to a row before them
+    Rows,
+    /// `DISTINCT ON (a, b)`, the rows whose values of the expressions are equal to
+    /// those of a row before them in the ORDER BY order
+    On(Vec<Expression>),
+}
+
+/// Which way an ORDER BY key sorts
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum OrderDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Where NULLs go relative to the other values of an ORDER BY key
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum NullsOrder {
+    First,
+    Last,
+}
+
+/// One key of an ORDER BY clause
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct OrderByExpr {
+    pub expr: Expression,
+    pub direction: OrderDirection,
+    /// None sorts NULLs as larger than any value: last ascending, first descending
+    pub nulls: Option<NullsOrder>,
+}
+
+/// The tables and the items to select from them, optionally filtered by a WHERE
+/// clause, grouped, sorted and paginated
 #[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
-pub(crate) struct SelectStatement {
-    table: String,
-    columns: Vec<String>,
+pub struct SelectStatement {
+    pub from: FromClause,
+    pub distinct: Option<Distinct>,
+    pub columns: Vec<SelectItem>,
+    pub where_clause: Option<Expression>,
+    pub group_by: Vec<Expression>,
+    /// Filters the groups, like WHERE filters the rows
+    pub having: Option<Expression>,
+    pub order_by: Vec<OrderByExpr>,
+    /// Most rows to return, from `LIMIT n` or `FETCH FIRST n ROWS ONLY`
+    pub limit: Option<u64>,
+    /// Rows to skip before returning any
+    pub offset: Option<u64>,
+    #[serde(skip)]
+    pub span: Span,
 }
 
-fn parse_table_name(input: RawSpan<'_>) -> ParserResult<'_, String> {
+fn parse_from(input: RawSpan<'_>) -> ParserResult<'_, FromClause> {
     preceded(
-        tuple((multispace1, tag_no_case("from"), multispace1)),
-        identifier.context("Table Name"),
+        tuple((whitespace1, tag_no_case("from"), whitespace1)),
+        FromClause::parse,
     )(input)
 }
 
-fn parse_column_names(input: RawSpan<'_>) -> ParserResult<'_, Vec<String>> {
+fn parse_select_item(input: RawSpan<'_>) -> ParserResult<'_, SelectItem> {
+    context(
+        "Select Item",
+        alt((
+            map(char('*'), |_| SelectItem::Wildcard),
+            map(
+                terminated(object_name, tuple((char('.'), char('*')))),
+                SelectItem::QualifiedWildcard,
+            ),
+            map(
+                pair(Expression::parse, opt(preceded(whitespace1, alias))),
+                |(expr, alias)| SelectItem::Expression { expr, alias },
+            ),
+        )),
+    )(input)
+}
+
+// `DISTINCT [ON (expr, ...)]`, or `ALL` which keeps every row like no keyword at all
+fn parse_distinct(input: RawSpan<'_>) -> ParserResult<'_, Option<Distinct>> {
+    let distinct_on = preceded(
+        tuple((whitespace1, keyword("on"), whitespace0)),
+        cut(delimited(
+            pair(char('('), whitespace0),
+            separated_list1(
+                tuple((whitespace0, char(','), whitespace0)),
+                Expression::parse,
+            ),
+            pair(whitespace0, char(')')),
+        ))
+        .context("Distinct On"),
+    );
+    alt((
+        map(preceded(keyword("distinct"), opt(distinct_on)), |on| {
+            Some(on.map_or(Distinct::Rows, Distinct::On))
+        }),
+        map(keyword("all"), |_| None),
+    ))(input)
+}
+
+fn parse_column_names(input: RawSpan<'_>) -> ParserResult<'_, (Option<Distinct>, Vec<SelectItem>)> {
     preceded(
-        tuple((tag_no_case("select"), multispace1)),
-        separated_list1(
-            tuple((char(','), multispace0)),
-            identifier.context("Column Name"),
+        tuple((tag_no_case("select"), whitespace1)),
+        pair(
+            map(
+                opt(terminated(parse_distinct, whitespace0)),
+                Option::flatten,
+            ),
+            separated_list1(
+                tuple((whitespace0, char(','), whitespace0)),
+                parse_select_item,
+            ),
         ),
     )(input)
 }
 
+pub(crate) fn parse_where_clause(input: RawSpan<'_>) -> ParserResult<'_, Expression> {
+    preceded(
+        tuple((whitespace1, tag_no_case("where"), whitespace1)),
+        // once WHERE is seen a broken predicate is an error, not a missing clause
+        cut(Expression::parse.context("Where Clause")),
+    )(input)
+}
+
+fn parse_group_by(input: RawSpan<'_>) -> ParserResult<'_, Vec<Expression>> {
+    preceded(
+        tuple((
+            whitespace1,
+            keyword("group"),
+            whitespace1,
+            keyword("by"),
+            whitespace1,
+        )),
+        cut(separated_list1(
+            tuple((whitespace0, char(','), whitespace0)),
+            Expression::parse,
+        ))
+        .context("Group By"),
+    )(input)
+}
+
+fn parse_having(input: RawSpan<'_>) -> ParserResult<'_, Expression> {
+    preceded(
+        tuple((whitespace1, keyword("having"), whitespace1)),
+        cut(Expression::parse.context("Having Clause")),
+    )(input)
+}
+
+pub(crate) fn parse_order_by_expr(input: RawSpan<'_>) -> ParserResult<'_, OrderByExpr> {
+    let direction = alt((
+        map(keyword("asc"), |_| OrderDirection::Asc),
+        map(keyword("desc"), |_| OrderDirection::Desc),
+    ));
+    let nulls = preceded(
+        pair(keyword("nulls"), whitespace1),
+        cut(alt((
+            map(keyword("first"), |_| NullsOrder::First),
+            map(keyword("last"), |_| NullsOrder::Last),
+        )))
+        .context("Nulls Order"),
+    );
+    map(
+        tuple((
+            Expression::parse,
+            opt(preceded(whitespace1, direction)),
+            opt(preceded(whitespace1, nulls)),
+        )),
+        |(expr, direction, nulls)| OrderByExpr {
+            expr,
+            direction: direction.unwrap_or_default(),
+            nulls,
+        },
+    )(input)
+}
+
+pub(crate) fn parse_order_by(input: RawSpan<'_>) -> ParserResult<'_, Vec<OrderByExpr>> {
+    preceded(
+        tuple((
+            whitespace1,
+            keyword("order"),
+            whitespace1,
+            keyword("by"),
+            whitespace1,
+        )),
+        cut(separated_list1(
+            tuple((whitespace0, char(','), whitespace0)),
+            parse_order_by_expr,
+        ))
+        .context("Order By"),
+    )(input)
+}
+
+fn parse_limit(input: RawSpan<'_>) -> ParserResult<'_, u64> {
+    preceded(
+        tuple((whitespace1, keyword("limit"), whitespace1)),
+        cut(u64).context("Limit"),
+    )(input)
+}
+
+fn row_or_rows(input: RawSpan<'_>) -> ParserResult<'_, RawSpan<'_>> {
+    alt((keyword("rows"), keyword("row")))(input)
+}
+
+// `OFFSET m [ROW | ROWS]`
+fn parse_offset(input: RawSpan<'_>) -> ParserResult<'_, u64> {
+    terminated(
+        preceded(
+            tuple((whitespace1, keyword("offset"), whitespace1)),
+            cut(u64).context("Offset"),
+        ),
+        opt(pair(whitespace1, row_or_rows)),
+    )(input)
+}
+
+// `FETCH {FIRST | NEXT} [n] {ROW | ROWS} ONLY`, the standard spelling of LIMIT
+fn parse_fetch(input: RawSpan<'_>) -> ParserResult<'_, u64> {
+    preceded(
+        tuple((
+            whitespace1,
+            keyword("fetch"),
+            whitespace1,
+            alt((keyword("first"), keyword("next"))),
+            whitespace1,
+        )),
+        cut(terminated(
+            map(opt(terminated(u64, whitespace1)), |count| {
+                count.unwrap_or(1)
+            }),
+            tuple((row_or_rows, whitespace1, keyword("only"))),
+        ))
+        .context("Fetch"),
+    )(input)
+}
+
+/// The limit and offset, which may come in either order
+pub(crate) fn parse_pagination(input: RawSpan<'_>) -> ParserResult<'_, (Option<u64>, Option<u64>)> {
+    alt((
+        map(pair(parse_limit, opt(parse_offset)), |(limit, offset)| {
+            (Some(limit), offset)
+        }),
+        map(
+            pair(parse_offset, opt(alt((parse_limit, parse_fetch)))),
+            |(offset, limit)| (limit, Some(offset)),
+        ),
+        map(parse_fetch, |limit| (Some(limit), None)),
+    ))(input)
+}
+
+/// A SELECT up to its ORDER BY, which in a set operation sorts the combined rows
+/// instead
+pub(crate) fn parse_select_core(input: RawSpan<'_>) -> ParserResult<'_, SelectStatement> {
+    map(
+        spanned(tuple((
+            parse_column_names,
+            parse_from,
+            opt(parse_where_clause),
+            opt(parse_group_by),
+            opt(parse_having),
+        ))),
+        |(((distinct, columns), from, where_clause, group_by, having), span)| SelectStatement {
+            from,
+            distinct,
+            columns,
+            where_clause,
+            group_by: group_by.unwrap_or_default(),
+            having,
+            span,
+            ..Default::default()
+        },
+    )(input)
+}
+
 impl<'a> Parse<'a> for SelectStatement {
     fn parse(input: RawSpan<'a>) -> ParserResult<'a, Self> {
         map(
-            tuple((parse_column_names, parse_table_name)),
-            |(columns, table)| Self { table, columns },
+            spanned(tuple((
+                parse_select_core,
+                opt(parse_order_by),
+                opt(parse_pagination),
+            ))),
+            |((select, order_by, pagination), span)| {
+                let (limit, offset) = pagination.unwrap_or_default();
+                Self {
+                    order_by: order_by.unwrap_or_default(),
+                    limit,
+                    offset,
+                    span,
+                    ..select
+                }
+            },
         )(input)
     }
 }
@@ -53,27 +342,108 @@ mod test {
     use super::*;
 
     #[test]
-    fn test_parse_table_name() {
-        let (_, parsed) = parse_table_name(LocatedSpan::new(" FROM PERSON")).unwrap();
+    fn test_parse_from() {
+        let (_, parsed) = parse_from(LocatedSpan::new(" FROM PERSON")).unwrap();
+
+        assert_eq!(parsed, "person".into());
+    }
+
+    #[test]
+    fn test_parse_from_ended_with_semicolon() {
+        let (_, parsed) = parse_from(LocatedSpan::new(" FROM PERSON;")).unwrap();
 
-        assert_eq!(parsed, "PERSON".to_string());
+        assert_eq!(parsed, "person".into());
     }
 
     #[test]
-    fn test_parse_table_name_ended_with_semicolon() {
-        let (_, parsed) = parse_table_name(LocatedSpan::new(" FROM PERSON;")).unwrap();
+    fn test_parse_from_with_aliases_followed_by_clauses() {
+        let (_, statement) = SelectStatement::parse_from_raw(
+            "SELECT p.name FROM Person p JOIN City c ON p.city = c.name ORDER BY p.name",
+        )
+        .unwrap();
 
-        assert_eq!(parsed, "PERSON".to_string());
+        let names: Vec<&str> = statement
+            .from
+            .tables()
+            .into_iter()
+            .map(|table| table.reference_name().as_str())
+            .collect();
+        assert_eq!(names, vec!["p", "c"]);
+        assert_eq!(statement.order_by.len(), 1);
     }
 
     #[test]
     fn test_parse_column_names() {
-        let (_, parsed) = parse_column_names(LocatedSpan::new(
+        let (_, (_, parsed)) = parse_column_names(LocatedSpan::new(
             "SELECT CustomerName, City FROM Customers;",
         ))
         .unwrap();
 
-        assert_eq!(parsed, vec!["CustomerName".to_string(), "City".to_string()]);
+        assert_eq!(parsed, vec!["customername".into(), "city".into()]);
+    }
+
+    #[test]
+    fn test_parse_wildcards() {
+        let (_, (_, parsed)) = parse_column_names(LocatedSpan::new(
+            "SELECT *, Person.*, public.Person.* FROM Person",
+        ))
+        .unwrap();
+
+        assert_eq!(
+            parsed,
+            vec![
+                SelectItem::Wildcard,
+                SelectItem::QualifiedWildcard("person".into()),
+                SelectItem::QualifiedWildcard(ObjectName(vec!["public".into(), "person".into()]))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_distinct() {
+        let distinct = |raw| SelectStatement::parse_from_raw(raw).unwrap().1.distinct;
+
+        assert_eq!(distinct("SELECT city FROM Person"), None);
+        assert_eq!(distinct("SELECT ALL city FROM Person"), None);
+        assert_eq!(
+            distinct("SELECT DISTINCT city, age FROM Person"),
+            Some(Distinct::Rows)
+        );
+        assert_eq!(
+            distinct("SELECT DISTINCT ON (city, age / 10) name FROM Person"),
+            Some(Distinct::On(vec![
+                Expression::parse_from_raw("city").unwrap().1,
+                Expression::parse_from_raw("age / 10").unwrap().1,
+            ]))
+        );
+        assert!(matches!(
+            SelectStatement::parse_from_raw("SELECT DISTINCT ON city FROM Person"),
+            Err(nom::Err::Failure(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_expression_items_with_aliases() {
+        let (_, statement) = SelectStatement::parse_from_raw(
+            "SELECT age + 1 AS older, Person.name who, city FROM Person",
+        )
+        .unwrap();
+
+        assert_eq!(
+            statement.columns,
+            vec![
+                SelectItem::Expression {
+                    expr: Expression::parse_from_raw("age + 1").unwrap().1,
+                    alias: Some("older".into()),
+                },
+                SelectItem::Expression {
+                    expr: Expression::parse_from_raw("Person.name").unwrap().1,
+                    alias: Some("who".into()),
+                },
+                "city".into(),
+            ]
+        );
+        assert_eq!(statement.from, "person".into());
     }
 
     #[test]
@@ -84,9 +454,136 @@ mod test {
         assert_eq!(
             statement,
             SelectStatement {
-                table: "Customers".to_string(),
-                columns: vec!["CustomerName".to_string(), "City".to_string()]
+                from: "customers".into(),
+                columns: vec!["customername".into(), "city".into()],
+                where_clause: None,
+                ..Default::default()
+            }
+        )
+    }
+
+    #[test]
+    fn test_parse_where_clause() {
+        let (_, parsed) = parse_where_clause(LocatedSpan::new(" WHERE age >= 18")).unwrap();
+
+        assert_eq!(parsed, Expression::parse_from_raw("age >= 18").unwrap().1);
+    }
+
+    #[test]
+    fn test_parse_select_statement_with_where_clause() {
+        let (_, statement) = SelectStatement::parse_from_raw(
+            "SELECT name FROM Person WHERE age > 18 AND city = 'Oslo';",
+        )
+        .unwrap();
+
+        assert_eq!(
+            statement,
+            SelectStatement {
+                from: "person".into(),
+                columns: vec!["name".into()],
+                where_clause: Some(
+                    Expression::parse_from_raw("age > 18 AND city = 'Oslo'")
+                        .unwrap()
+                        .1
+                ),
+                ..Default::default()
             }
         )
     }
+
+    #[test]
+    fn test_parse_group_by_and_having() {
+        let (_, statement) = SelectStatement::parse_from_raw(
+            "SELECT city, COUNT(*) FROM Person GROUP BY city, age / 10 HAVING COUNT(*) > 1 ORDER BY city",
+        )
+        .unwrap();
+
+        let expression = |raw| Expression::parse_from_raw(raw).unwrap().1;
+        assert_eq!(
+            statement.group_by,
+            vec![expression("city"), expression("age / 10")]
+        );
+        assert_eq!(statement.having, Some(expression("COUNT(*) > 1")));
+        assert_eq!(statement.order_by.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_order_by() {
+        let (_, statement) = SelectStatement::parse_from_raw(
+            "SELECT name FROM Person ORDER BY age DESC NULLS LAST, name, city asc",
+        )
+        .unwrap();
+
+        let column = |name: &str| Expression::Column(name.into());
+        assert_eq!(
+            statement.order_by,
+            vec![
+                OrderByExpr {
+                    expr: column("age"),
+                    direction: OrderDirection::Desc,
+                    nulls: Some(NullsOrder::Last),
+                },
+                OrderByExpr {
+                    expr: column("name"),
+                    direction: OrderDirection::Asc,
+                    nulls: None,
+                },
+                OrderByExpr {
+                    expr: column("city"),
+                    direction: OrderDirection::Asc,
+                    nulls: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_limit_and_offset_in_either_order() {
+        let pagination = |raw: &str| {
+            let (_, statement) = SelectStatement::parse_from_raw(raw).unwrap();
+            (statement.limit, statement.offset)
+        };
+
+        assert_eq!(pagination("SELECT a FROM t LIMIT 10"), (Some(10), None));
+        assert_eq!(
+            pagination("SELECT a FROM t LIMIT 10 OFFSET 5"),
+            (Some(10), Some(5))
+        );
+        assert_eq!(
+            pagination("SELECT a FROM t OFFSET 5 LIMIT 10"),
+            (Some(10), Some(5))
+        );
+        assert_eq!(pagination("SELECT a FROM t OFFSET 5 ROWS"), (None, Some(5)));
+    }
+
+    #[test]
+    fn test_parse_fetch_first() {
+        let pagination = |raw: &str| {
+            let (_, statement) = SelectStatement::parse_from_raw(raw).unwrap();
+            (statement.limit, statement.offset)
+        };
+
+        assert_eq!(
+            pagination("SELECT a FROM t ORDER BY a FETCH FIRST 3 ROWS ONLY"),
+            (Some(3), None)
+        );
+        assert_eq!(
+            pagination("SELECT a FROM t OFFSET 1 ROW FETCH NEXT ROW ONLY"),
+            (Some(1), Some(1))
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_limit_is_an_error() {
+        let result = SelectStatement::parse_from_raw("SELECT a FROM t LIMIT ten");
+
+        assert!(matches!(result, Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn test_parse_select_statement_with_invalid_where_clause() {
+        let result = SelectStatement::parse_from_raw("SELECT name FROM Person WHERE ;");
+
+        assert!(matches!(result, Err(nom::Err::Failure(_))));
+    }
 }
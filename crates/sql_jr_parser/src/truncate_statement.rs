@@ -0,0 +1,69 @@
+use nom::{
+    bytes::complete::tag_no_case,
+    combinator::{map, opt},
+    sequence::{preceded, tuple},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common_parsers::{if_exists, keyword, spanned, table_names, whitespace1},
+    ident::ObjectName,
+    types::{Parse, ParserResult, RawSpan, Span},
+};
+
+/// The tables to remove every row from, keeping the tables themselves
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct TruncateStatement {
+    pub tables: Vec<ObjectName>,
+    /// Tables that don't exist are skipped instead of failing the statement
+    pub if_exists: bool,
+    #[serde(skip)]
+    pub span: Span,
+}
+
+impl<'a> Parse<'a> for TruncateStatement {
+    fn parse(input: RawSpan<'a>) -> ParserResult<'a, Self> {
+        // the TABLE keyword is optional
+        let truncate_table = tuple((
+            tag_no_case("truncate"),
+            whitespace1,
+            opt(tuple((keyword("table"), whitespace1))),
+        ));
+        map(
+            spanned(preceded(truncate_table, tuple((if_exists, table_names)))),
+            |((if_exists, tables), span)| Self {
+                tables,
+                if_exists,
+                span,
+            },
+        )(input)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_truncate_statement() {
+        let (_, statement) =
+            TruncateStatement::parse_from_raw("TRUNCATE TABLE IF EXISTS Person, City;").unwrap();
+
+        assert_eq!(
+            statement,
+            TruncateStatement {
+                tables: vec!["person".into(), "city".into()],
+                if_exists: true,
+                span: Span::default(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_truncate_statement_without_table_keyword() {
+        let (_, statement) = TruncateStatement::parse_from_raw("truncate Person").unwrap();
+
+        assert_eq!(statement.tables, vec!["person".into()]);
+        assert!(!statement.if_exists);
+    }
+}
@@ -1,7 +1,13 @@
+use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+};
+
 use nom::Finish;
 use nom::{combinator::all_consuming, IResult};
 use nom_locate::LocatedSpan;
 use nom_supreme::error::ErrorTree;
+use serde::{Deserialize, Serialize};
 
 use crate::error::{format_parse_error, FormattedError};
 
@@ -14,6 +20,67 @@ pub type RawSpan<'a> = LocatedSpan<&'a str>;
 // this will use a default error type but we will change that latter
 pub type ParserResult<'a, T> = IResult<RawSpan<'a>, T, MyParseError<'a>>;
 
+/// Where in the parsed input a node of the AST came from, as a byte offset and length
+///
+/// Spans are only location metadata: they never make two nodes unequal, so a query
+/// compares equal to the same query written with different whitespace
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct Span {
+    pub offset: usize,
+    pub length: usize,
+}
+
+impl Span {
+    /// The smallest span covering both `self` and `other`
+    pub fn union(self, other: Span) -> Span {
+        let start = self.offset.min(other.offset);
+        let end = (self.offset + self.length).max(other.offset + other.length);
+        Span {
+            offset: start,
+            length: end - start,
+        }
+    }
+}
+
+impl PartialEq for Span {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for Span {}
+
+impl PartialOrd for Span {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Span {
+    fn cmp(&self, _other: &Self) -> Ordering {
+        Ordering::Equal
+    }
+}
+
+impl Hash for Span {
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+impl From<RawSpan<'_>> for Span {
+    fn from(span: RawSpan<'_>) -> Self {
+        Self {
+            offset: span.location_offset(),
+            length: span.fragment().len(),
+        }
+    }
+}
+
+impl From<Span> for miette::SourceSpan {
+    fn from(span: Span) -> Self {
+        Self::new(span.offset.into(), span.length.into())
+    }
+}
+
 pub trait Parse<'a>: Sized {
     fn parse(input: RawSpan<'a>) -> ParserResult<'a, Self>;
     fn parse_from_raw(input: &'a str) -> ParserResult<'a, Self> {
@@ -0,0 +1,149 @@
+use nom::{
+    bytes::complete::tag_no_case,
+    character::complete::char,
+    combinator::{map, opt},
+    error::context,
+    multi::separated_list1,
+    sequence::{preceded, separated_pair, tuple},
+};
+use nom_supreme::ParserExt;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common_parsers::{identifier, object_name, spanned, whitespace0, whitespace1},
+    expression::Expression,
+    ident::{Ident, ObjectName},
+    select_statement::parse_where_clause,
+    types::{Parse, ParserResult, RawSpan, Span},
+};
+
+/// A `column = expression` pair of an UPDATE's SET clause
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct Assignment {
+    pub column: Ident,
+    pub value: Expression,
+}
+
+/// The table to update, the new values of its columns and which rows to change
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct UpdateStatement {
+    pub table: ObjectName,
+    pub assignments: Vec<Assignment>,
+    pub where_clause: Option<Expression>,
+    #[serde(skip)]
+    pub span: Span,
+}
+
+fn parse_table_name(input: RawSpan<'_>) -> ParserResult<'_, ObjectName> {
+    preceded(
+        tuple((tag_no_case("update"), whitespace1)),
+        object_name.context("Table Name"),
+    )(input)
+}
+
+fn parse_assignment(input: RawSpan<'_>) -> ParserResult<'_, Assignment> {
+    context(
+        "Assignment",
+        map(
+            separated_pair(
+                identifier.context("Column Name"),
+                tuple((whitespace0, char('='), whitespace0)),
+                Expression::parse,
+            ),
+            |(column, value)| Assignment { column, value },
+        ),
+    )(input)
+}
+
+fn parse_assignments(input: RawSpan<'_>) -> ParserResult<'_, Vec<Assignment>> {
+    preceded(
+        tuple((whitespace1, tag_no_case("set"), whitespace1)),
+        separated_list1(
+            tuple((whitespace0, char(','), whitespace0)),
+            parse_assignment,
+        ),
+    )(input)
+}
+
+impl<'a> Parse<'a> for UpdateStatement {
+    fn parse(input: RawSpan<'a>) -> ParserResult<'a, Self> {
+        map(
+            spanned(tuple((
+                parse_table_name,
+                parse_assignments,
+                opt(parse_where_clause),
+            ))),
+            |((table, assignments, where_clause), span)| Self {
+                table,
+                assignments,
+                where_clause,
+                span,
+            },
+        )(input)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nom_locate::LocatedSpan;
+
+    use super::*;
+
+    fn expression(raw: &str) -> Expression {
+        Expression::parse_from_raw(raw).unwrap().1
+    }
+
+    #[test]
+    fn test_parse_assignments() {
+        let (_, parsed) =
+            parse_assignments(LocatedSpan::new(" SET age = age + 1,name='Jo'")).unwrap();
+
+        assert_eq!(
+            parsed,
+            vec![
+                Assignment {
+                    column: "age".into(),
+                    value: expression("age + 1"),
+                },
+                Assignment {
+                    column: "name".into(),
+                    value: expression("'Jo'"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_update_statement() {
+        let (_, statement) =
+            UpdateStatement::parse_from_raw("UPDATE Person SET age = 43 WHERE name = 'Jo';")
+                .unwrap();
+
+        assert_eq!(
+            statement,
+            UpdateStatement {
+                table: "person".into(),
+                assignments: vec![Assignment {
+                    column: "age".into(),
+                    value: expression("43"),
+                }],
+                where_clause: Some(expression("name = 'Jo'")),
+                span: Span::default(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_update_statement_without_where_clause() {
+        let (_, statement) =
+            UpdateStatement::parse_from_raw("update Person set age = NULL").unwrap();
+
+        assert_eq!(statement.where_clause, None);
+        assert_eq!(statement.assignments.len(), 1);
+    }
+
+    #[test]
+    fn test_update_without_assignments_is_an_error() {
+        assert!(UpdateStatement::parse_from_raw("UPDATE Person SET WHERE age = 1").is_err());
+    }
+}
@@ -0,0 +1,449 @@
+use std::fmt::{self, Display, Formatter};
+
+use nom::{
+    branch::alt,
+    character::complete::{char, u64},
+    combinator::{cut, map, opt, verify},
+    multi::separated_list1,
+    sequence::{delimited, pair, preceded, terminated, tuple},
+};
+use nom_supreme::ParserExt;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common_parsers::{keyword, spanned, whitespace0, whitespace1},
+    expression::{parse_aggregate, AggregateArg, AggregateFunction, Expression},
+    select_statement::{parse_order_by_expr, OrderByExpr},
+    types::{Parse, ParserResult, RawSpan},
+};
+
+/// The arguments of LAG and LEAD
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct OffsetArgs {
+    /// Evaluated on the row `offset` rows away
+    pub expr: Box<Expression>,
+    /// How many rows away, 1 when not given
+    pub offset: Option<Box<Expression>>,
+    /// The value when there is no row that far away, NULL when not given
+    pub default: Option<Box<Expression>>,
+}
+
+/// A function computing a value for each row from the rows of its window
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum WindowFunction {
+    /// The position of the row in its partition, from 1
+    RowNumber,
+    /// The position of the first row the row ties with, leaving gaps after ties
+    Rank,
+    /// Like RANK without the gaps: the number of distinct ORDER BY values so far
+    DenseRank,
+    /// The value of the row `offset` rows before in the partition
+    Lag(OffsetArgs),
+    /// The value of the row `offset` rows after in the partition
+    Lead(OffsetArgs),
+    /// The value of the first row of the frame
+    FirstValue(Box<Expression>),
+    /// An aggregate computed over the rows of the frame
+    Aggregate {
+        function: AggregateFunction,
+        distinct: bool,
+        arg: AggregateArg,
+    },
+}
+
+impl WindowFunction {
+    /// The expressions the function is computed from
+    pub fn args(&self) -> Vec<&Expression> {
+        match self {
+            Self::RowNumber | Self::Rank | Self::DenseRank => Vec::new(),
+            Self::Lag(args) | Self::Lead(args) => [Some(&args.expr), args.offset.as_ref()]
+                .into_iter()
+                .chain([args.default.as_ref()])
+                .flatten()
+                .map(|expr| &**expr)
+                .collect(),
+            Self::FirstValue(expr) => vec![expr],
+            Self::Aggregate { arg, .. } => match arg {
+                AggregateArg::Wildcard => Vec::new(),
+                AggregateArg::Expression(expr) => vec![expr],
+            },
+        }
+    }
+}
+
+impl Display for WindowFunction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RowNumber => write!(f, "ROW_NUMBER"),
+            Self::Rank => write!(f, "RANK"),
+            Self::DenseRank => write!(f, "DENSE_RANK"),
+            Self::Lag(_) => write!(f, "LAG"),
+            Self::Lead(_) => write!(f, "LEAD"),
+            Self::FirstValue(_) => write!(f, "FIRST_VALUE"),
+            Self::Aggregate { function, .. } => write!(f, "{function}"),
+        }
+    }
+}
+
+/// Whether the frame bounds count rows or ORDER BY values
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum FrameUnits {
+    /// `n PRECEDING` is the row n rows before
+    Rows,
+    /// `n PRECEDING` is the first row whose ORDER BY value is at most n before, and
+    /// CURRENT ROW takes in the rows tied with the row
+    Range,
+}
+
+/// One end of a window frame
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum FrameBound {
+    UnboundedPreceding,
+    Preceding(u64),
+    CurrentRow,
+    Following(u64),
+    UnboundedFollowing,
+}
+
+impl FrameBound {
+    // bounds further down the partition are ranked higher, the end of a frame can't
+    // be ranked lower than its start
+    fn rank(self) -> u8 {
+        match self {
+            Self::UnboundedPreceding => 0,
+            Self::Preceding(_) => 1,
+            Self::CurrentRow => 2,
+            Self::Following(_) => 3,
+            Self::UnboundedFollowing => 4,
+        }
+    }
+}
+
+/// `ROWS | RANGE BETWEEN start AND end`, the rows of the partition a row's window
+/// functions are computed over
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct WindowFrame {
+    pub units: FrameUnits,
+    pub start: FrameBound,
+    pub end: FrameBound,
+}
+
+impl Default for WindowFrame {
+    /// From the start of the partition to the last row tied with the row, the whole
+    /// partition when it isn't sorted
+    fn default() -> Self {
+        Self {
+            units: FrameUnits::Range,
+            start: FrameBound::UnboundedPreceding,
+            end: FrameBound::CurrentRow,
+        }
+    }
+}
+
+/// What follows OVER: how the rows are split into partitions, how each partition
+/// is sorted and which of its rows each row's frame holds
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct WindowSpec {
+    pub partition_by: Vec<Expression>,
+    pub order_by: Vec<OrderByExpr>,
+    /// The default frame when None
+    pub frame: Option<WindowFrame>,
+}
+
+impl WindowSpec {
+    /// The PARTITION BY and ORDER BY expressions
+    pub fn expressions(&self) -> impl Iterator<Item = &Expression> {
+        self.partition_by
+            .iter()
+            .chain(self.order_by.iter().map(|order_by| &order_by.expr))
+    }
+}
+
+fn comma(input: RawSpan<'_>) -> ParserResult<'_, char> {
+    delimited(whitespace0, char(','), whitespace0)(input)
+}
+
+// `PARTITION BY expr, ...`
+fn partition_by(input: RawSpan<'_>) -> ParserResult<'_, Vec<Expression>> {
+    preceded(
+        tuple((
+            keyword("partition"),
+            whitespace1,
+            keyword("by"),
+            whitespace1,
+        )),
+        cut(separated_list1(comma, Expression::parse)).context("Partition By"),
+    )(input)
+}
+
+fn order_by(input: RawSpan<'_>) -> ParserResult<'_, Vec<OrderByExpr>> {
+    preceded(
+        tuple((keyword("order"), whitespace1, keyword("by"), whitespace1)),
+        cut(separated_list1(comma, parse_order_by_expr)).context("Order By"),
+    )(input)
+}
+
+fn frame_bound(input: RawSpan<'_>) -> ParserResult<'_, FrameBound> {
+    let preceding = || preceded(whitespace1, keyword("preceding"));
+    let following = || preceded(whitespace1, keyword("following"));
+    alt((
+        preceded(
+            pair(keyword("unbounded"), whitespace1),
+            alt((
+                map(keyword("preceding"), |_| FrameBound::UnboundedPreceding),
+                map(keyword("following"), |_| FrameBound::UnboundedFollowing),
+            )),
+        ),
+        map(
+            tuple((keyword("current"), whitespace1, keyword("row"))),
+            |_| FrameBound::CurrentRow,
+        ),
+        map(terminated(u64, preceding()), FrameBound::Preceding),
+        map(terminated(u64, following()), FrameBound::Following),
+    ))(input)
+}
+
+// `ROWS | RANGE BETWEEN start AND end`, or just `ROWS | RANGE start` ending at the
+// current row. The frame can't end before it starts
+fn frame(input: RawSpan<'_>) -> ParserResult<'_, WindowFrame> {
+    let units = alt((
+        map(keyword("rows"), |_| FrameUnits::Rows),
+        map(keyword("range"), |_| FrameUnits::Range),
+    ));
+    let bounds = alt((
+        preceded(
+            pair(keyword("between"), whitespace1),
+            pair(
+                frame_bound,
+                preceded(
+                    tuple((whitespace1, keyword("and"), whitespace1)),
+                    frame_bound,
+                ),
+            ),
+        ),
+        map(frame_bound, |start| (start, FrameBound::CurrentRow)),
+    ));
+    map(
+        pair(
+            terminated(units, whitespace1),
+            cut(verify(bounds, |(start, end)| {
+                *start != FrameBound::UnboundedFollowing
+                    && *end != FrameBound::UnboundedPreceding
+                    && start.rank() <= end.rank()
+            }))
+            .context("Window Frame"),
+        ),
+        |(units, (start, end))| WindowFrame { units, start, end },
+    )(input)
+}
+
+// `OVER ([PARTITION BY ...] [ORDER BY ...] [frame])`
+fn parse_over(input: RawSpan<'_>) -> ParserResult<'_, WindowSpec> {
+    map(
+        preceded(
+            pair(keyword("over"), whitespace0),
+            cut(delimited(
+                pair(char('('), whitespace0),
+                tuple((
+                    opt(terminated(partition_by, whitespace0)),
+                    opt(terminated(order_by, whitespace0)),
+                    opt(terminated(frame, whitespace0)),
+                )),
+                char(')'),
+            ))
+            .context("Window"),
+        ),
+        |(partition_by, order_by, frame)| WindowSpec {
+            partition_by: partition_by.unwrap_or_default(),
+            order_by: order_by.unwrap_or_default(),
+            frame,
+        },
+    )(input)
+}
+
+// the arguments of LAG or LEAD: `expr [, offset [, default]]`
+fn offset_args(input: RawSpan<'_>) -> ParserResult<'_, OffsetArgs> {
+    map(
+        pair(
+            Expression::parse,
+            opt(pair(
+                preceded(comma, Expression::parse),
+                opt(preceded(comma, Expression::parse)),
+            )),
+        ),
+        |(expr, rest)| {
+            let (offset, default) = match rest {
+                Some((offset, default)) => (Some(Box::new(offset)), default.map(Box::new)),
+                None => (None, None),
+            };
+            OffsetArgs {
+                expr: Box::new(expr),
+                offset,
+                default,
+            }
+        },
+    )(input)
+}
+
+// `name(args)`, once the parenthesis is open this can only be a call
+fn call<'a, O>(
+    name: &'static str,
+    args: impl FnMut(RawSpan<'a>) -> ParserResult<'a, O>,
+) -> impl FnMut(RawSpan<'a>) -> ParserResult<'a, O> {
+    preceded(
+        pair(keyword(name), pair(whitespace0, char('('))),
+        cut(delimited(whitespace0, args, pair(whitespace0, char(')'))))
+            .context("Window Function Argument"),
+    )
+}
+
+fn no_args(input: RawSpan<'_>) -> ParserResult<'_, ()> {
+    Ok((input, ()))
+}
+
+// a call of a function that is only a window function
+fn window_function(input: RawSpan<'_>) -> ParserResult<'_, WindowFunction> {
+    alt((
+        map(call("row_number", no_args), |_| WindowFunction::RowNumber),
+        map(call("rank", no_args), |_| WindowFunction::Rank),
+        map(call("dense_rank", no_args), |_| WindowFunction::DenseRank),
+        map(call("lag", offset_args), WindowFunction::Lag),
+        map(call("lead", offset_args), WindowFunction::Lead),
+        map(call("first_value", Expression::parse), |expr| {
+            WindowFunction::FirstValue(Box::new(expr))
+        }),
+    ))(input)
+}
+
+/// A window function call, `RANK() OVER (...)` or an aggregate call followed by OVER
+pub(crate) fn parse_window_call(input: RawSpan<'_>) -> ParserResult<'_, Expression> {
+    let aggregate = map(parse_aggregate, |aggregate| match aggregate {
+        Expression::Aggregate {
+            function,
+            distinct,
+            arg,
+            ..
+        } => WindowFunction::Aggregate {
+            function,
+            distinct,
+            arg,
+        },
+        _ => unreachable!("only aggregate calls are parsed"),
+    });
+    map(
+        spanned(alt((
+            // a window function has to have a window
+            pair(window_function, cut(preceded(whitespace0, parse_over))),
+            pair(aggregate, preceded(whitespace0, parse_over)),
+        ))),
+        |((function, window), span)| Expression::Window {
+            function,
+            window,
+            span,
+        },
+    )(input)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{select_statement::OrderDirection, value::Value};
+
+    fn window(raw: &str) -> (WindowFunction, WindowSpec) {
+        match Expression::parse_from_raw(raw).unwrap().1 {
+            Expression::Window {
+                function, window, ..
+            } => (function, window),
+            other => panic!("expected a window function call, got {other:?}"),
+        }
+    }
+
+    fn column(name: &str) -> Expression {
+        Expression::Column(name.into())
+    }
+
+    #[test]
+    fn test_parse_window_functions() {
+        assert_eq!(
+            window("row_number ( ) over ( )"),
+            (WindowFunction::RowNumber, WindowSpec::default())
+        );
+        assert_eq!(
+            window("RANK() OVER (PARTITION BY city, country ORDER BY age DESC)"),
+            (
+                WindowFunction::Rank,
+                WindowSpec {
+                    partition_by: vec![column("city"), column("country")],
+                    order_by: vec![OrderByExpr {
+                        expr: column("age"),
+                        direction: OrderDirection::Desc,
+                        nulls: None,
+                    }],
+                    frame: None,
+                }
+            )
+        );
+        assert_eq!(
+            window("LAG(age, 2, 0) OVER (ORDER BY age)").0,
+            WindowFunction::Lag(OffsetArgs {
+                expr: Box::new(column("age")),
+                offset: Some(Box::new(Expression::Literal(Value::Int(2).into()))),
+                default: Some(Box::new(Expression::Literal(Value::Int(0).into()))),
+            })
+        );
+        assert_eq!(
+            window("SUM(DISTINCT age) OVER (PARTITION BY city)").0,
+            WindowFunction::Aggregate {
+                function: AggregateFunction::Sum,
+                distinct: true,
+                arg: AggregateArg::Expression(Box::new(column("age"))),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_window_frames() {
+        let frame = |raw| window(raw).1.frame.unwrap();
+
+        assert_eq!(
+            frame("COUNT(*) OVER (ORDER BY age ROWS BETWEEN 2 PRECEDING AND UNBOUNDED FOLLOWING)"),
+            WindowFrame {
+                units: FrameUnits::Rows,
+                start: FrameBound::Preceding(2),
+                end: FrameBound::UnboundedFollowing,
+            }
+        );
+        assert_eq!(
+            frame("FIRST_VALUE(age) OVER (RANGE 1 PRECEDING)"),
+            WindowFrame {
+                units: FrameUnits::Range,
+                start: FrameBound::Preceding(1),
+                end: FrameBound::CurrentRow,
+            }
+        );
+    }
+
+    #[test]
+    fn test_window_function_names_are_columns_without_parentheses() {
+        let (_, expression) = Expression::parse_from_raw("rank").unwrap();
+
+        assert_eq!(expression, column("rank"));
+    }
+
+    #[test]
+    fn test_invalid_windows_are_errors() {
+        for raw in [
+            "ROW_NUMBER()",
+            "RANK() OVER (ORDER BY)",
+            "LAG() OVER ()",
+            "SUM(age) OVER (ROWS BETWEEN CURRENT ROW AND 1 PRECEDING)",
+            "SUM(age) OVER (ROWS UNBOUNDED FOLLOWING)",
+            "SUM(age) OVER (RANGE 1 FOLLOWING)",
+        ] {
+            assert!(
+                matches!(Expression::parse_from_raw(raw), Err(nom::Err::Failure(_))),
+                "{raw}"
+            );
+        }
+    }
+}
@@ -1,31 +1,133 @@
-use miette::GraphicalReportHandler;
+use std::io::{self, IsTerminal, Read};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::Parser;
+use miette::{Diagnostic, GraphicalReportHandler, GraphicalTheme};
 use rustyline::error::ReadlineError;
 use rustyline::{DefaultEditor, Result};
-use sql_jr_parser::{query::SqlQuery, types::Parse};
+use sql_jr_execution::database::{Database, DEFAULT_RECURSION_LIMIT};
+use sql_jr_parser::{query::SqlScript, types::Parse};
+use table_formatter::format_result;
+
+mod table_formatter;
 
 const HISTORY_FILE: &str = "./history.txt";
 
-fn main() -> Result<()> {
+/// A toy SQL database. Without a file or command to run it reads statements
+/// from stdin when that is piped in, and starts an interactive prompt otherwise
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Args {
+    /// Store the tables in DIR so they are kept between runs, they are only kept
+    /// in memory otherwise
+    #[arg(short, long, value_name = "DIR")]
+    data_dir: Option<PathBuf>,
+
+    /// Run the statements in FILE and exit, can be given several times
+    #[arg(short, long, value_name = "FILE")]
+    file: Vec<PathBuf>,
+
+    /// Run COMMAND and exit, after any files. Can be given several times
+    #[arg(short, long)]
+    command: Vec<String>,
+
+    /// Fail a recursive query still returning rows after running N times
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_RECURSION_LIMIT)]
+    recursion_limit: usize,
+}
+
+fn print_report(report: &dyn Diagnostic) {
+    // only colour the report when a person is going to read it
+    let theme = if io::stderr().is_terminal() {
+        GraphicalTheme::default()
+    } else {
+        GraphicalTheme::unicode_nocolor()
+    };
+    let mut s = String::new();
+    GraphicalReportHandler::new_themed(theme)
+        .render_report(&mut s, report)
+        .unwrap();
+    eprintln!("{s}");
+}
+
+// runs every statement of `src` in order, stopping at the first one that fails.
+// Returns whether all of them succeeded
+fn run_script(database: &mut Database, src: &str) -> bool {
+    let script = match SqlScript::parse_format_error(src) {
+        Ok(script) => script,
+        Err(e) => {
+            print_report(&e);
+            return false;
+        }
+    };
+
+    for query in &script.queries {
+        match database.run(query, src) {
+            Ok(result) => println!("{}", format_result(&result)),
+            Err(e) => {
+                print_report(&e);
+                return false;
+            }
+        }
+    }
+    true
+}
+
+// runs the files and commands given on the command line, stopping at the first failure
+fn run_batch(database: &mut Database, args: &Args) -> ExitCode {
+    for path in &args.file {
+        let src = match std::fs::read_to_string(path) {
+            Ok(src) => src,
+            Err(e) => {
+                eprintln!("could not read {}: {e}", path.display());
+                return ExitCode::FAILURE;
+            }
+        };
+        if !run_script(database, &src) {
+            return ExitCode::FAILURE;
+        }
+    }
+    for command in &args.command {
+        if !run_script(database, command) {
+            return ExitCode::FAILURE;
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+fn run_stdin(database: &mut Database) -> ExitCode {
+    let mut src = String::new();
+    if let Err(e) = io::stdin().read_to_string(&mut src) {
+        eprintln!("could not read stdin: {e}");
+        return ExitCode::FAILURE;
+    }
+    match run_script(database, &src) {
+        true => ExitCode::SUCCESS,
+        false => ExitCode::FAILURE,
+    }
+}
+
+fn run_interactive(database: &mut Database) -> Result<()> {
     let mut rl = DefaultEditor::new()?;
 
     if rl.load_history(HISTORY_FILE).is_err() {
         println!("No previous history.");
     }
 
+    // statements can span several lines, they are run once a line ends with `;`
+    let mut buffer = String::new();
     loop {
-        let readline = rl.readline(">> ");
+        let prompt = if buffer.is_empty() { ">> " } else { "-> " };
+        let readline = rl.readline(prompt);
         match readline {
             Ok(line) => {
                 rl.add_history_entry(line.as_str())?;
-                match SqlQuery::parse_format_error(line.as_ref()) {
-                    Ok(q) => println!("{q:?}"),
-                    Err(e) => {
-                        let mut s = String::new();
-                        GraphicalReportHandler::new()
-                            .render_report(&mut s, &e)
-                            .unwrap();
-                        println!("{s}");
-                    }
+                buffer.push_str(&line);
+                buffer.push('\n');
+                if buffer.trim_end().ends_with(';') {
+                    run_script(database, &buffer);
+                    buffer.clear();
                 }
             }
             Err(ReadlineError::Interrupted) => {
@@ -42,6 +144,36 @@ fn main() -> Result<()> {
             }
         }
     }
-    rl.save_history("history.txt")?;
+    rl.save_history(HISTORY_FILE)?;
     Ok(())
 }
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let database = match &args.data_dir {
+        Some(data_dir) => Database::open(data_dir),
+        None => Ok(Database::new()),
+    };
+    let mut database = match database {
+        Ok(database) => database,
+        Err(e) => {
+            print_report(&e);
+            return ExitCode::FAILURE;
+        }
+    };
+    database.set_recursion_limit(args.recursion_limit);
+
+    if !args.file.is_empty() || !args.command.is_empty() {
+        return run_batch(&mut database, &args);
+    }
+    if !io::stdin().is_terminal() {
+        return run_stdin(&mut database);
+    }
+    match run_interactive(&mut database) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
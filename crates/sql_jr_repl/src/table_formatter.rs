@@ -0,0 +1,182 @@
+use sql_jr_execution::{
+    result::{ColumnInfo, QueryResult},
+    table::Row,
+};
+use sql_jr_parser::{create_statement::SqlTypeInfo, value::Value};
+
+/// Cells longer than this many characters are cut short
+const MAX_CELL_WIDTH: usize = 40;
+const TRUNCATION_MARKER: &str = "...";
+/// How NULL is shown, so it can't be mistaken for an empty string or the string 'NULL'
+const NULL_MARKER: &str = "(null)";
+
+/// Render the outcome of a query the way psql does
+pub fn format_result(result: &QueryResult) -> String {
+    match result {
+        QueryResult::Rows { columns, rows } => format_table(columns, rows),
+        QueryResult::Affected(1) => "1 row affected".to_string(),
+        QueryResult::Affected(count) => format!("{count} rows affected"),
+        QueryResult::Created => "CREATE TABLE".to_string(),
+        QueryResult::Dropped => "DROP TABLE".to_string(),
+        QueryResult::Truncated => "TRUNCATE TABLE".to_string(),
+        QueryResult::Altered => "ALTER TABLE".to_string(),
+    }
+}
+
+fn format_cell(value: &Value) -> String {
+    let text = match value {
+        Value::Null => return NULL_MARKER.to_string(),
+        value => value.to_string(),
+    };
+    if text.chars().count() <= MAX_CELL_WIDTH {
+        return text;
+    }
+    let kept = MAX_CELL_WIDTH - TRUNCATION_MARKER.len();
+    text.chars().take(kept).collect::<String>() + TRUNCATION_MARKER
+}
+
+fn pad(text: &str, width: usize, right_align: bool) -> String {
+    if right_align {
+        format!("{text:>width$}")
+    } else {
+        format!("{text:<width$}")
+    }
+}
+
+/// Render rows as an aligned table with a header, e.g.
+///
+/// ```text
+///  name | age
+/// ------+-----
+///  Jo   |  42
+/// (1 row)
+/// ```
+pub fn format_table(columns: &[ColumnInfo], rows: &[Row]) -> String {
+    let cells: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| row.iter().map(format_cell).collect())
+        .collect();
+
+    let widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            cells
+                .iter()
+                .map(|row| row[i].chars().count())
+                .chain(std::iter::once(column.name.chars().count()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let mut lines = Vec::with_capacity(rows.len() + 3);
+
+    // headers are centered like psql does
+    let header: Vec<String> = columns
+        .iter()
+        .zip(&widths)
+        .map(|(column, &width)| format!("{:^width$}", column.name))
+        .collect();
+    lines.push(format!(" {} ", header.join(" | ")).trim_end().to_string());
+
+    let rule: Vec<String> = widths.iter().map(|&width| "-".repeat(width + 2)).collect();
+    lines.push(rule.join("+"));
+
+    for row in &cells {
+        let row: Vec<String> = row
+            .iter()
+            .zip(columns.iter().zip(&widths))
+            .map(|(cell, (column, &width))| {
+                pad(cell, width, column.type_info == Some(SqlTypeInfo::Int))
+            })
+            .collect();
+        lines.push(format!(" {} ", row.join(" | ")).trim_end().to_string());
+    }
+
+    lines.push(match rows.len() {
+        1 => "(1 row)".to_string(),
+        count => format!("({count} rows)"),
+    });
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn columns() -> Vec<ColumnInfo> {
+        vec![
+            ColumnInfo {
+                name: "name".to_string(),
+                type_info: Some(SqlTypeInfo::String),
+            },
+            ColumnInfo {
+                name: "age".to_string(),
+                type_info: Some(SqlTypeInfo::Int),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_format_table() {
+        let rows = vec![
+            vec![Value::String("Jo".to_string()), Value::Int(42)],
+            vec![Value::String("Alexander".to_string()), Value::Int(7)],
+        ];
+
+        assert_eq!(
+            format_table(&columns(), &rows),
+            [
+                "   name    | age",
+                "-----------+-----",
+                " Jo        |  42",
+                " Alexander |   7",
+                "(2 rows)",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_format_table_shows_null_distinctly() {
+        let rows = vec![vec![Value::String("".to_string()), Value::Null]];
+
+        assert_eq!(
+            format_table(&columns(), &rows),
+            [
+                " name |  age",
+                "------+--------",
+                "      | (null)",
+                "(1 row)",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_format_table_truncates_long_strings() {
+        let long = "x".repeat(MAX_CELL_WIDTH + 10);
+        let rows = vec![vec![Value::String(long), Value::Int(1)]];
+
+        let formatted = format_table(&columns(), &rows);
+        let row = formatted.lines().nth(2).unwrap();
+
+        assert!(row.starts_with(&format!(" {}...", "x".repeat(MAX_CELL_WIDTH - 3))));
+        assert_eq!(row.chars().count(), MAX_CELL_WIDTH + 7);
+    }
+
+    #[test]
+    fn test_format_empty_table() {
+        assert_eq!(
+            format_table(&columns(), &[]),
+            [" name | age", "------+-----", "(0 rows)"].join("\n")
+        );
+    }
+
+    #[test]
+    fn test_format_affected_rows() {
+        assert_eq!(format_result(&QueryResult::Affected(1)), "1 row affected");
+        assert_eq!(format_result(&QueryResult::Affected(3)), "3 rows affected");
+    }
+}
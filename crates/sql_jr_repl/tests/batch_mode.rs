@@ -0,0 +1,148 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn sql_jr_repl(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_sql_jr_repl"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn test_command_runs_every_statement() {
+    let output = sql_jr_repl(
+        &[
+            "-c",
+            "CREATE TABLE Person (name string); INSERT INTO Person VALUES ('Jo'); SELECT name FROM Person;",
+        ],
+        "",
+    );
+
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        "CREATE TABLE\n1 row affected\n name\n------\n Jo\n(1 row)\n"
+    );
+}
+
+#[test]
+fn test_failing_statement_stops_with_an_error() {
+    let output = sql_jr_repl(
+        &[
+            "-c",
+            "SELECT name FROM Person; CREATE TABLE Person (name string);",
+        ],
+        "",
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert_eq!(stdout(&output), "");
+    assert!(stderr.contains("table person does not exist"));
+}
+
+#[test]
+fn test_failing_to_run_a_statement_labels_it() {
+    let output = sql_jr_repl(
+        &[
+            "-c",
+            "CREATE TABLE t (n int); INSERT INTO t VALUES (0); SELECT 1 / n FROM t;",
+        ],
+        "",
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("query failed"));
+    assert!(stderr.contains("SELECT 1 / n FROM t"));
+    assert!(stderr.contains("division by zero"));
+}
+
+#[test]
+fn test_file_runs_before_commands() {
+    let path = std::env::temp_dir().join(format!("sql_jr_schema_{}.sql", std::process::id()));
+    std::fs::write(&path, "-- schema\nCREATE TABLE Person (age int);\n").unwrap();
+
+    let output = sql_jr_repl(
+        &[
+            "-c",
+            "SELECT age FROM Person;",
+            "-f",
+            path.to_str().unwrap(),
+        ],
+        "",
+    );
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "CREATE TABLE\n age\n-----\n(0 rows)\n");
+}
+
+#[test]
+fn test_missing_file_is_an_error() {
+    let output = sql_jr_repl(&["-f", "/no/such/file.sql"], "");
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_statements_are_read_from_piped_stdin() {
+    let output = sql_jr_repl(
+        &[],
+        "CREATE TABLE Person (age int);\nSELECT age FROM Person;\n",
+    );
+
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "CREATE TABLE\n age\n-----\n(0 rows)\n");
+}
+
+#[test]
+fn test_tables_are_kept_in_the_data_dir() {
+    let dir = std::env::temp_dir().join(format!("sql_jr_data_{}", std::process::id()));
+    let data_dir = dir.to_str().unwrap();
+
+    let created = sql_jr_repl(
+        &[
+            "-d",
+            data_dir,
+            "-c",
+            "CREATE TABLE Person (age int); INSERT INTO Person VALUES (42);",
+        ],
+        "",
+    );
+    let selected = sql_jr_repl(&["-d", data_dir, "-c", "SELECT age FROM Person;"], "");
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(created.status.success());
+    assert_eq!(stdout(&selected), " age\n-----\n  42\n(1 row)\n");
+}
+
+#[test]
+fn test_recursion_limit() {
+    let query = "CREATE TABLE t (n int); INSERT INTO t VALUES (1); \
+        WITH RECURSIVE r(n) AS (SELECT n FROM t UNION ALL SELECT n + 1 FROM r WHERE n < 3) \
+        SELECT n FROM r;";
+    let limited = sql_jr_repl(&["--recursion-limit", "2", "-c", query], "");
+    let unlimited = sql_jr_repl(&["--recursion-limit", "3", "-c", query], "");
+
+    assert!(!limited.status.success());
+    assert!(unlimited.status.success());
+    assert_eq!(
+        stdout(&unlimited),
+        "CREATE TABLE\n1 row affected\n n\n---\n 1\n 2\n 3\n(3 rows)\n"
+    );
+}